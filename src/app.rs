@@ -1,8 +1,12 @@
-use crate::audio::AudioSystem;
+use crate::audio::{AudioFeatures, AudioSupervisor, SupervisorEvent, LUFS_FLOOR};
 use crate::capability::probe_runtime;
 use crate::config::{Config, EngineMode, Quality, RendererMode, SwitchMode, SystemDataMode};
-use crate::control_matrix::{ControlMatrix, ControlState};
+use crate::control_matrix::{preset_param_control, ControlMatrix, ControlState};
+use crate::idle::{dim_rgba, idle_features, IdleMode};
 use crate::lyrics::LyricsTrack;
+use crate::preset_file::PresetDefinition;
+use crate::preset_graph::{CompiledPresetGraph, PresetGraphError};
+use crate::preset_id::{preset_slug, resolve_refs, PresetRef};
 use crate::prefs::{self, AppPrefs};
 use crate::render::{AsciiRenderer, BrailleRenderer, Frame, HalfBlockRenderer, KittyRenderer, Renderer, SextantRenderer};
 use crate::system_data::SystemDataFeed;
@@ -12,7 +16,10 @@ use crate::typography::{
     apply_typography_overlay_pixels, typography_overlay_text, typography_reactive_audio,
     TypographyMode,
 };
-use crate::visual::{make_presets, CameraPathMode, PresetEngine, RenderCtx, VisualEngine};
+use crate::visual::{
+    apply_stereo_split, check_definition, make_presets_with, CameraPathMode, Level,
+    PresetCategory, PresetEngine, PresetMeta, RenderCtx, VisualEngine, BUILTIN_PRESET_IDS,
+};
 use anyhow::Context;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 use std::collections::HashSet;
@@ -111,6 +118,7 @@ struct GraphOption {
     label: String,
     preset_indices: Vec<usize>,
     entry_preset: Option<usize>,
+    graph: Option<CompiledPresetGraph>,
 }
 
 #[derive(Clone, Debug)]
@@ -220,6 +228,7 @@ impl LatencyCalibration {
         if offset_ms > 0.5 {
             if let Some(prev) = self.prev_audio {
                 let horizon = (offset_ms / 1000.0).min(0.20);
+                shift_tempo_phase(&mut out, horizon);
                 let inv_dt = 1.0 / dt.max(1e-3);
                 let onset_slope = (audio.onset - prev.onset) * inv_dt;
                 let beat_slope = (audio.beat_strength - prev.beat_strength) * inv_dt;
@@ -233,9 +242,13 @@ impl LatencyCalibration {
             }
         } else if offset_ms < -0.5 {
             let lag_s = (-offset_ms / 1000.0).min(0.25);
+            shift_tempo_phase(&mut out, -lag_s);
             let decay = (0.1f32).powf((lag_s / 0.12).clamp(0.0, 2.0));
             out.onset = (out.onset * decay).clamp(0.0, 1.0);
             out.beat_strength = (out.beat_strength * decay).clamp(0.0, 1.0);
+            out.kick *= decay;
+            out.snare *= decay;
+            out.hat *= decay;
             if lag_s > 0.08 {
                 out.beat = false;
             }
@@ -266,6 +279,8 @@ struct AppState {
 #[derive(Default)]
 struct ControlRuntime {
     state: ControlState,
+    /// Set while the `next_preset` route sits high, so one rise fires one switch.
+    next_preset_held: bool,
 }
 
 pub fn run(cfg: Config) -> anyhow::Result<()> {
@@ -323,12 +338,22 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
         RendererMode::Sextant => (2usize, 3usize),
     };
 
-    let audio = AudioSystem::new(cfg.source, cfg.device.as_deref())
+    let mut audio = AudioSupervisor::new(&cfg)
         .with_context(|| format!("start audio (source={:?})", cfg.source))?;
-    let audio_features = audio.features();
+    let mut audio_features = audio.features();
+    let mut audio_snapshot = audio.snapshot();
+    let mut snapshot = crate::audio::AudioSnapshot::default();
 
-    let presets = make_presets();
+    // Presets, the playback context and graph walkers all draw from (or fork)
+    // this thread's global RNG, so seeding it here fixes every random choice.
+    if let Some(seed) = cfg.seed {
+        fastrand::seed(seed);
+    }
+    let user_presets = load_user_presets(cfg.preset_dir.as_deref(), &mut startup_warnings);
+    let presets = make_presets_with(&user_presets);
     let preset_names = presets.iter().map(|p| p.name()).collect::<Vec<_>>();
+    let preset_metas = presets.iter().map(|p| p.meta()).collect::<Vec<_>>();
+    let preset_ids = presets.iter().map(|p| p.id()).collect::<Vec<_>>();
     let preset_count = preset_names.len();
     let mut requested_active = select_preset(&cfg.preset, &presets);
 
@@ -336,40 +361,31 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
     let mut zoom_drive = 1.0f32;
     let mut loaded_theme_name = String::new();
     let mut loaded_graph_name = String::new();
+    let mut startup_graph: Option<CompiledPresetGraph> = None;
     let mut default_playlist_name: Option<String> = None;
     let mut default_playlist_indices: Option<Vec<usize>> = None;
     let mut control_matrix: Option<ControlMatrix> = None;
     let mut control_runtime = ControlRuntime::default();
+    let mut idle = IdleMode::new(cfg.idle_dim, cfg.idle_attract);
 
     if let Some(path) = cfg.preset_graph.as_deref() {
-        match crate::preset_graph::PresetGraph::load(path).and_then(|g| g.compile()) {
+        match crate::preset_graph::PresetGraph::load(path).and_then(|g| g.compile_with(&preset_ids)) {
             Ok(graph) => {
                 let mut indices = Vec::new();
                 for node in &graph.nodes {
-                    if node.preset_index < preset_count && !indices.contains(&node.preset_index) {
+                    if !indices.contains(&node.preset_index) {
                         indices.push(node.preset_index);
                     }
                 }
-                if indices.is_empty() {
-                    push_warning(
-                        &mut startup_warnings,
-                        format!(
-                            "preset graph '{}' parsed but contains no in-range preset indices",
-                            path
-                        ),
-                    );
-                } else {
-                    default_playlist_name = Some("Graph Defaults".to_string());
-                    default_playlist_indices = Some(indices);
-                    loaded_graph_name = format!("{} nodes", graph.nodes.len());
-                    if cfg.preset.is_none() {
-                        if let Some(entry) = graph.nodes.get(graph.entry) {
-                            if entry.preset_index < preset_count {
-                                requested_active = Some(entry.preset_index);
-                            }
-                        }
+                default_playlist_name = Some("Graph Defaults".to_string());
+                default_playlist_indices = Some(indices);
+                loaded_graph_name = format!("{} nodes", graph.nodes.len());
+                if cfg.preset.is_none() {
+                    if let Some(entry) = graph.nodes.get(graph.entry) {
+                        requested_active = Some(entry.preset_index);
                     }
                 }
+                startup_graph = Some(graph);
             }
             Err(err) => {
                 push_warning(
@@ -383,21 +399,18 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
     if let Some(path) = cfg.theme_pack.as_deref() {
         match ThemePackManifest::load(path) {
             Ok(pack) => {
-                let mut indices = pack
-                    .preset_indices
-                    .iter()
-                    .copied()
-                    .filter(|&idx| idx < preset_count)
-                    .collect::<Vec<_>>();
+                let (mut indices, unresolved) = pack.resolve_presets(&preset_ids);
                 indices.sort_unstable();
-                indices.dedup();
+                if !unresolved.is_empty() {
+                    push_warning(
+                        &mut startup_warnings,
+                        unresolved_presets_warning("theme pack", path, &unresolved),
+                    );
+                }
                 if indices.is_empty() {
                     push_warning(
                         &mut startup_warnings,
-                        format!(
-                            "theme pack '{}' parsed but contains no in-range preset indices",
-                            path
-                        ),
+                        format!("theme pack '{}' parsed but none of its presets resolve", path),
                     );
                 } else {
                     loaded_theme_name = pack.name.clone();
@@ -424,41 +437,23 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
         }
     }
 
-    if let Some(path) = cfg.control_matrix.as_deref() {
-        match ControlMatrix::load(path) {
-            Ok(matrix) => {
-                let unsupported = matrix
-                    .routes()
-                    .iter()
-                    .filter(|route| !supports_control_name(route.control.as_str()))
-                    .count();
-                if unsupported > 0 {
-                    push_warning(
-                        &mut startup_warnings,
-                        format!(
-                            "control matrix '{}' has {} unsupported route(s); unsupported controls are ignored",
-                            path, unsupported
-                        ),
-                    );
-                }
-                control_matrix = Some(matrix);
-            }
-            Err(err) => {
-                push_warning(
-                    &mut startup_warnings,
-                    format!("failed to load control matrix '{}': {err}", path),
-                );
-            }
-        }
-    }
-
     let (theme_options, mut theme_selected) =
-        discover_theme_options(cfg.theme_pack.as_deref(), preset_count);
+        discover_theme_options(
+            cfg.theme_pack.as_deref(),
+            &preset_ids,
+            &preset_metas,
+            &mut startup_warnings,
+        );
     if loaded_theme_name.is_empty() {
         theme_selected = 0;
     }
     let (graph_options, mut graph_selected) =
-        discover_graph_options(cfg.preset_graph.as_deref(), preset_count);
+        discover_graph_options(
+            cfg.preset_graph.as_deref(),
+            &preset_ids,
+            &preset_metas,
+            &mut startup_warnings,
+        );
     if loaded_graph_name.is_empty() {
         graph_selected = 0;
     }
@@ -481,7 +476,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
             #[cfg(target_os = "macos")]
             {
                 match crate::visual::MetalEngine::new(
-                    preset_names.clone(),
+                    presets,
                     active,
                     cfg.shuffle,
                     cfg.switch,
@@ -499,7 +494,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                             format!("metal engine unavailable ({err}); using cpu engine"),
                         );
                         Box::new(PresetEngine::new(
-                            make_presets(),
+                            make_presets_with(&user_presets),
                             active,
                             cfg.shuffle,
                             cfg.switch,
@@ -521,7 +516,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                     "metal engine unsupported on this platform; using cpu engine".to_string(),
                 );
                 Box::new(PresetEngine::new(
-                    make_presets(),
+                    make_presets_with(&user_presets),
                     active,
                     cfg.shuffle,
                     cfg.switch,
@@ -533,8 +528,42 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
     };
     engine.set_fractal_zoom_drive(zoom_drive);
 
-    let playlist_store = playlist_storage_path();
-    let mut playlists = load_playlists(playlist_store.as_deref(), preset_names.len());
+    // `preset.*` routes only count if the chosen engine can apply them (Metal
+    // can't tune shader-backed presets).
+    let preset_param_names = engine.tunable_preset_params();
+    if let Some(path) = cfg.control_matrix.as_deref() {
+        match ControlMatrix::load(path) {
+            Ok(matrix) => {
+                let unsupported = matrix
+                    .routes()
+                    .iter()
+                    .filter(|route| !supports_control_name(&route.control, &preset_param_names))
+                    .count();
+                if unsupported > 0 {
+                    push_warning(
+                        &mut startup_warnings,
+                        format!(
+                            "control matrix '{}' has {} unsupported route(s); unsupported controls are ignored",
+                            path, unsupported
+                        ),
+                    );
+                }
+                control_matrix = Some(matrix);
+            }
+            Err(err) => {
+                push_warning(
+                    &mut startup_warnings,
+                    format!("failed to load control matrix '{}': {err}", path),
+                );
+            }
+        }
+    }
+
+    let playlist_store = PlaylistStore {
+        path: playlist_storage_path(),
+        preset_ids: preset_ids.clone(),
+    };
+    let mut playlists = load_playlists(&playlist_store, &mut startup_warnings);
     let mut active_playlist = 0usize;
     if let Some(indices) = default_playlist_indices {
         if !indices.is_empty() {
@@ -548,6 +577,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
     let playlist_ui = PlaylistUi::new();
     let selector_ui = SelectorUi::new();
     engine.set_playlist_indices(&playlists[active_playlist].preset_indices);
+    engine.set_preset_graph(startup_graph);
 
     let mut last_size = crossterm::terminal::size().context("get terminal size")?;
     if last_size.1 < 2 || last_size.0 < 4 {
@@ -566,6 +596,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
 
     let start = Instant::now();
     let mut last_frame = start;
+    let mut last_reconnect_failure: Option<Instant> = None;
 
     let mut fps = FpsCounter::new();
     let mut beat_pulse = 0.0f32;
@@ -577,7 +608,8 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
         LatencyCalibration::new(cfg.latency_calibration, cfg.latency_offset_ms);
     let typography_mode = TypographyMode::Off;
     let typography_last_non_off = TypographyMode::WordPulse;
-    let mut typography_pixels = Vec::<u8>::new();
+    let mut overlay_pixels = Vec::<u8>::new();
+    let mut stereo_gains = Vec::<f32>::new();
     let mut lyric_track = if let Some(path) = cfg.lyrics_file.as_deref() {
         match LyricsTrack::load(path) {
             Ok(track) => Some(track),
@@ -718,7 +750,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                             &mut playlists,
                             &mut active_playlist,
                             preset_names.len(),
-                            playlist_store.as_deref(),
+                            &playlist_store,
                         )
                     } else if state.selector_ui.open {
                         match handle_selector_key(
@@ -828,9 +860,50 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
         last_frame = now;
         let t = now.duration_since(start).as_secs_f32();
 
-        let raw_audio = audio_features.load();
+        match audio.poll(now) {
+            Some(SupervisorEvent::Lost(reason)) => push_warning(
+                &mut startup_warnings,
+                format!("audio lost ({reason}); reconnecting [#{}]", audio.outages()),
+            ),
+            Some(SupervisorEvent::Reconnected(device)) => {
+                audio_features = audio.features();
+                audio_snapshot = audio.snapshot();
+                push_warning(
+                    &mut startup_warnings,
+                    format!("audio reconnected on {device} [#{}]", audio.outages()),
+                );
+                last_reconnect_failure = None;
+            }
+            Some(SupervisorEvent::ReconnectFailed(err)) => {
+                if last_reconnect_failure
+                    .is_none_or(|t| now.duration_since(t) >= RECONNECT_FAILURE_WARNING_INTERVAL)
+                {
+                    last_reconnect_failure = Some(now);
+                    push_warning(
+                        &mut startup_warnings,
+                        format!("audio reconnect failed ({err}) [#{}]", audio.outages()),
+                    );
+                }
+            }
+            None => {}
+        }
+        // While the stream is down, treat it as silence so idle mode takes over.
+        let raw_audio = if audio.is_down() {
+            AudioFeatures {
+                silent: true,
+                ..AudioFeatures::default()
+            }
+        } else {
+            audio_features.load()
+        };
+        audio_snapshot.load_into(&mut snapshot);
         let audio_age_ms = audio_features.age_ms();
-        let corrected_audio = state.latency_calibration.apply_phase_correction(raw_audio, dt);
+        let idle_now = cfg.idle && idle.update(now, raw_audio.silent);
+        let corrected_audio = if idle_now {
+            idle_features(t)
+        } else {
+            state.latency_calibration.apply_phase_correction(raw_audio, dt)
+        };
 
         if let Some(matrix) = control_matrix.as_ref() {
             let controls = matrix.evaluate(&corrected_audio, &mut control_runtime.state);
@@ -840,6 +913,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                 &mut state.intensity,
                 &mut state.zoom_drive,
                 &mut state.typography_mode,
+                &mut control_runtime.next_preset_held,
             );
             if state.typography_mode != TypographyMode::Off {
                 state.typography_last_non_off = state.typography_mode;
@@ -852,7 +926,11 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
         // Exponential decay; tuned for hypnotic "breathing" rather than a hard flash.
         beat_pulse *= (0.1f32).powf(dt);
 
-        engine.update_auto_switch(now, &corrected_audio);
+        if !idle_now {
+            engine.update_auto_switch(now, &corrected_audio);
+        } else if idle.attract_due(now) && engine.auto_switch() {
+            engine.next_preset();
+        }
 
         let (term_cols, term_rows) = last_size;
         let preset_name = engine.preset_name().to_string();
@@ -874,7 +952,10 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
         let zoom_mode = format!("{:?}", engine.fractal_zoom_mode());
         let zoom_enabled = engine.fractal_zoom_enabled();
         let fractal_bias = engine.fractal_bias();
-        let scene_section = engine.scene_section_name();
+        let scene_section = match engine.structure_phase_name() {
+            Some(phase) => format!("{} ({phase})", engine.scene_section_name()),
+            None => engine.scene_section_name().to_string(),
+        };
         let camera_mode = engine.camera_path_mode_name();
         let camera_speed = engine.camera_path_speed();
         let renderer_name = renderer.name();
@@ -901,9 +982,11 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
             loaded_theme_name.as_str()
         };
         let graph_label = if loaded_graph_name.is_empty() {
-            "none"
+            "none".to_string()
+        } else if let Some(node) = engine.graph_node_name() {
+            format!("{} @ {}", loaded_graph_name, node)
         } else {
-            loaded_graph_name.as_str()
+            loaded_graph_name.clone()
         };
         let system_data_label = state.system_data_feed
             .as_ref()
@@ -937,7 +1020,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                     zoom_enabled,
                     fractal_bias,
                     state.stage_mode,
-                    scene_section,
+                    &scene_section,
                     camera_mode,
                     camera_speed,
                     state.typography_mode.label(),
@@ -945,7 +1028,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                     &latency_status,
                     &probe_status,
                     theme_label,
-                    graph_label,
+                    &graph_label,
                     &lyrics_label,
                     system_data_label,
                     warning_status,
@@ -957,7 +1040,9 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                 last_engine_ms,
                 last_render_ms,
                 last_total_ms,
-                &source_label,
+                &if idle_now { format!("{source_label} (idle)") } else { source_label.clone() },
+                &agc_label(cfg.analyzer.agc, raw_audio.gain),
+                &loudness_label(&raw_audio),
                 &engine_label,
                 renderer_name,
             )
@@ -992,31 +1077,47 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
             beat_pulse: (beat_pulse * state.intensity).clamp(0.0, 1.0),
             fractal_zoom_mul: 1.0,
             safe: cfg.safe,
+            stereo: cfg.stereo_split,
             quality: runtime.quality,
             scale: runtime.scale,
+            spectrum: &snapshot.spectrum,
+            waveform: &snapshot.waveform,
+            side: &snapshot.side,
         };
 
         let engine_start = Instant::now();
         let pixels = engine.render(ctx, runtime.quality, runtime.scale);
-        let pixels_rgba = if state.typography_mode == TypographyMode::Off {
+        let brightness = idle.brightness(now);
+        let pixels_rgba = if state.typography_mode == TypographyMode::Off
+            && !cfg.stereo_split
+            && brightness >= 1.0
+        {
             pixels
         } else {
-            if typography_pixels.len() != pixels.len() {
-                typography_pixels.resize(pixels.len(), 0);
+            if overlay_pixels.len() != pixels.len() {
+                overlay_pixels.resize(pixels.len(), 0);
             }
-            typography_pixels.copy_from_slice(pixels);
-            apply_typography_overlay_pixels(
-                state.typography_mode,
-                &mut typography_pixels,
-                w,
-                h,
-                &audio,
-                beat_pulse,
-                t,
-                lyric_line,
-                system_token,
-            );
-            typography_pixels.as_slice()
+            overlay_pixels.copy_from_slice(pixels);
+            if brightness < 1.0 {
+                dim_rgba(&mut overlay_pixels, brightness);
+            }
+            if cfg.stereo_split {
+                apply_stereo_split(&mut overlay_pixels, w, h, &audio, &mut stereo_gains);
+            }
+            if state.typography_mode != TypographyMode::Off {
+                apply_typography_overlay_pixels(
+                    state.typography_mode,
+                    &mut overlay_pixels,
+                    w,
+                    h,
+                    &audio,
+                    beat_pulse,
+                    t,
+                    lyric_line,
+                    system_token,
+                );
+            }
+            overlay_pixels.as_slice()
         };
         let engine_ms = engine_start.elapsed().as_secs_f32() * 1000.0;
         last_engine_ms = engine_ms;
@@ -1029,6 +1130,7 @@ pub fn run(cfg: Config) -> anyhow::Result<()> {
                 active_playlist,
                 &state.playlist_ui,
                 &preset_names,
+                &preset_metas,
             ))
         } else {
             None
@@ -1130,9 +1232,11 @@ fn select_preset(preset: &Option<String>, presets: &[Box<dyn crate::visual::Pres
         return (i < presets.len()).then_some(i);
     }
     let p_l = p.to_lowercase();
-    presets
-        .iter()
-        .position(|x| x.name().to_lowercase().contains(&p_l))
+    presets.iter().position(|x| x.id() == p_l).or_else(|| {
+        presets
+            .iter()
+            .position(|x| x.name().to_lowercase().contains(&p_l))
+    })
 }
 
 impl AppState {
@@ -1511,7 +1615,7 @@ fn handle_playlist_key(
     playlists: &mut Vec<Playlist>,
     active_playlist: &mut usize,
     preset_count: usize,
-    playlist_store: Option<&Path>,
+    playlist_store: &PlaylistStore,
 ) -> bool {
     if mods.contains(KeyModifiers::CONTROL) && matches!(code, KeyCode::Char('c')) {
         return true;
@@ -1698,6 +1802,7 @@ fn build_playlist_popup(
     active_playlist: usize,
     ui: &PlaylistUi,
     preset_names: &[&'static str],
+    preset_metas: &[PresetMeta],
 ) -> String {
     let cols = term_cols as usize;
     let rows = term_rows as usize;
@@ -1765,8 +1870,10 @@ fn build_playlist_popup(
                 .map(|pl| pl.preset_indices.contains(&idx))
                 .unwrap_or(false);
             let check = if in_pl { 'x' } else { ' ' };
-            let nm = truncate_for_width(name, right_w.saturating_sub(5));
-            format!("{cursor}[{check}] {nm}")
+            let tag = preset_metas.get(idx).map(preset_meta_tag).unwrap_or_default();
+            let name_w = right_w.saturating_sub(6 + tag.len());
+            let nm = truncate_for_width(name, name_w);
+            format!("{cursor}[{check}] {nm:<name_w$} {tag}")
         } else {
             String::new()
         };
@@ -1903,7 +2010,42 @@ fn typography_mode_description(mode: TypographyMode) -> &'static str {
     }
 }
 
-fn discover_theme_options(explicit_path: Option<&str>, preset_count: usize) -> (Vec<ThemeOption>, usize) {
+/// Short category/energy tag for a preset row; `!` marks a high flash risk.
+fn preset_meta_tag(meta: &PresetMeta) -> String {
+    let flash = if meta.flash_risk == Level::High { " !" } else { "" };
+    format!("{:<9} {:<6}{flash}", meta.category.label(), meta.energy.label())
+}
+
+/// Mix summary for a selector entry: the most common category among the
+/// presets and their average energy level.
+fn preset_mix_summary(indices: &[usize], preset_metas: &[PresetMeta]) -> String {
+    let metas = indices.iter().filter_map(|&i| preset_metas.get(i)).collect::<Vec<_>>();
+    if metas.is_empty() {
+        return "empty".to_string();
+    }
+    let dominant = PresetCategory::ALL
+        .into_iter()
+        .max_by_key(|c| metas.iter().filter(|m| m.category == *c).count())
+        .unwrap_or(PresetCategory::Geometric);
+    let energy_sum = metas.iter().map(|m| m.energy as usize).sum::<usize>();
+    let energy = match (energy_sum * 2 + metas.len()) / (metas.len() * 2) {
+        0 => Level::Low,
+        1 => Level::Medium,
+        _ => Level::High,
+    };
+    format!("mostly {}, {} energy", dominant.label(), energy.label())
+}
+
+/// Lists selectable theme packs. Packs whose preset references no longer all
+/// resolve are still offered with what remains, with a warning; the explicit
+/// `--theme-pack` file was already reported at startup.
+fn discover_theme_options(
+    explicit_path: Option<&str>,
+    preset_ids: &[String],
+    preset_metas: &[PresetMeta],
+    warnings: &mut Vec<String>,
+) -> (Vec<ThemeOption>, usize) {
+    let preset_count = preset_ids.len();
     let mut options = vec![ThemeOption {
         label: format!("none (all presets, {} entries)", preset_count),
         pack: None,
@@ -1924,24 +2066,30 @@ fn discover_theme_options(explicit_path: Option<&str>, preset_count: usize) -> (
         let Ok(pack) = ThemePackManifest::load(&path) else {
             continue;
         };
-        let mut indices = pack
-            .preset_indices
-            .iter()
-            .copied()
-            .filter(|&idx| idx < preset_count)
-            .collect::<Vec<_>>();
+        let explicit = explicit_key.as_ref().is_some_and(|k| k == &key);
+        let (mut indices, unresolved) = pack.resolve_presets(preset_ids);
         indices.sort_unstable();
-        indices.dedup();
+        if !unresolved.is_empty() && !explicit {
+            push_warning(
+                warnings,
+                unresolved_presets_warning("theme pack", path.display(), &unresolved),
+            );
+        }
         if indices.is_empty() {
             continue;
         }
 
-        if explicit_key.as_ref().is_some_and(|k| k == &key) {
+        if explicit {
             selected = options.len();
         }
 
         options.push(ThemeOption {
-            label: format!("{} [{} presets]", pack.name, indices.len()),
+            label: format!(
+                "{} [{} presets, {}]",
+                pack.name,
+                indices.len(),
+                preset_mix_summary(&indices, preset_metas)
+            ),
             pack: Some(pack),
             preset_indices: indices,
         });
@@ -1951,11 +2099,17 @@ fn discover_theme_options(explicit_path: Option<&str>, preset_count: usize) -> (
     (options, selected)
 }
 
-fn discover_graph_options(explicit_path: Option<&str>, preset_count: usize) -> (Vec<GraphOption>, usize) {
+fn discover_graph_options(
+    explicit_path: Option<&str>,
+    preset_ids: &[String],
+    preset_metas: &[PresetMeta],
+    warnings: &mut Vec<String>,
+) -> (Vec<GraphOption>, usize) {
     let mut options = vec![GraphOption {
         label: "none (disabled)".to_string(),
         preset_indices: Vec::new(),
         entry_preset: None,
+        graph: None,
     }];
     let mut selected = 0usize;
     let explicit_key = explicit_path
@@ -1969,27 +2123,28 @@ fn discover_graph_options(explicit_path: Option<&str>, preset_count: usize) -> (
             continue;
         }
 
-        let Ok(graph) = crate::preset_graph::PresetGraph::load(&path).and_then(|g| g.compile()) else {
-            continue;
+        let explicit = explicit_key.as_ref().is_some_and(|k| k == &key);
+        let graph = match crate::preset_graph::PresetGraph::load(&path).and_then(|g| g.compile_with(preset_ids)) {
+            Ok(graph) => graph,
+            Err(err @ PresetGraphError::UnresolvedPreset { .. }) => {
+                if !explicit {
+                    push_warning(warnings, format!("preset graph '{}' skipped: {err}", path.display()));
+                }
+                continue;
+            }
+            Err(_) => continue,
         };
 
         let mut indices = Vec::new();
         for node in &graph.nodes {
-            if node.preset_index < preset_count && !indices.contains(&node.preset_index) {
+            if !indices.contains(&node.preset_index) {
                 indices.push(node.preset_index);
             }
         }
-        if indices.is_empty() {
-            continue;
-        }
 
-        let entry_preset = graph
-            .nodes
-            .get(graph.entry)
-            .map(|n| n.preset_index)
-            .filter(|&idx| idx < preset_count);
+        let entry_preset = graph.nodes.get(graph.entry).map(|n| n.preset_index);
 
-        if explicit_key.as_ref().is_some_and(|k| k == &key) {
+        if explicit {
             selected = options.len();
         }
 
@@ -1999,9 +2154,16 @@ fn discover_graph_options(explicit_path: Option<&str>, preset_count: usize) -> (
             .unwrap_or("graph")
             .to_string();
         options.push(GraphOption {
-            label: format!("{} [{} nodes / {} presets]", stem, graph.nodes.len(), indices.len()),
+            label: format!(
+                "{} [{} nodes / {} presets, {}]",
+                stem,
+                graph.nodes.len(),
+                indices.len(),
+                preset_mix_summary(&indices, preset_metas)
+            ),
             preset_indices: indices,
             entry_preset,
+            graph: Some(graph),
         });
     }
 
@@ -2063,6 +2225,65 @@ fn discover_lyrics_options(explicit_path: Option<&str>) -> (Vec<LyricOption>, us
     (options, selected)
 }
 
+/// Reads `.preset` definitions from the bundled, user-config and `--preset-dir`
+/// directories. Files that fail to parse or resolve, and ids already taken by
+/// a built-in or earlier file, are skipped with a warning.
+fn load_user_presets(explicit_dir: Option<&str>, warnings: &mut Vec<String>) -> Vec<PresetDefinition> {
+    let mut candidates = Vec::<PathBuf>::new();
+    collect_files_with_ext(Path::new("assets/preset"), "preset", &mut candidates);
+    if let Some(cfg_dir) = app_config_dir() {
+        collect_files_with_ext(&cfg_dir.join("presets"), "preset", &mut candidates);
+    }
+    if let Some(dir) = explicit_dir.filter(|d| !d.trim().is_empty()) {
+        if !Path::new(dir).is_dir() {
+            push_warning(warnings, format!("preset dir '{dir}' is not a directory"));
+        }
+        collect_files_with_ext(Path::new(dir), "preset", &mut candidates);
+    }
+
+    let mut ids = BUILTIN_PRESET_IDS
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    let mut defs = Vec::new();
+    for path in candidates {
+        if !seen.insert(path_identity(path.as_path())) {
+            continue;
+        }
+        let def = match PresetDefinition::load(&path) {
+            Ok(def) => def,
+            Err(err) => {
+                push_warning(warnings, format!("preset file '{}' skipped: {err}", path.display()));
+                continue;
+            }
+        };
+        if let Err(err) = check_definition(&def) {
+            push_warning(warnings, format!("preset file '{}' skipped: {err}", path.display()));
+            continue;
+        }
+        let id = preset_slug(&def.name);
+        if id.is_empty() || !ids.insert(id.clone()) {
+            push_warning(
+                warnings,
+                format!("preset file '{}' skipped: id '{id}' already in use", path.display()),
+            );
+            continue;
+        }
+        defs.push(def);
+    }
+    defs
+}
+
+fn unresolved_presets_warning(kind: &str, path: impl std::fmt::Display, unresolved: &[PresetRef]) -> String {
+    let refs = unresolved.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+    format!(
+        "{kind} '{path}': {} preset reference(s) no longer resolve ({})",
+        refs.len(),
+        refs.join(", ")
+    )
+}
+
 fn candidate_config_files(
     explicit_path: Option<&str>,
     bundled_dir: &Path,
@@ -2213,6 +2434,7 @@ fn apply_graph_option(
             engine.set_playlist_indices(&[entry]);
         }
         engine.set_playlist_indices(&indices);
+        engine.set_preset_graph(option.graph.clone());
         *loaded_graph_name = option.label.clone();
     } else {
         remove_runtime_playlists(playlists, active_playlist, "[Graph] ");
@@ -2222,6 +2444,7 @@ fn apply_graph_option(
         if let Some(active) = playlists.get(*active_playlist) {
             engine.set_playlist_indices(&active.preset_indices);
         }
+        engine.set_preset_graph(None);
         loaded_graph_name.clear();
     }
 }
@@ -2324,9 +2547,17 @@ fn playlist_storage_path() -> Option<PathBuf> {
     )
 }
 
-fn load_playlists(path: Option<&Path>, preset_count: usize) -> Vec<Playlist> {
-    let mut playlists = default_playlists(preset_count);
-    let Some(path) = path else {
+/// Saved playlist location and the preset ids their entries are written as.
+struct PlaylistStore {
+    path: Option<PathBuf>,
+    preset_ids: Vec<String>,
+}
+
+/// Reads saved playlists. Entries are preset ids, or positions in files saved
+/// before ids existed; entries that no longer resolve are dropped with a warning.
+fn load_playlists(store: &PlaylistStore, warnings: &mut Vec<String>) -> Vec<Playlist> {
+    let mut playlists = default_playlists(store.preset_ids.len());
+    let Some(path) = store.path.as_deref() else {
         return playlists;
     };
     let Ok(raw) = fs::read_to_string(path) else {
@@ -2342,19 +2573,18 @@ fn load_playlists(path: Option<&Path>, preset_count: usize) -> Vec<Playlist> {
             continue;
         };
 
-        let mut indices = idx_raw
-            .split(',')
-            .filter_map(|x| x.trim().parse::<usize>().ok())
-            .filter(|&i| i < preset_count)
-            .collect::<Vec<_>>();
-        indices.sort_unstable();
-        indices.dedup();
-        if indices.is_empty() {
+        let name = name_raw.trim();
+        if name.is_empty() || name.eq_ignore_ascii_case("all presets") {
             continue;
         }
 
-        let name = name_raw.trim();
-        if name.is_empty() || name.eq_ignore_ascii_case("all presets") {
+        let refs = idx_raw.split(',').filter_map(PresetRef::parse).collect::<Vec<_>>();
+        let (mut indices, unresolved) = resolve_refs(&refs, &store.preset_ids);
+        if !unresolved.is_empty() {
+            push_warning(warnings, unresolved_presets_warning("playlist", name, &unresolved));
+        }
+        indices.sort_unstable();
+        if indices.is_empty() {
             continue;
         }
         playlists.push(Playlist {
@@ -2366,8 +2596,8 @@ fn load_playlists(path: Option<&Path>, preset_count: usize) -> Vec<Playlist> {
     playlists
 }
 
-fn save_playlists(playlists: &[Playlist], path: Option<&Path>) {
-    let Some(path) = path else {
+fn save_playlists(playlists: &[Playlist], store: &PlaylistStore) {
+    let Some(path) = store.path.as_deref() else {
         return;
     };
     let Some(parent) = path.parent() else {
@@ -2377,19 +2607,19 @@ fn save_playlists(playlists: &[Playlist], path: Option<&Path>) {
         return;
     }
 
-    let mut content = String::from("# tui_visualizer playlists v1\n");
+    let mut content = String::from("# tui_visualizer playlists v2\n");
     for pl in playlists {
         let mut name = pl.name.replace(['\n', '\r', '\t'], " ");
         if name.trim().is_empty() {
             name = "Playlist".to_string();
         }
-        let indices = pl
+        let ids = pl
             .preset_indices
             .iter()
-            .map(|i| i.to_string())
+            .filter_map(|&i| store.preset_ids.get(i).map(String::as_str))
             .collect::<Vec<_>>()
             .join(",");
-        let _ = writeln!(&mut content, "{}\t{}", name, indices);
+        let _ = writeln!(&mut content, "{}\t{}", name, ids);
     }
     let tmp = path.with_extension("tmp");
     if fs::write(&tmp, content).is_ok() {
@@ -2455,6 +2685,8 @@ fn build_wrapped_hud(
     render_ms: f32,
     total_ms: f32,
     source_label: &str,
+    agc_label: &str,
+    loudness_label: &str,
     engine_label: &str,
     renderer_name: &str,
 ) -> String {
@@ -2485,8 +2717,8 @@ fn build_wrapped_hud(
             lat_now, lat_avg, lat_p95, latency_mode, typography_text
         ),
         format!(
-            "ms(E/R/T): {:>4.1}/{:>4.1}/{:>4.1} | Source: {} | Engine: {} | Renderer: {} | Probe: {}",
-            engine_ms, render_ms, total_ms, source_label, engine_label, renderer_name, probe_status
+            "ms(E/R/T): {:>4.1}/{:>4.1}/{:>4.1} | Source: {} | AGC: {} | Loud: {} | Engine: {} | Renderer: {} | Probe: {}",
+            engine_ms, render_ms, total_ms, source_label, agc_label, loudness_label, engine_label, renderer_name, probe_status
         ),
         format!(
             "Theme: {} | Graph: {} | Lyrics: {} | SysData: {} | Warning: {} | Stage: {} | Help: {} | FPS: {:>4.1}",
@@ -2505,6 +2737,31 @@ fn build_wrapped_hud(
     wrap_hud_lines(cols, &logical_lines).join("\n")
 }
 
+/// HUD text for the analyzer's automatic gain, in dB relative to the fixed curve.
+fn agc_label(enabled: bool, gain: f32) -> String {
+    if !enabled {
+        return "off".to_string();
+    }
+    format!("{:+.0}dB", 20.0 * gain.max(1e-6).log10())
+}
+
+/// HUD loudness meter: an 8-cell bar over -40..-8 LUFS momentary, then the
+/// momentary/short-term readings and the crest factor.
+fn loudness_label(audio: &AudioFeatures) -> String {
+    if audio.lufs_momentary <= LUFS_FLOOR {
+        return "--------  -inf LUFS".to_string();
+    }
+    let cells = (audio.loudness() * 8.0).round() as usize;
+    format!(
+        "{}{} M{:>5.1} S{:>5.1} LUFS CF {:.0}dB",
+        "#".repeat(cells),
+        "-".repeat(8 - cells),
+        audio.lufs_momentary,
+        audio.lufs_short_term,
+        audio.crest_db
+    )
+}
+
 fn wrap_hud_lines(cols: usize, lines: &[String]) -> Vec<String> {
     let width = cols.max(1);
     let mut out = Vec::new();
@@ -2690,6 +2947,9 @@ q or esc  quit",
     )
 }
 
+/// Repeated reconnect failures add at most one HUD warning per interval.
+const RECONNECT_FAILURE_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
 fn push_warning(warnings: &mut Vec<String>, message: impl Into<String>) {
     let message = message.into();
     if warnings.iter().any(|w| w == &message) {
@@ -2705,7 +2965,11 @@ fn latest_warning(warnings: &[String]) -> &str {
     warnings.last().map(|s| s.as_str()).unwrap_or("none")
 }
 
-fn supports_control_name(name: &str) -> bool {
+/// Global controls, plus `preset.<param>` for any parameter the engine can tune.
+fn supports_control_name(name: &str, preset_params: &HashSet<&'static str>) -> bool {
+    if let Some(param) = preset_param_control(name) {
+        return preset_params.contains(param);
+    }
     matches!(
         name,
         "intensity"
@@ -2716,6 +2980,7 @@ fn supports_control_name(name: &str) -> bool {
             | "fractal_bias"
             | "zoom_enabled"
             | "typography_mode"
+            | "next_preset"
     )
 }
 
@@ -2725,6 +2990,7 @@ fn apply_control_routes(
     intensity: &mut f32,
     zoom_drive: &mut f32,
     typography_mode: &mut TypographyMode,
+    next_preset_held: &mut bool,
 ) {
     if let Some(v) = controls.get("intensity").copied() {
         *intensity = v.clamp(0.10, 2.5);
@@ -2772,6 +3038,22 @@ fn apply_control_routes(
     if let Some(v) = controls.get("typography_mode").copied() {
         *typography_mode = TypographyMode::from_unit_interval(v);
     }
+
+    if let Some(v) = controls.get("next_preset").copied() {
+        if v >= 0.66 && !*next_preset_held {
+            *next_preset_held = true;
+            engine.next_preset();
+        } else if v <= 0.33 {
+            *next_preset_held = false;
+        }
+    }
+
+    // Presets without the parameter ignore the route.
+    for (control, &v) in controls {
+        if let Some(param) = preset_param_control(control) {
+            engine.set_preset_param(param, v);
+        }
+    }
 }
 
 fn set_engine_camera_mode(engine: &mut dyn VisualEngine, target_idx: usize) {
@@ -2950,6 +3232,16 @@ fn quality_rank(q: Quality) -> u8 {
     }
 }
 
+/// Moves the tracked beat/bar phase by `seconds` at the current tempo.
+fn shift_tempo_phase(a: &mut crate::audio::AudioFeatures, seconds: f32) {
+    if a.bpm <= 0.0 {
+        return;
+    }
+    let beats = a.bpm / 60.0 * seconds;
+    a.beat_phase = (a.beat_phase + beats).rem_euclid(1.0);
+    a.bar_phase = (a.bar_phase + beats * 0.25).rem_euclid(1.0);
+}
+
 fn apply_intensity(mut a: crate::audio::AudioFeatures, intensity: f32) -> crate::audio::AudioFeatures {
     let s = intensity.clamp(0.0, 8.0);
     if (s - 1.0).abs() < 1e-3 {
@@ -2961,6 +3253,11 @@ fn apply_intensity(mut a: crate::audio::AudioFeatures, intensity: f32) -> crate:
     }
     a.onset = (a.onset * s).clamp(0.0, 1.0);
     a.beat_strength = (a.beat_strength * s).clamp(0.0, 1.0);
+    a.kick = (a.kick * s).clamp(0.0, 1.0);
+    a.snare = (a.snare * s).clamp(0.0, 1.0);
+    a.hat = (a.hat * s).clamp(0.0, 1.0);
+    a.rms_left = (a.rms_left * s).clamp(0.0, 1.0);
+    a.rms_right = (a.rms_right * s).clamp(0.0, 1.0);
     a
 }
 
@@ -1,57 +1,226 @@
-use crate::config::AudioSource;
-use anyhow::{anyhow, Context};
+use crate::config::{
+    AnalyzerArgs, AudioSource, BandSpacing, CaptureArgs, ChannelSelection, Config, PcmFormat,
+    SynthPattern, WindowFunction,
+};
+use crate::recording::{FeatureRecorder, FeatureRecording};
+use crate::synth::{synth_snapshot, PatternSynth};
+use anyhow::{anyhow, bail, Context};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Sample, SampleFormat};
+use cpal::{
+    BufferSize, Sample, SampleFormat, SampleRate, SupportedBufferSize, SupportedStreamConfig,
+    SupportedStreamConfigRange,
+};
 use ringbuf::HeapRb;
 use ringbuf::traits::{Consumer as _, Producer as _, Split as _};
 use rustfft::num_complex::Complex;
 use rustfft::FftPlanner;
+use std::collections::VecDeque;
 use std::f32::consts::PI;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[cfg(target_os = "macos")]
 use screencapturekit::prelude::*;
 #[cfg(target_os = "macos")]
 use screencapturekit::cm::AudioBufferList;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct AudioFeatures {
     pub rms: f32,
-    pub bands: [f32; 8],
+    /// Band energies (0..1) in the configured layout; entries past `band_count` are 0.
+    /// Use `band8`/`bands8` for the classic 8-band meaning under any layout.
+    pub bands: [f32; MAX_BANDS],
+    /// Number of configured bands in use (1..=`MAX_BANDS`).
+    pub band_count: usize,
     pub onset: f32,
     pub beat: bool,
     pub beat_strength: f32,
     pub centroid: f32,
     pub flatness: f32,
+    /// Tracked tempo in beats per minute; 0 while the tracker has no lock.
+    pub bpm: f32,
+    /// Position inside the current beat (0..1), 0 at the beat.
+    pub beat_phase: f32,
+    /// Position inside the current 4/4 bar (0..1), 0 at the downbeat.
+    pub bar_phase: f32,
+    /// Low-band (kick) hit envelope (0..1): jumps on a detected hit, then decays.
+    pub kick: f32,
+    /// Mid-band (snare/clap) hit envelope (0..1).
+    pub snare: f32,
+    /// High-band (hi-hat/cymbal) hit envelope (0..1).
+    pub hat: f32,
+    /// Left-channel RMS (0..1); equals `rms_right` for mono sources.
+    pub rms_left: f32,
+    /// Right-channel RMS (0..1).
+    pub rms_right: f32,
+    /// Level balance (-1 hard left .. 0 centre .. 1 hard right).
+    pub balance: f32,
+    /// Side/mid ratio (0..1): 0 for mono, about 0.5 for uncorrelated or hard-panned
+    /// material, 1 when the channels are out of phase.
+    pub stereo_width: f32,
+    /// Current automatic gain on RMS relative to the fixed curve; 1.0 when AGC is off.
+    pub gain: f32,
+    /// Energy per pitch class (C, C#, .. B), scaled so the strongest class is 1.
+    pub chroma: [f32; 12],
+    /// Dominant pitch in Hz; 0 when no clear pitch stands out.
+    pub pitch_hz: f32,
+    /// Estimated key tonic as a pitch class (0 = C .. 11 = B).
+    pub key: u8,
+    pub key_minor: bool,
+    /// Key profile correlation (0..1); 0 until a key has been locked.
+    pub key_confidence: f32,
+    /// Key change envelope (0..1): jumps when the key estimate changes, then decays.
+    pub key_change: f32,
+    /// EBU R128 momentary loudness (400 ms, K-weighted) in LUFS; `LUFS_FLOOR` for silence.
+    /// Measured before AGC, so it tracks the input level rather than the gain-riding.
+    pub lufs_momentary: f32,
+    /// EBU R128 short-term loudness (3 s) in LUFS.
+    pub lufs_short_term: f32,
+    /// Peak-to-RMS ratio over the short-term window in dB: about 3 for a sine or a
+    /// brickwalled master, 15+ for sparse, punchy material; 0 for silence.
+    pub crest_db: f32,
+    /// Input has stayed below the silence threshold for the configured hold time.
+    pub silent: bool,
+}
+
+impl AudioFeatures {
+    /// Copies `values` into a full-size band array (extra entries are dropped).
+    pub fn pad_bands(values: &[f32]) -> [f32; MAX_BANDS] {
+        let mut out = [0.0; MAX_BANDS];
+        for (dst, &v) in out.iter_mut().zip(values) {
+            *dst = v;
+        }
+        out
+    }
+
+    /// Band `i` of the classic sub/bass/low-mid/mid/high-mid/treble/air/presence
+    /// layout, resampled from the configured bands by position.
+    pub fn band8(&self, i: usize) -> f32 {
+        let n = self.band_count.clamp(1, MAX_BANDS);
+        let pos = ((i.min(7) as f32 + 0.5) * n as f32 / 8.0 - 0.5).clamp(0.0, (n - 1) as f32);
+        let k = pos.floor() as usize;
+        let frac = pos - k as f32;
+        self.bands[k] * (1.0 - frac) + self.bands[(k + 1).min(n - 1)] * frac
+    }
+
+    pub fn bands8(&self) -> [f32; 8] {
+        std::array::from_fn(|i| self.band8(i))
+    }
+
+    /// Momentary loudness mapped from -40..-8 LUFS onto 0..1.
+    pub fn loudness(&self) -> f32 {
+        lufs_to_unit(self.lufs_momentary)
+    }
+
+    /// Short-term loudness mapped from -40..-8 LUFS onto 0..1.
+    pub fn loudness_short(&self) -> f32 {
+        lufs_to_unit(self.lufs_short_term)
+    }
+
+    /// Crest factor mapped from 3 dB (flat, compressed) .. 18 dB (punchy) onto 0..1.
+    pub fn dynamics(&self) -> f32 {
+        ((self.crest_db - 3.0) / 15.0).clamp(0.0, 1.0)
+    }
+
+    /// Level (0..1) for section decisions: RMS blended with momentary loudness
+    /// once the analyzer has measured any, RMS alone otherwise.
+    pub fn perceived_level(&self) -> f32 {
+        if self.lufs_momentary > LUFS_FLOOR {
+            (self.rms + self.loudness()) * 0.5
+        } else {
+            self.rms
+        }
+    }
+
+    /// Indices of the configured bands that cover classic bands `classic`.
+    pub fn band8_range(&self, classic: std::ops::Range<usize>) -> std::ops::Range<usize> {
+        let n = self.band_count.clamp(1, MAX_BANDS);
+        let lo = (classic.start * n / 8).min(n - 1);
+        lo..(classic.end * n / 8).clamp(lo + 1, n)
+    }
 }
 
 impl Default for AudioFeatures {
     fn default() -> Self {
         Self {
             rms: 0.0,
-            bands: [0.0; 8],
+            bands: [0.0; MAX_BANDS],
+            band_count: CLASSIC_BAND_EDGES_HZ.len() - 1,
             onset: 0.0,
             beat: false,
             beat_strength: 0.0,
             centroid: 0.0,
             flatness: 0.0,
+            bpm: 0.0,
+            beat_phase: 0.0,
+            bar_phase: 0.0,
+            kick: 0.0,
+            snare: 0.0,
+            hat: 0.0,
+            rms_left: 0.0,
+            rms_right: 0.0,
+            balance: 0.0,
+            stereo_width: 0.0,
+            gain: 1.0,
+            chroma: [0.0; 12],
+            pitch_hz: 0.0,
+            key: 0,
+            key_minor: false,
+            key_confidence: 0.0,
+            key_change: 0.0,
+            lufs_momentary: LUFS_FLOOR,
+            lufs_short_term: LUFS_FLOOR,
+            crest_db: 0.0,
+            silent: false,
         }
     }
 }
 
+/// Loudness reported for silence; the floor of both LUFS features.
+pub const LUFS_FLOOR: f32 = -70.0;
+/// Loudness span `AudioFeatures::loudness` maps onto 0..1.
+const LOUDNESS_UNIT_LUFS: (f32, f32) = (-40.0, -8.0);
+
+fn lufs_to_unit(lufs: f32) -> f32 {
+    let (lo, hi) = LOUDNESS_UNIT_LUFS;
+    ((lufs - lo) / (hi - lo)).clamp(0.0, 1.0)
+}
+
 pub struct AtomicAudioFeatures {
     seq: AtomicU64,
     rms: AtomicU32,
-    bands: [AtomicU32; 8],
+    bands: [AtomicU32; MAX_BANDS],
+    band_count: AtomicU32,
     onset: AtomicU32,
     beat: AtomicU32,
     beat_strength: AtomicU32,
     centroid: AtomicU32,
     flatness: AtomicU32,
+    bpm: AtomicU32,
+    beat_phase: AtomicU32,
+    bar_phase: AtomicU32,
+    kick: AtomicU32,
+    snare: AtomicU32,
+    hat: AtomicU32,
+    rms_left: AtomicU32,
+    rms_right: AtomicU32,
+    balance: AtomicU32,
+    stereo_width: AtomicU32,
+    gain: AtomicU32,
+    chroma: [AtomicU32; 12],
+    pitch_hz: AtomicU32,
+    key: AtomicU32,
+    key_minor: AtomicU32,
+    key_confidence: AtomicU32,
+    key_change: AtomicU32,
+    lufs_momentary: AtomicU32,
+    lufs_short_term: AtomicU32,
+    crest_db: AtomicU32,
+    silent: AtomicU32,
     updated_ms: AtomicU64,
 }
 
@@ -61,11 +230,33 @@ impl AtomicAudioFeatures {
             seq: AtomicU64::new(0),
             rms: AtomicU32::new(0),
             bands: std::array::from_fn(|_| AtomicU32::new(0)),
+            band_count: AtomicU32::new(8),
             onset: AtomicU32::new(0),
             beat: AtomicU32::new(0),
             beat_strength: AtomicU32::new(0),
             centroid: AtomicU32::new(0),
             flatness: AtomicU32::new(0),
+            bpm: AtomicU32::new(0),
+            beat_phase: AtomicU32::new(0),
+            bar_phase: AtomicU32::new(0),
+            kick: AtomicU32::new(0),
+            snare: AtomicU32::new(0),
+            hat: AtomicU32::new(0),
+            rms_left: AtomicU32::new(0),
+            rms_right: AtomicU32::new(0),
+            balance: AtomicU32::new(0),
+            stereo_width: AtomicU32::new(0),
+            gain: AtomicU32::new(1.0f32.to_bits()),
+            chroma: std::array::from_fn(|_| AtomicU32::new(0)),
+            pitch_hz: AtomicU32::new(0),
+            key: AtomicU32::new(0),
+            key_minor: AtomicU32::new(0),
+            key_confidence: AtomicU32::new(0),
+            key_change: AtomicU32::new(0),
+            lufs_momentary: AtomicU32::new(LUFS_FLOOR.to_bits()),
+            lufs_short_term: AtomicU32::new(LUFS_FLOOR.to_bits()),
+            crest_db: AtomicU32::new(0),
+            silent: AtomicU32::new(0),
             updated_ms: AtomicU64::new(0),
         }
     }
@@ -76,6 +267,7 @@ impl AtomicAudioFeatures {
         for (dst, src) in self.bands.iter().zip(f.bands) {
             dst.store(src.to_bits(), Ordering::Relaxed);
         }
+        self.band_count.store(f.band_count as u32, Ordering::Relaxed);
         self.onset.store(f.onset.to_bits(), Ordering::Relaxed);
         self.beat
             .store(if f.beat { 1 } else { 0 }, Ordering::Relaxed);
@@ -83,6 +275,29 @@ impl AtomicAudioFeatures {
             .store(f.beat_strength.to_bits(), Ordering::Relaxed);
         self.centroid.store(f.centroid.to_bits(), Ordering::Relaxed);
         self.flatness.store(f.flatness.to_bits(), Ordering::Relaxed);
+        self.bpm.store(f.bpm.to_bits(), Ordering::Relaxed);
+        self.beat_phase.store(f.beat_phase.to_bits(), Ordering::Relaxed);
+        self.bar_phase.store(f.bar_phase.to_bits(), Ordering::Relaxed);
+        self.kick.store(f.kick.to_bits(), Ordering::Relaxed);
+        self.snare.store(f.snare.to_bits(), Ordering::Relaxed);
+        self.hat.store(f.hat.to_bits(), Ordering::Relaxed);
+        self.rms_left.store(f.rms_left.to_bits(), Ordering::Relaxed);
+        self.rms_right.store(f.rms_right.to_bits(), Ordering::Relaxed);
+        self.balance.store(f.balance.to_bits(), Ordering::Relaxed);
+        self.stereo_width.store(f.stereo_width.to_bits(), Ordering::Relaxed);
+        self.gain.store(f.gain.to_bits(), Ordering::Relaxed);
+        for (dst, src) in self.chroma.iter().zip(f.chroma) {
+            dst.store(src.to_bits(), Ordering::Relaxed);
+        }
+        self.pitch_hz.store(f.pitch_hz.to_bits(), Ordering::Relaxed);
+        self.key.store(f.key as u32, Ordering::Relaxed);
+        self.key_minor.store(f.key_minor as u32, Ordering::Relaxed);
+        self.key_confidence.store(f.key_confidence.to_bits(), Ordering::Relaxed);
+        self.key_change.store(f.key_change.to_bits(), Ordering::Relaxed);
+        self.lufs_momentary.store(f.lufs_momentary.to_bits(), Ordering::Relaxed);
+        self.lufs_short_term.store(f.lufs_short_term.to_bits(), Ordering::Relaxed);
+        self.crest_db.store(f.crest_db.to_bits(), Ordering::Relaxed);
+        self.silent.store(f.silent as u32, Ordering::Relaxed);
         self.updated_ms.store(now_ms(), Ordering::Relaxed);
         self.seq.fetch_add(1, Ordering::Release); // even => stable
     }
@@ -96,26 +311,73 @@ impl AtomicAudioFeatures {
             }
 
             let rms = f32::from_bits(self.rms.load(Ordering::Relaxed));
-            let mut bands = [0.0f32; 8];
+            let mut bands = [0.0f32; MAX_BANDS];
             for (i, src) in self.bands.iter().enumerate() {
                 bands[i] = f32::from_bits(src.load(Ordering::Relaxed));
             }
+            let band_count = self.band_count.load(Ordering::Relaxed) as usize;
             let onset = f32::from_bits(self.onset.load(Ordering::Relaxed));
             let beat = self.beat.load(Ordering::Relaxed) != 0;
             let beat_strength = f32::from_bits(self.beat_strength.load(Ordering::Relaxed));
             let centroid = f32::from_bits(self.centroid.load(Ordering::Relaxed));
             let flatness = f32::from_bits(self.flatness.load(Ordering::Relaxed));
+            let bpm = f32::from_bits(self.bpm.load(Ordering::Relaxed));
+            let beat_phase = f32::from_bits(self.beat_phase.load(Ordering::Relaxed));
+            let bar_phase = f32::from_bits(self.bar_phase.load(Ordering::Relaxed));
+            let kick = f32::from_bits(self.kick.load(Ordering::Relaxed));
+            let snare = f32::from_bits(self.snare.load(Ordering::Relaxed));
+            let hat = f32::from_bits(self.hat.load(Ordering::Relaxed));
+            let rms_left = f32::from_bits(self.rms_left.load(Ordering::Relaxed));
+            let rms_right = f32::from_bits(self.rms_right.load(Ordering::Relaxed));
+            let balance = f32::from_bits(self.balance.load(Ordering::Relaxed));
+            let stereo_width = f32::from_bits(self.stereo_width.load(Ordering::Relaxed));
+            let gain = f32::from_bits(self.gain.load(Ordering::Relaxed));
+            let chroma = self
+                .chroma
+                .each_ref()
+                .map(|c| f32::from_bits(c.load(Ordering::Relaxed)));
+            let pitch_hz = f32::from_bits(self.pitch_hz.load(Ordering::Relaxed));
+            let key = self.key.load(Ordering::Relaxed) as u8;
+            let key_minor = self.key_minor.load(Ordering::Relaxed) != 0;
+            let key_confidence = f32::from_bits(self.key_confidence.load(Ordering::Relaxed));
+            let key_change = f32::from_bits(self.key_change.load(Ordering::Relaxed));
+            let lufs_momentary = f32::from_bits(self.lufs_momentary.load(Ordering::Relaxed));
+            let lufs_short_term = f32::from_bits(self.lufs_short_term.load(Ordering::Relaxed));
+            let crest_db = f32::from_bits(self.crest_db.load(Ordering::Relaxed));
+            let silent = self.silent.load(Ordering::Relaxed) != 0;
 
             let v2 = self.seq.load(Ordering::Acquire);
             if v1 == v2 {
                 return AudioFeatures {
                     rms,
                     bands,
+                    band_count,
                     onset,
                     beat,
                     beat_strength,
                     centroid,
                     flatness,
+                    bpm,
+                    beat_phase,
+                    bar_phase,
+                    kick,
+                    snare,
+                    hat,
+                    rms_left,
+                    rms_right,
+                    balance,
+                    stereo_width,
+                    gain,
+                    chroma,
+                    pitch_hz,
+                    key,
+                    key_minor,
+                    key_confidence,
+                    key_change,
+                    lufs_momentary,
+                    lufs_short_term,
+                    crest_db,
+                    silent,
                 };
             }
         }
@@ -130,6 +392,264 @@ impl AtomicAudioFeatures {
     }
 }
 
+/// Default analysis window length in samples; also the length of the published waveform.
+pub const ANALYZER_WINDOW: usize = 1024;
+/// Default number of samples between successive analysis windows.
+pub const ANALYZER_HOP: usize = 256;
+pub const MIN_FFT_SIZE: usize = 256;
+pub const MAX_FFT_SIZE: usize = 16_384;
+pub const MIN_SPECTRUM_BINS: usize = 8;
+pub const MAX_SPECTRUM_BINS: usize = 512;
+pub const MAX_BANDS: usize = 32;
+/// Nominal sample rate of `--source synth`; frames are paced one hop apart at this rate.
+const SYNTH_SAMPLE_RATE_HZ: u32 = 48_000;
+/// Edges of the classic sub/bass/low-mid/mid/high-mid/treble/air/presence bands.
+pub const CLASSIC_BAND_EDGES_HZ: [f32; 9] =
+    [20.0, 60.0, 150.0, 400.0, 1000.0, 2500.0, 6000.0, 12000.0, 20000.0];
+
+/// Analysis settings: FFT window, band layout, spectrum resolution and AGC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzerConfig {
+    /// FFT length in samples (power of two).
+    pub fft_size: usize,
+    /// Samples between successive windows (1..=`fft_size`).
+    pub hop: usize,
+    pub window: WindowFunction,
+    /// Ascending band edges in Hz; `len() - 1` bands, at most `MAX_BANDS`.
+    pub band_edges_hz: Vec<f32>,
+    pub spectrum_bins: usize,
+    pub agc: bool,
+    /// Raw window RMS (dBFS) below which the input counts as quiet.
+    pub silence_threshold_db: f32,
+    /// Seconds of continuous quiet before `AudioFeatures::silent` is set.
+    pub silence_hold_s: f32,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            fft_size: ANALYZER_WINDOW,
+            hop: ANALYZER_HOP,
+            window: WindowFunction::Hann,
+            band_edges_hz: CLASSIC_BAND_EDGES_HZ.to_vec(),
+            spectrum_bins: 64,
+            agc: false,
+            silence_threshold_db: -60.0,
+            silence_hold_s: 1.5,
+        }
+    }
+}
+
+impl AnalyzerConfig {
+    /// Builds and validates the analyzer settings from the command line.
+    pub fn from_config(cfg: &Config) -> anyhow::Result<Self> {
+        Self::from_args(&cfg.analyzer)
+    }
+
+    /// Builds and validates the analyzer settings from the shared analysis flags.
+    /// `--band-edges` wins over `--bands`/`--band-spacing`.
+    pub fn from_args(cfg: &AnalyzerArgs) -> anyhow::Result<Self> {
+        let band_edges_hz = if cfg.band_edges.is_empty() {
+            band_edges_hz(cfg.band_spacing, cfg.bands, 20.0, 20_000.0)
+        } else {
+            cfg.band_edges.clone()
+        };
+        let out = Self {
+            fft_size: cfg.fft_size,
+            hop: cfg.hop,
+            window: cfg.window,
+            band_edges_hz,
+            spectrum_bins: cfg.spectrum_bins.clamp(MIN_SPECTRUM_BINS, MAX_SPECTRUM_BINS),
+            agc: cfg.agc,
+            silence_threshold_db: cfg.silence_threshold_db,
+            silence_hold_s: cfg.silence_hold,
+        };
+        out.validate()?;
+        Ok(out)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&self.fft_size) {
+            bail!("--fft-size must be a power of two in {MIN_FFT_SIZE}..={MAX_FFT_SIZE}");
+        }
+        if self.hop == 0 || self.hop > self.fft_size {
+            bail!("--hop must be in 1..=fft size ({})", self.fft_size);
+        }
+        let bands = self.band_count();
+        if !(1..=MAX_BANDS).contains(&bands) {
+            bail!("band layout must have 1..={MAX_BANDS} bands (got {bands})");
+        }
+        let ascending = self.band_edges_hz.windows(2).all(|e| e[0] < e[1]);
+        if !ascending || self.band_edges_hz[0] < 0.0 {
+            bail!("--band-edges must be non-negative and strictly ascending");
+        }
+        if !self.silence_threshold_db.is_finite() || self.silence_threshold_db > 0.0 {
+            bail!("--silence-threshold-db must be at most 0 dBFS");
+        }
+        if !(0.0..=3600.0).contains(&self.silence_hold_s) {
+            bail!("--silence-hold must be in 0..=3600 seconds");
+        }
+        Ok(())
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.band_edges_hz.len().saturating_sub(1)
+    }
+}
+
+/// Evenly spaced band edges between `lo_hz` and `hi_hz` on the given scale;
+/// `count + 1` values. `Classic` yields the fixed split for 8 bands, `Log` otherwise.
+pub fn band_edges_hz(spacing: BandSpacing, count: usize, lo_hz: f32, hi_hz: f32) -> Vec<f32> {
+    let to_mel = |f: f32| 2595.0 * (1.0 + f / 700.0).log10();
+    let from_mel = |m: f32| 700.0 * (10f32.powf(m / 2595.0) - 1.0);
+    if spacing == BandSpacing::Classic && count == CLASSIC_BAND_EDGES_HZ.len() - 1 {
+        return CLASSIC_BAND_EDGES_HZ.to_vec();
+    }
+    (0..=count)
+        .map(|i| {
+            let u = i as f32 / count.max(1) as f32;
+            match spacing {
+                BandSpacing::Linear => lo_hz + (hi_hz - lo_hz) * u,
+                BandSpacing::Mel => from_mel(to_mel(lo_hz) + (to_mel(hi_hz) - to_mel(lo_hz)) * u),
+                BandSpacing::Classic | BandSpacing::Log => lo_hz * (hi_hz / lo_hz).powf(u),
+            }
+        })
+        .collect()
+}
+
+fn window_coefficients(kind: WindowFunction, n: usize) -> Vec<f32> {
+    (0..n)
+        .map(|i| {
+            let x = 2.0 * PI * i as f32 / n as f32;
+            match kind {
+                WindowFunction::Hann => 0.5 - 0.5 * x.cos(),
+                WindowFunction::Hamming => 0.54 - 0.46 * x.cos(),
+                WindowFunction::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+                WindowFunction::Rect => 1.0,
+            }
+        })
+        .collect()
+}
+
+/// Owned copy of the latest spectrum/waveform, refilled each frame by the reader.
+#[derive(Debug, Clone, Default)]
+pub struct AudioSnapshot {
+    /// Log-spaced magnitude bins (0..1), lowest frequency first.
+    pub spectrum: Vec<f32>,
+    /// Most recent analysis window (-1..1), oldest sample first.
+    pub waveform: Vec<f32>,
+    /// Side signal `(left - right) / 2` for the same window; all zeros for mono.
+    pub side: Vec<f32>,
+}
+
+/// Seqlock-protected spectrum and waveform buffers, written by the analyzer thread.
+pub struct AtomicAudioSnapshot {
+    seq: AtomicU64,
+    spectrum: Vec<AtomicU32>,
+    waveform: Vec<AtomicU32>,
+    side: Vec<AtomicU32>,
+}
+
+impl AtomicAudioSnapshot {
+    pub fn new(spectrum_bins: usize, waveform_len: usize) -> Self {
+        Self {
+            seq: AtomicU64::new(0),
+            spectrum: (0..spectrum_bins).map(|_| AtomicU32::new(0)).collect(),
+            waveform: (0..waveform_len).map(|_| AtomicU32::new(0)).collect(),
+            side: (0..waveform_len).map(|_| AtomicU32::new(0)).collect(),
+        }
+    }
+
+    pub fn spectrum_bins(&self) -> usize {
+        self.spectrum.len()
+    }
+
+    pub fn waveform_len(&self) -> usize {
+        self.waveform.len()
+    }
+
+    /// Publishes new buffers; extra input is ignored and missing entries read as 0.
+    pub fn store(&self, spectrum: &[f32], waveform: &[f32], side: &[f32]) {
+        self.seq.fetch_add(1, Ordering::Release); // odd => write in progress
+        for (i, dst) in self.spectrum.iter().enumerate() {
+            let v = spectrum.get(i).copied().unwrap_or(0.0);
+            dst.store(v.to_bits(), Ordering::Relaxed);
+        }
+        for (i, dst) in self.waveform.iter().enumerate() {
+            let v = waveform.get(i).copied().unwrap_or(0.0);
+            dst.store(v.to_bits(), Ordering::Relaxed);
+        }
+        for (i, dst) in self.side.iter().enumerate() {
+            let v = side.get(i).copied().unwrap_or(0.0);
+            dst.store(v.to_bits(), Ordering::Relaxed);
+        }
+        self.seq.fetch_add(1, Ordering::Release); // even => stable
+    }
+
+    /// Copies a consistent snapshot into `out`, reusing its allocations.
+    pub fn load_into(&self, out: &mut AudioSnapshot) {
+        out.spectrum.resize(self.spectrum.len(), 0.0);
+        out.waveform.resize(self.waveform.len(), 0.0);
+        out.side.resize(self.side.len(), 0.0);
+        loop {
+            let v1 = self.seq.load(Ordering::Acquire);
+            if v1 & 1 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+
+            for (dst, src) in out.spectrum.iter_mut().zip(&self.spectrum) {
+                *dst = f32::from_bits(src.load(Ordering::Relaxed));
+            }
+            for (dst, src) in out.waveform.iter_mut().zip(&self.waveform) {
+                *dst = f32::from_bits(src.load(Ordering::Relaxed));
+            }
+            for (dst, src) in out.side.iter_mut().zip(&self.side) {
+                *dst = f32::from_bits(src.load(Ordering::Relaxed));
+            }
+
+            let v2 = self.seq.load(Ordering::Acquire);
+            if v1 == v2 {
+                return;
+            }
+        }
+    }
+}
+
+/// Resamples FFT magnitudes into `out.len()` log-spaced bins between 30 Hz and
+/// 16 kHz (or Nyquist), compressed to 0..1 the same way as the 8 feature bands.
+pub fn log_spectrum_bins(mags: &[f32], sample_rate_hz: u32, fft_len: usize, out: &mut [f32]) {
+    let count = out.len();
+    if count == 0 || mags.is_empty() || fft_len == 0 {
+        out.fill(0.0);
+        return;
+    }
+    let hz_per_bin = sample_rate_hz as f32 / fft_len as f32;
+    let lo_hz = 30.0f32;
+    let hi_hz = (sample_rate_hz as f32 * 0.5).min(16_000.0).max(lo_hz * 2.0);
+    let ratio = (hi_hz / lo_hz).ln();
+    let last = mags.len() - 1;
+
+    for (i, dst) in out.iter_mut().enumerate() {
+        let f0 = lo_hz * (ratio * i as f32 / count as f32).exp();
+        let f1 = lo_hz * (ratio * (i + 1) as f32 / count as f32).exp();
+        let b0 = f0 / hz_per_bin;
+        let b1 = f1 / hz_per_bin;
+        let m = if b1 - b0 < 1.0 {
+            // Narrower than one FFT bin: interpolate at the band centre.
+            let c = ((b0 + b1) * 0.5).min(last as f32);
+            let k = c.floor() as usize;
+            let frac = c - k as f32;
+            mags[k] * (1.0 - frac) + mags[(k + 1).min(last)] * frac
+        } else {
+            let k0 = (b0.ceil() as usize).min(last);
+            let k1 = (b1.floor() as usize).clamp(k0, last);
+            mags[k0..=k1].iter().copied().fold(0.0f32, f32::max)
+        };
+        *dst = (m * 0.01).tanh();
+    }
+}
+
 fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -139,21 +659,154 @@ fn now_ms() -> u64 {
 
 pub fn list_input_devices() -> anyhow::Result<()> {
     let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
     let devices = host
         .input_devices()
         .context("enumerate input devices")?;
 
     let mut out = io::stdout();
-    writeln!(out, "Input devices:")?;
-    for dev in devices {
+    writeln!(out, "Input devices (--device <index|name>):")?;
+    for (index, dev) in devices.enumerate() {
         let name = dev.name().unwrap_or_else(|_| "<unknown>".to_string());
-        writeln!(out, "  - {}", name)?;
+        let mark = if default_name.as_ref() == Some(&name) { " (default)" } else { "" };
+        writeln!(out, "  [{index}] {name}{mark}")?;
+        if let Ok(def) = dev.default_input_config() {
+            writeln!(
+                out,
+                "      default: {} Hz, {} ch, {}",
+                def.sample_rate().0,
+                def.channels(),
+                def.sample_format()
+            )?;
+        }
+        match dev.supported_input_configs() {
+            Ok(ranges) => {
+                for line in describe_config_ranges(ranges) {
+                    writeln!(out, "      {line}")?;
+                }
+            }
+            Err(err) => writeln!(out, "      (configs unavailable: {err})")?,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        writeln!(out, "Monitor sources (--source system --device <name>):")?;
+        match list_monitor_sources() {
+            Ok(sources) if sources.is_empty() => writeln!(out, "  (none found)")?,
+            Ok(sources) => {
+                for name in sources {
+                    writeln!(out, "  - {}", name)?;
+                }
+            }
+            Err(err) => writeln!(out, "  (unavailable: {err})")?,
+        }
     }
     Ok(())
 }
 
+/// One line per distinct supported config, e.g. `2 ch f32 44100-96000 Hz, buffer 64-4096`.
+fn describe_config_ranges(ranges: impl Iterator<Item = SupportedStreamConfigRange>) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    for r in ranges {
+        let rates = if r.min_sample_rate() == r.max_sample_rate() {
+            format!("{} Hz", r.min_sample_rate().0)
+        } else {
+            format!("{}-{} Hz", r.min_sample_rate().0, r.max_sample_rate().0)
+        };
+        let mut line = format!("{} ch {} {rates}", r.channels(), r.sample_format());
+        if let SupportedBufferSize::Range { min, max } = r.buffer_size() {
+            line.push_str(&format!(", buffer {min}-{max}"));
+        }
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Lists PulseAudio/PipeWire monitor source names via `pactl list short sources`.
+#[cfg(target_os = "linux")]
+fn list_monitor_sources() -> anyhow::Result<Vec<String>> {
+    let output = std::process::Command::new("pactl")
+        .args(["list", "short", "sources"])
+        .output()
+        .context("run pactl (install pulseaudio-utils or pipewire-pulse)")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("pactl exited with {}: {}", output.status, stderr.trim());
+    }
+    Ok(parse_monitor_sources(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(target_os = "linux")]
+fn parse_monitor_sources(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .map(str::trim)
+        .filter(|name| name.ends_with(".monitor"))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn select_monitor_source(device_query: Option<&str>) -> anyhow::Result<String> {
+    let Some(want) = device_query.map(|s| s.to_lowercase()) else {
+        return Ok("@DEFAULT_MONITOR@".to_string());
+    };
+    let sources = list_monitor_sources()?;
+    let exact = sources.iter().position(|name| name.to_lowercase() == want);
+    exact
+        .or_else(|| sources.iter().position(|name| name.to_lowercase().contains(&want)))
+        .map(|i| sources[i].clone())
+        .ok_or_else(|| anyhow!("no monitor source matching: {want}"))
+}
+
+/// How long `parec` must stay up after spawning before capture counts as started.
+#[cfg(target_os = "linux")]
+const PAREC_STARTUP_CHECK: Duration = Duration::from_millis(150);
+
+/// Keeps the last non-empty stderr line of a child process, so its exit can be
+/// explained without letting the pipe fill up while it runs. The thread ends
+/// once the child closes stderr; join it before reading the tail after an exit.
+#[cfg(target_os = "linux")]
+fn spawn_stderr_tail(
+    stderr: impl Read + Send + 'static,
+) -> (Arc<Mutex<String>>, thread::JoinHandle<()>) {
+    use std::io::BufRead as _;
+
+    let tail = Arc::new(Mutex::new(String::new()));
+    let tail_for_thread = Arc::clone(&tail);
+    let handle = thread::spawn(move || {
+        for line in io::BufReader::new(stderr).lines().map_while(Result::ok) {
+            let line = line.trim();
+            if !line.is_empty() {
+                *tail_for_thread.lock().unwrap_or_else(|e| e.into_inner()) = line.to_string();
+            }
+        }
+    });
+    (tail, handle)
+}
+
+#[cfg(target_os = "linux")]
+fn parec_exit_message(status: std::process::ExitStatus, stderr_tail: &Mutex<String>) -> String {
+    let tail = stderr_tail.lock().unwrap_or_else(|e| e.into_inner());
+    if tail.is_empty() {
+        format!("parec exited with {status}")
+    } else {
+        format!("parec exited with {status}: {tail}")
+    }
+}
+
 enum AudioBackend {
     Cpal(cpal::Stream),
+    Feeder(thread::JoinHandle<()>),
+    #[cfg(target_os = "linux")]
+    PulseMonitor {
+        child: Arc<Mutex<std::process::Child>>,
+        reader: thread::JoinHandle<()>,
+    },
+    Stopped,
     #[cfg(target_os = "macos")]
     ScreenCaptureKit(SystemAudioStream),
 }
@@ -163,59 +816,224 @@ struct SystemAudioStream {
     stream: SCStream,
 }
 
+/// First error reported by a capture callback or reader thread, held until
+/// the supervisor picks it up.
+#[derive(Clone, Default)]
+pub struct StreamFault(Arc<Mutex<Option<String>>>);
+
+impl StreamFault {
+    pub fn raise(&self, message: impl Into<String>) {
+        let mut slot = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        slot.get_or_insert_with(|| message.into());
+    }
+
+    fn take(&self) -> Option<String> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
 pub struct AudioSystem {
     backend: AudioBackend,
     stop: Arc<AtomicBool>,
     analyzer_handle: Option<thread::JoinHandle<()>>,
     features: Arc<AtomicAudioFeatures>,
+    snapshot: Arc<AtomicAudioSnapshot>,
     pub sample_rate_hz: u32,
+    fault: StreamFault,
+    recorder: RecorderSlot,
+    /// Full name of the input or monitor source `--device` resolved to.
+    device_name: Option<String>,
+    started: Instant,
 }
 
 impl AudioSystem {
-    pub fn new(source: AudioSource, device_query: Option<&str>) -> anyhow::Result<Self> {
-        match source {
-            AudioSource::Mic => Self::new_mic(device_query),
-            AudioSource::System => Self::new_system(),
+    pub fn new(cfg: &Config) -> anyhow::Result<Self> {
+        let opts = AnalyzerConfig::from_config(cfg)?;
+        let record = cfg.record_features.as_deref().map(Path::new);
+        match cfg.source {
+            AudioSource::Mic => Self::new_mic(cfg.device.as_deref(), &cfg.capture, opts, record),
+            AudioSource::System => Self::new_system(cfg.device.as_deref(), opts, record),
+            AudioSource::File => {
+                let path = cfg
+                    .input
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("--source file requires --input <path.wav>"))?;
+                Self::new_file(Path::new(path), cfg.input_loop, opts, record)
+            }
+            AudioSource::Stdin => Self::new_stdin(
+                cfg.stdin_format,
+                cfg.stdin_rate,
+                cfg.stdin_channels,
+                opts,
+                record,
+            ),
+            AudioSource::Replay => {
+                let path = cfg.input.as_deref().ok_or_else(|| {
+                    anyhow!("--source replay requires --input <features recording>")
+                })?;
+                Self::new_replay(Path::new(path), cfg.input_loop, &opts, record)
+            }
+            AudioSource::Synth => Self::new_synth(cfg.synth_pattern, cfg.synth_bpm, &opts, record),
+        }
+    }
+
+    fn new_stdin(
+        format: PcmFormat,
+        sample_rate_hz: u32,
+        channels: u16,
+        opts: AnalyzerConfig,
+        record: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        if sample_rate_hz == 0 {
+            bail!("--stdin-rate must be >= 1");
+        }
+        if channels == 0 {
+            bail!("--stdin-channels must be >= 1");
+        }
+        let channels = channels as usize;
+
+        let analyzer = Analyzer::new(opts, sample_rate_hz)?;
+
+        let recorder = open_recorder(record, sample_rate_hz)?;
+
+        let rb_capacity = (sample_rate_hz as usize).saturating_mul(4);
+        let rb = HeapRb::<[f32; 2]>::new(rb_capacity);
+        let (mut prod, cons) = rb.split();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_for_reader = Arc::clone(&stop);
+
+        // Blocking reads on stdin cannot be interrupted, so the reader is detached
+        // and exits on EOF or on the next read after `stop` is raised.
+        thread::spawn(move || {
+            read_pcm_stream(
+                io::stdin().lock(),
+                format,
+                channels,
+                &stop_for_reader,
+                &mut prod,
+            )
+        });
+
+        let (features, snapshot, analyzer_handle) =
+            spawn_analyzer(cons, analyzer, Arc::clone(&recorder), &stop);
+
+        Ok(Self {
+            backend: AudioBackend::Stopped,
+            stop,
+            analyzer_handle: Some(analyzer_handle),
+            features,
+            snapshot,
+            sample_rate_hz,
+            fault: StreamFault::default(),
+            recorder,
+            device_name: None,
+            started: Instant::now(),
+        })
+    }
+
+    fn new_file(
+        path: &Path,
+        looped: bool,
+        opts: AnalyzerConfig,
+        record: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let (sample_rate_hz, samples) =
+            read_wav_stereo_f32(path).with_context(|| format!("read wav {}", path.display()))?;
+        if samples.is_empty() {
+            bail!("wav had no samples: {}", path.display());
         }
+
+        let analyzer = Analyzer::new(opts, sample_rate_hz)?;
+
+        let recorder = open_recorder(record, sample_rate_hz)?;
+
+        let rb_capacity = (sample_rate_hz as usize).saturating_mul(4);
+        let rb = HeapRb::<[f32; 2]>::new(rb_capacity);
+        let (mut prod, cons) = rb.split();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_for_feeder = Arc::clone(&stop);
+
+        let feeder = thread::spawn(move || {
+            feed_samples_realtime(&samples, sample_rate_hz, looped, &stop_for_feeder, &mut prod)
+        });
+
+        let (features, snapshot, analyzer_handle) =
+            spawn_analyzer(cons, analyzer, Arc::clone(&recorder), &stop);
+
+        Ok(Self {
+            backend: AudioBackend::Feeder(feeder),
+            stop,
+            analyzer_handle: Some(analyzer_handle),
+            features,
+            snapshot,
+            sample_rate_hz,
+            fault: StreamFault::default(),
+            recorder,
+            device_name: None,
+            started: Instant::now(),
+        })
     }
 
-    fn new_mic(device_query: Option<&str>) -> anyhow::Result<Self> {
+    fn new_mic(
+        device_query: Option<&str>,
+        capture: &CaptureArgs,
+        opts: AnalyzerConfig,
+        record: Option<&Path>,
+    ) -> anyhow::Result<Self> {
         let host = cpal::default_host();
         let device = select_mic_input_device(&host, device_query)?;
-        let supported = device
+        let device_name = device_query.and_then(|_| device.name().ok());
+        let default = device
             .default_input_config()
             .context("get default input config")?;
+        let ranges = device
+            .supported_input_configs()
+            .context("list supported input configs")?
+            .collect::<Vec<_>>();
+        let supported = capture_config(default, &ranges, capture)?;
         let sample_rate_hz = supported.sample_rate().0;
         let channels = supported.channels() as usize;
-        let config: cpal::StreamConfig = supported.clone().into();
+        let mut config = supported.config();
+        config.buffer_size = capture_buffer_size(supported.buffer_size(), capture.capture_buffer)?;
+        let select = capture
+            .capture_channels
+            .as_ref()
+            .map(|c| c.indices().to_vec())
+            .unwrap_or_default();
+
+        let analyzer = Analyzer::new(opts, sample_rate_hz)?;
+
+        let recorder = open_recorder(record, sample_rate_hz)?;
 
         let rb_capacity = (sample_rate_hz as usize).saturating_mul(4);
-        let rb = HeapRb::<f32>::new(rb_capacity);
-        let (mut prod, mut cons) = rb.split();
+        let rb = HeapRb::<[f32; 2]>::new(rb_capacity);
+        let (mut prod, cons) = rb.split();
 
         let stop = Arc::new(AtomicBool::new(false));
-        let features = Arc::new(AtomicAudioFeatures::new());
-        let features_for_thread = Arc::clone(&features);
-        let stop_for_thread = Arc::clone(&stop);
 
-        let err_fn = |err| eprintln!("audio stream error: {err}");
+        // Printing here would tear the TUI; the supervisor polls the fault instead.
+        let fault = StreamFault::default();
+        let fault_for_stream = fault.clone();
+        let err_fn = move |err: cpal::StreamError| fault_for_stream.raise(err.to_string());
 
         let stream = match supported.sample_format() {
             SampleFormat::F32 => device.build_input_stream(
                 &config,
-                move |data: &[f32], _| push_interleaved(data, channels, &mut prod),
+                move |data: &[f32], _| push_selected(data, channels, &select, &mut prod),
                 err_fn,
                 None,
             )?,
             SampleFormat::I16 => device.build_input_stream(
                 &config,
-                move |data: &[i16], _| push_interleaved(data, channels, &mut prod),
+                move |data: &[i16], _| push_selected(data, channels, &select, &mut prod),
                 err_fn,
                 None,
             )?,
             SampleFormat::U16 => device.build_input_stream(
                 &config,
-                move |data: &[u16], _| push_interleaved(data, channels, &mut prod),
+                move |data: &[u16], _| push_selected(data, channels, &select, &mut prod),
                 err_fn,
                 None,
             )?,
@@ -224,42 +1042,53 @@ impl AudioSystem {
 
         stream.play().context("start input stream")?;
 
-        let analyzer_handle = thread::spawn(move || {
-            analyze_loop(
-                &mut cons,
-                sample_rate_hz,
-                &stop_for_thread,
-                &features_for_thread,
-            )
-        });
+        let (features, snapshot, analyzer_handle) =
+            spawn_analyzer(cons, analyzer, Arc::clone(&recorder), &stop);
 
         Ok(Self {
             backend: AudioBackend::Cpal(stream),
             stop,
             analyzer_handle: Some(analyzer_handle),
             features,
+            snapshot,
             sample_rate_hz,
+            fault,
+            recorder,
+            device_name,
+            started: Instant::now(),
         })
     }
 
-    fn new_system() -> anyhow::Result<Self> {
-        #[cfg(not(target_os = "macos"))]
+    fn new_system(
+        device_query: Option<&str>,
+        opts: AnalyzerConfig,
+        record: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        #[cfg(target_os = "linux")]
         {
-            Err(anyhow!("--source system is only supported on macOS in v1"))
+            Self::new_pulse_monitor(device_query, opts, record)
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        {
+            let _ = (device_query, opts, record);
+            Err(anyhow!("--source system is only supported on macOS and Linux"))
         }
 
         #[cfg(target_os = "macos")]
         {
+            let _ = device_query;
             let sample_rate_hz = 48_000u32;
 
+            let analyzer = Analyzer::new(opts, sample_rate_hz)?;
+
+            let recorder = open_recorder(record, sample_rate_hz)?;
+
             let rb_capacity = (sample_rate_hz as usize).saturating_mul(4);
-            let rb = HeapRb::<f32>::new(rb_capacity);
-            let (prod, mut cons) = rb.split();
+            let rb = HeapRb::<[f32; 2]>::new(rb_capacity);
+            let (prod, cons) = rb.split();
 
             let stop = Arc::new(AtomicBool::new(false));
-            let features = Arc::new(AtomicAudioFeatures::new());
-            let features_for_thread = Arc::clone(&features);
-            let stop_for_thread = Arc::clone(&stop);
 
             let handler = SystemAudioHandler {
                 prod: std::sync::Mutex::new(prod),
@@ -268,93 +1097,940 @@ impl AudioSystem {
             let stream = start_system_audio_stream(handler)
                 .context("start system audio capture (ScreenCaptureKit)")?;
 
-            let analyzer_handle = thread::spawn(move || {
-                analyze_loop(
-                    &mut cons,
-                    sample_rate_hz,
-                    &stop_for_thread,
-                    &features_for_thread,
-                )
-            });
+            let (features, snapshot, analyzer_handle) =
+                spawn_analyzer(cons, analyzer, Arc::clone(&recorder), &stop);
 
             Ok(Self {
                 backend: AudioBackend::ScreenCaptureKit(SystemAudioStream { stream }),
                 stop,
                 analyzer_handle: Some(analyzer_handle),
                 features,
+                snapshot,
                 sample_rate_hz,
+                fault: StreamFault::default(),
+                recorder,
+                device_name: None,
+                started: Instant::now(),
             })
         }
     }
 
-    pub fn features(&self) -> Arc<AtomicAudioFeatures> {
-        Arc::clone(&self.features)
-    }
-}
-
-impl Drop for AudioSystem {
-    fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
-        if let Some(h) = self.analyzer_handle.take() {
-            let _ = h.join();
-        }
-
-        match &mut self.backend {
-            // Keep the mic stream alive for the full AudioSystem lifetime.
-            // Explicitly matching here makes that intent visible and avoids dead-code warnings.
-            AudioBackend::Cpal(_stream) => {}
-            #[cfg(target_os = "macos")]
-            AudioBackend::ScreenCaptureKit(s) => {
-                let _ = s.stream.stop_capture();
+    /// Captures the default sink's monitor (or the monitor matching `--device`)
+    /// through `parec`, which speaks to both PulseAudio and PipeWire's Pulse server.
+    /// A `parec` that exits (at startup or mid-stream) is reported with its exit
+    /// status and last stderr line, through the returned error or the stream fault.
+    #[cfg(target_os = "linux")]
+    fn new_pulse_monitor(
+        device_query: Option<&str>,
+        opts: AnalyzerConfig,
+        record: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        use std::process::{Command, Stdio};
+
+        let sample_rate_hz = 48_000u32;
+        let channels = 2usize;
+        let source = select_monitor_source(device_query)?;
+        let analyzer = Analyzer::new(opts, sample_rate_hz)?;
+        let recorder = open_recorder(record, sample_rate_hz)?;
+
+        let mut child = Command::new("parec")
+            .arg(format!("--device={source}"))
+            .args([
+                "--raw",
+                "--format=float32le",
+                "--rate=48000",
+                "--channels=2",
+                "--latency-msec=10",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("spawn parec for monitor capture (install pulseaudio-utils or pipewire-pulse)")?;
+        let stdout = child
+            .stdout
+            .take()
+            .context("failed to open parec stdout")?;
+        let (stderr_tail, stderr_reader) = child
+            .stderr
+            .take()
+            .map(spawn_stderr_tail)
+            .context("failed to open parec stderr")?;
+
+        // parec exits right away on a bad source or an unreachable server.
+        let started = Instant::now();
+        while started.elapsed() < PAREC_STARTUP_CHECK {
+            if let Some(status) = child.try_wait().context("poll parec")? {
+                let _ = stderr_reader.join();
+                bail!("{}", parec_exit_message(status, &stderr_tail));
             }
+            thread::sleep(Duration::from_millis(10));
         }
-    }
-}
+        let child = Arc::new(Mutex::new(child));
 
-fn select_mic_input_device(
-    host: &cpal::Host,
-    device_query: Option<&str>,
-) -> anyhow::Result<cpal::Device> {
-    let devices = host
-        .input_devices()
-        .context("enumerate input devices")?
-        .collect::<Vec<_>>();
+        let rb_capacity = (sample_rate_hz as usize).saturating_mul(4);
+        let rb = HeapRb::<[f32; 2]>::new(rb_capacity);
+        let (mut prod, cons) = rb.split();
 
-    let want = device_query.map(|s| s.to_lowercase());
-    if let Some(want) = want.as_deref() {
-        if let Some(dev) = devices.iter().find(|d| {
-            d.name()
-                .map(|n| n.to_lowercase().contains(want))
-                .unwrap_or(false)
-        }) {
-            return Ok(dev.clone());
-        }
-        return Err(anyhow!("no input device matching: {want}"));
-    }
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_for_reader = Arc::clone(&stop);
+        let fault = StreamFault::default();
+        let fault_for_reader = fault.clone();
+        let child_for_reader = Arc::clone(&child);
+
+        let reader = thread::spawn(move || {
+            read_pcm_stream(stdout, PcmFormat::F32le, channels, &stop_for_reader, &mut prod);
+            if stop_for_reader.load(Ordering::Relaxed) {
+                return;
+            }
+            // stdout closed on its own: give parec a moment to exit, then report
+            // how it ended. The lock is only held per poll so `Drop` can kill it.
+            for _ in 0..50 {
+                let status = child_for_reader
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .try_wait();
+                match status {
+                    Ok(Some(status)) => {
+                        let _ = stderr_reader.join();
+                        fault_for_reader.raise(parec_exit_message(status, &stderr_tail));
+                        return;
+                    }
+                    Ok(None) => thread::sleep(Duration::from_millis(20)),
+                    Err(err) => {
+                        fault_for_reader.raise(format!("parec: {err}"));
+                        return;
+                    }
+                }
+            }
+            fault_for_reader.raise("parec closed its output");
+        });
 
-    host.default_input_device()
-        .ok_or_else(|| anyhow!("no default input device found"))
-}
+        let (features, snapshot, analyzer_handle) =
+            spawn_analyzer(cons, analyzer, Arc::clone(&recorder), &stop);
 
-fn push_interleaved<T: Sample<Float = f32> + Copy>(
-    data: &[T],
-    channels: usize,
-    prod: &mut ringbuf::HeapProd<f32>,
-) {
-    for frame in data.chunks(channels) {
-        let mut acc = 0.0f32;
-        for s in frame {
-            acc += (*s).to_float_sample();
-        }
-        let mono = acc / channels as f32;
-        let _ = prod.try_push(mono);
+        Ok(Self {
+            backend: AudioBackend::PulseMonitor { child, reader },
+            stop,
+            analyzer_handle: Some(analyzer_handle),
+            features,
+            snapshot,
+            sample_rate_hz,
+            fault,
+            recorder,
+            device_name: device_query.map(|_| source),
+            started: Instant::now(),
+        })
     }
-}
 
-#[cfg(target_os = "macos")]
-struct SystemAudioHandler {
-    prod: std::sync::Mutex<ringbuf::HeapProd<f32>>,
-}
+    /// Plays a `--record-features` log back on its original timeline. No audio
+    /// is analyzed, so the spectrum and waveform snapshot stays silent.
+    fn new_replay(
+        path: &Path,
+        looped: bool,
+        opts: &AnalyzerConfig,
+        record: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let recording = FeatureRecording::load(path)?;
+        if recording.frames.is_empty() {
+            bail!("feature recording had no frames: {}", path.display());
+        }
+        let sample_rate_hz = recording.sample_rate_hz;
+        let recorder = open_recorder(record, sample_rate_hz)?;
+        let recorder_for_feeder = Arc::clone(&recorder);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_for_feeder = Arc::clone(&stop);
+        let features = Arc::new(AtomicAudioFeatures::new());
+        let features_for_feeder = Arc::clone(&features);
+        let snapshot = Arc::new(AtomicAudioSnapshot::new(opts.spectrum_bins, opts.fft_size));
+
+        let feeder = thread::spawn(move || {
+            replay_features(
+                &recording.frames,
+                looped,
+                &stop_for_feeder,
+                &features_for_feeder,
+                &recorder_for_feeder,
+            )
+        });
+
+        Ok(Self {
+            backend: AudioBackend::Feeder(feeder),
+            stop,
+            analyzer_handle: None,
+            features,
+            snapshot,
+            sample_rate_hz,
+            fault: StreamFault::default(),
+            recorder,
+            device_name: None,
+            started: Instant::now(),
+        })
+    }
+
+    /// Generates `PatternSynth` features in real time, with a matching synthetic
+    /// spectrum and waveform so scope presets have something to draw.
+    fn new_synth(
+        pattern: SynthPattern,
+        bpm: f32,
+        opts: &AnalyzerConfig,
+        record: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        if !(bpm.is_finite() && (40.0..=240.0).contains(&bpm)) {
+            bail!("--synth-bpm must be in 40..=240");
+        }
+        let sample_rate_hz = SYNTH_SAMPLE_RATE_HZ;
+        let recorder = open_recorder(record, sample_rate_hz)?;
+        let recorder_for_feeder = Arc::clone(&recorder);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_for_feeder = Arc::clone(&stop);
+        let features = Arc::new(AtomicAudioFeatures::new());
+        let features_for_feeder = Arc::clone(&features);
+        let snapshot = Arc::new(AtomicAudioSnapshot::new(opts.spectrum_bins, opts.fft_size));
+        let snapshot_for_feeder = Arc::clone(&snapshot);
+        let frame_period = Duration::from_secs_f64(opts.hop as f64 / sample_rate_hz as f64);
+
+        let feeder = thread::spawn(move || {
+            let mut synth = PatternSynth::new(pattern, bpm);
+            let mut snap = AudioSnapshot {
+                spectrum: vec![0.0; snapshot_for_feeder.spectrum_bins()],
+                waveform: vec![0.0; snapshot_for_feeder.waveform_len()],
+                side: vec![0.0; snapshot_for_feeder.waveform_len()],
+            };
+            let start = Instant::now();
+            let mut next = start;
+            while !stop_for_feeder.load(Ordering::Relaxed) {
+                let f = synth.step(start.elapsed().as_secs_f32());
+                synth_snapshot(&f, &mut snap);
+                features_for_feeder.store(f);
+                snapshot_for_feeder.store(&snap.spectrum, &snap.waveform, &snap.side);
+                record_frame(&recorder_for_feeder, &f);
+                next += frame_period;
+                if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                    thread::sleep(wait);
+                }
+            }
+            flush_recorder(&recorder_for_feeder);
+        });
+
+        Ok(Self {
+            backend: AudioBackend::Feeder(feeder),
+            stop,
+            analyzer_handle: None,
+            features,
+            snapshot,
+            sample_rate_hz,
+            fault: StreamFault::default(),
+            recorder,
+            device_name: None,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn features(&self) -> Arc<AtomicAudioFeatures> {
+        Arc::clone(&self.features)
+    }
+
+    pub fn snapshot(&self) -> Arc<AtomicAudioSnapshot> {
+        Arc::clone(&self.snapshot)
+    }
+
+    pub fn stream_fault(&self) -> StreamFault {
+        self.fault.clone()
+    }
+
+    /// Full name of the device `--device` picked, for live capture.
+    pub fn device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
+
+    /// Live capture should publish continuously; files, stdin and generated
+    /// sources are allowed to end or pause.
+    fn is_live(&self) -> bool {
+        match self.backend {
+            AudioBackend::Cpal(_) => true,
+            #[cfg(target_os = "linux")]
+            AudioBackend::PulseMonitor { .. } => true,
+            #[cfg(target_os = "macos")]
+            AudioBackend::ScreenCaptureKit(_) => true,
+            AudioBackend::Feeder(_) | AudioBackend::Stopped => false,
+        }
+    }
+
+    /// What the supervisor keeps of this stream on the render thread.
+    fn handle(&self) -> StreamHandle {
+        StreamHandle {
+            features: self.features(),
+            snapshot: self.snapshot(),
+            fault: self.stream_fault(),
+            live: self.is_live(),
+            started: self.started,
+        }
+    }
+
+    /// Detaches the `--record-features` log, flushed, so it can outlive this stream.
+    fn take_recorder(&self) -> Option<FeatureRecorder> {
+        let mut rec = self.recorder.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(rec) = rec.as_mut() {
+            let _ = rec.flush();
+        }
+        rec
+    }
+
+    fn attach_recorder(&self, recorder: Option<FeatureRecorder>) {
+        *self.recorder.lock().unwrap_or_else(|e| e.into_inner()) = recorder;
+    }
+}
+
+fn spawn_analyzer(
+    mut cons: ringbuf::HeapCons<[f32; 2]>,
+    mut analyzer: Analyzer,
+    recorder: RecorderSlot,
+    stop: &Arc<AtomicBool>,
+) -> (
+    Arc<AtomicAudioFeatures>,
+    Arc<AtomicAudioSnapshot>,
+    thread::JoinHandle<()>,
+) {
+    let features = Arc::new(AtomicAudioFeatures::new());
+    let snapshot = Arc::new(AtomicAudioSnapshot::new(
+        analyzer.spectrum().len(),
+        analyzer.waveform().len(),
+    ));
+    let features_for_thread = Arc::clone(&features);
+    let snapshot_for_thread = Arc::clone(&snapshot);
+    let stop_for_thread = Arc::clone(stop);
+    let handle = thread::spawn(move || {
+        analyze_loop(
+            &mut cons,
+            &mut analyzer,
+            &recorder,
+            &stop_for_thread,
+            &features_for_thread,
+            &snapshot_for_thread,
+        )
+    });
+    (features, snapshot, handle)
+}
+
+impl Drop for AudioSystem {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(h) = self.analyzer_handle.take() {
+            let _ = h.join();
+        }
+
+        match std::mem::replace(&mut self.backend, AudioBackend::Stopped) {
+            // Keep the mic stream alive for the full AudioSystem lifetime.
+            // Explicitly matching here makes that intent visible and avoids dead-code warnings.
+            AudioBackend::Cpal(_stream) => {}
+            AudioBackend::Feeder(handle) => {
+                let _ = handle.join();
+            }
+            #[cfg(target_os = "linux")]
+            AudioBackend::PulseMonitor { child, reader } => {
+                // Killing parec closes its stdout, which ends the reader loop.
+                {
+                    let mut child = child.lock().unwrap_or_else(|e| e.into_inner());
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                let _ = reader.join();
+            }
+            AudioBackend::Stopped => {}
+            #[cfg(target_os = "macos")]
+            AudioBackend::ScreenCaptureKit(s) => {
+                let _ = s.stream.stop_capture();
+            }
+        }
+    }
+}
+
+/// How long live capture may go without publishing before it counts as stalled.
+const STALL_AFTER: Duration = Duration::from_secs(2);
+/// Wait before the first reopen after a fault, and between later attempts.
+const REOPEN_DELAY: Duration = Duration::from_secs(1);
+const REOPEN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Builds an `AudioSystem` from a config; `AudioSystem::new` outside of tests.
+pub type AudioOpener = fn(&Config) -> anyhow::Result<AudioSystem>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupervisorEvent {
+    /// The stream errored or stalled and has been closed.
+    Lost(String),
+    /// A new stream is running on the named device.
+    Reconnected(String),
+    /// A reopen attempt failed; another follows after the retry interval.
+    ReconnectFailed(String),
+}
+
+/// The render thread's view of a stream owned by the supervisor's audio thread.
+struct StreamHandle {
+    features: Arc<AtomicAudioFeatures>,
+    snapshot: Arc<AtomicAudioSnapshot>,
+    fault: StreamFault,
+    live: bool,
+    started: Instant,
+}
+
+impl StreamHandle {
+    /// Returns why the stream should be considered dead: a reported error, or
+    /// live capture that has published nothing for longer than `stall`.
+    fn check_fault(&self, stall: Duration) -> Option<String> {
+        if let Some(err) = self.fault.take() {
+            return Some(err);
+        }
+        if !self.live {
+            return None;
+        }
+        let silent_for = match self.features.updated_ms.load(Ordering::Relaxed) {
+            0 => self.started.elapsed(),
+            t => Duration::from_millis(now_ms().saturating_sub(t)),
+        };
+        (silent_for > stall).then(|| format!("no audio for {:.1}s", silent_for.as_secs_f32()))
+    }
+}
+
+enum AudioRequest {
+    Close,
+    Reopen,
+    Shutdown,
+}
+
+type Opened = anyhow::Result<(StreamHandle, String)>;
+
+/// Owns the running `AudioSystem` and rebuilds it when the stream errors out or
+/// stalls (device unplugged, sound server restarted): first on the device
+/// `--device` resolved to at startup, by its full name, then on the default one
+/// with the full channel layout.
+///
+/// Streams live on a dedicated audio thread, so device enumeration and stream
+/// setup never block a frame; `poll` only exchanges messages with it. The
+/// `--record-features` log is moved onto each reopened stream and keeps its
+/// original timeline.
+pub struct AudioSupervisor {
+    requests: mpsc::Sender<AudioRequest>,
+    replies: mpsc::Receiver<Opened>,
+    thread: Option<thread::JoinHandle<()>>,
+    stream: Option<StreamHandle>,
+    features: Arc<AtomicAudioFeatures>,
+    snapshot: Arc<AtomicAudioSnapshot>,
+    next_attempt: Option<Instant>,
+    reopening: bool,
+    outages: u32,
+}
+
+impl AudioSupervisor {
+    pub fn new(cfg: &Config) -> anyhow::Result<Self> {
+        Self::with_opener(cfg, AudioSystem::new)
+    }
+
+    /// Opens the first stream on the audio thread and waits for it.
+    pub fn with_opener(cfg: &Config, open: AudioOpener) -> anyhow::Result<Self> {
+        let (requests, requests_rx) = mpsc::channel();
+        let (replies_tx, replies) = mpsc::channel();
+        let cfg = cfg.clone();
+        let thread = thread::spawn(move || run_audio_thread(cfg, open, requests_rx, replies_tx));
+        let (stream, _) = match replies.recv() {
+            Ok(opened) => opened?,
+            Err(_) => bail!("audio thread exited before opening a stream"),
+        };
+        Ok(Self {
+            requests,
+            replies,
+            thread: Some(thread),
+            features: Arc::clone(&stream.features),
+            snapshot: Arc::clone(&stream.snapshot),
+            stream: Some(stream),
+            next_attempt: None,
+            reopening: false,
+            outages: 0,
+        })
+    }
+
+    /// Features of the current stream; re-fetch after `Reconnected`.
+    pub fn features(&self) -> Arc<AtomicAudioFeatures> {
+        Arc::clone(&self.features)
+    }
+
+    pub fn snapshot(&self) -> Arc<AtomicAudioSnapshot> {
+        Arc::clone(&self.snapshot)
+    }
+
+    /// Error slot of the running stream, if there is one.
+    pub fn stream_fault(&self) -> Option<StreamFault> {
+        self.stream.as_ref().map(|s| s.fault.clone())
+    }
+
+    /// True between a `Lost` and the next `Reconnected`.
+    pub fn is_down(&self) -> bool {
+        self.stream.is_none()
+    }
+
+    /// True while a reopen attempt is running on the audio thread.
+    pub fn is_reopening(&self) -> bool {
+        self.reopening
+    }
+
+    /// Number of times the stream has been lost so far.
+    pub fn outages(&self) -> u32 {
+        self.outages
+    }
+
+    /// Checks the stream once per frame and drives reconnection. Never blocks:
+    /// reopen attempts run on the audio thread and are picked up by a later poll.
+    pub fn poll(&mut self, now: Instant) -> Option<SupervisorEvent> {
+        if let Some(stream) = self.stream.as_ref() {
+            let reason = stream.check_fault(STALL_AFTER)?;
+            // Close the old stream first so the device is free to reopen.
+            self.stream = None;
+            let _ = self.requests.send(AudioRequest::Close);
+            self.outages += 1;
+            self.next_attempt = Some(now + REOPEN_DELAY);
+            return Some(SupervisorEvent::Lost(reason));
+        }
+        if self.reopening {
+            match self.replies.try_recv() {
+                Ok(Ok((stream, device))) => {
+                    self.features = Arc::clone(&stream.features);
+                    self.snapshot = Arc::clone(&stream.snapshot);
+                    self.stream = Some(stream);
+                    self.reopening = false;
+                    self.next_attempt = None;
+                    return Some(SupervisorEvent::Reconnected(device));
+                }
+                Ok(Err(err)) => {
+                    self.reopening = false;
+                    return Some(SupervisorEvent::ReconnectFailed(format!("{err:#}")));
+                }
+                // Still opening, or the audio thread is gone (an opener panicked);
+                // either way there is nothing to retry yet.
+                Err(_) => return None,
+            }
+        }
+        if self.next_attempt.is_some_and(|t| now < t) {
+            return None;
+        }
+        self.next_attempt = Some(now + REOPEN_INTERVAL);
+        self.reopening = self.requests.send(AudioRequest::Reopen).is_ok();
+        None
+    }
+}
+
+impl Drop for AudioSupervisor {
+    fn drop(&mut self) {
+        let _ = self.requests.send(AudioRequest::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Body of the supervisor's audio thread. Every `AudioSystem` is created and
+/// dropped here, since capture streams can't move between threads.
+fn run_audio_thread(
+    cfg: Config,
+    open: AudioOpener,
+    requests: mpsc::Receiver<AudioRequest>,
+    replies: mpsc::Sender<Opened>,
+) {
+    let audio = match open(&cfg) {
+        Ok(audio) => audio,
+        Err(err) => {
+            let _ = replies.send(Err(err));
+            return;
+        }
+    };
+    if replies.send(Ok((audio.handle(), String::new()))).is_err() {
+        return;
+    }
+    let mut cfg = cfg;
+    // Reopen by name: a device index can point at another input once something
+    // is unplugged and the rest shift down.
+    if let Some(name) = audio.device_name() {
+        cfg.device = Some(name.to_string());
+    }
+    let mut audio = Some(audio);
+    let mut recorder = None;
+    // Reopened streams continue the recording moved off the old one instead of
+    // truncating the file.
+    cfg.record_features = None;
+
+    while let Ok(request) = requests.recv() {
+        match request {
+            AudioRequest::Close => {
+                if let Some(old) = audio.take() {
+                    recorder = old.take_recorder();
+                }
+            }
+            AudioRequest::Reopen => {
+                let opened = reopen(open, &cfg).map(|(new, device)| {
+                    new.attach_recorder(recorder.take());
+                    let handle = new.handle();
+                    audio = Some(new);
+                    (handle, device)
+                });
+                if replies.send(opened).is_err() {
+                    return;
+                }
+            }
+            AudioRequest::Shutdown => return,
+        }
+    }
+}
+
+fn reopen(open: AudioOpener, cfg: &Config) -> anyhow::Result<(AudioSystem, String)> {
+    const DEFAULT: &str = "default device";
+    let err = match open(cfg) {
+        Ok(audio) => {
+            let device = cfg.device.as_deref().unwrap_or(DEFAULT);
+            return Ok((audio, device.to_string()));
+        }
+        Err(err) => err,
+    };
+    // Already on the default device; nothing else to fall back to.
+    if cfg.device.is_none() {
+        return Err(err);
+    }
+    // A `--capture-channels` subset was picked for the named device and may not
+    // exist on the default one.
+    let mut fallback = cfg.clone();
+    fallback.device = None;
+    fallback.capture.capture_channels = None;
+    match open(&fallback) {
+        Ok(audio) => Ok((audio, DEFAULT.to_string())),
+        Err(fallback_err) => Err(anyhow!("{err:#}; {DEFAULT}: {fallback_err:#}")),
+    }
+}
+
+fn select_mic_input_device(
+    host: &cpal::Host,
+    device_query: Option<&str>,
+) -> anyhow::Result<cpal::Device> {
+    let devices = host
+        .input_devices()
+        .context("enumerate input devices")?
+        .collect::<Vec<_>>();
+
+    let want = device_query.map(|s| s.to_lowercase());
+    if let Some(index) = want.as_deref().and_then(|w| w.parse::<usize>().ok()) {
+        let count = devices.len();
+        return devices
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| anyhow!("no input device at index {index} ({count} found)"));
+    }
+    if let Some(want) = want.as_deref() {
+        // An exact name wins over a substring, so reopening by the full name
+        // finds the same device.
+        let name_is = |d: &&cpal::Device, exact: bool| {
+            d.name()
+                .map(|n| n.to_lowercase())
+                .is_ok_and(|n| if exact { n == want } else { n.contains(want) })
+        };
+        if let Some(dev) = devices
+            .iter()
+            .find(|d| name_is(d, true))
+            .or_else(|| devices.iter().find(|d| name_is(d, false)))
+        {
+            return Ok(dev.clone());
+        }
+        return Err(anyhow!("no input device matching: {want}"));
+    }
+
+    host.default_input_device()
+        .ok_or_else(|| anyhow!("no default input device found"))
+}
+
+/// Picks the stream config for `--source mic`: the device default unless the
+/// requested rate or channels rule it out, then the closest supported config
+/// (same sample format as the default first, then the fewest channels).
+fn capture_config(
+    default: SupportedStreamConfig,
+    ranges: &[SupportedStreamConfigRange],
+    capture: &CaptureArgs,
+) -> anyhow::Result<SupportedStreamConfig> {
+    let need = capture
+        .capture_channels
+        .as_ref()
+        .map_or(1, ChannelSelection::min_channels);
+    let rate = capture
+        .capture_rate
+        .map_or(default.sample_rate(), SampleRate);
+    if default.channels() >= need && default.sample_rate() == rate {
+        return Ok(default);
+    }
+    ranges
+        .iter()
+        .filter(|r| {
+            r.channels() >= need
+                && matches!(
+                    r.sample_format(),
+                    SampleFormat::F32 | SampleFormat::I16 | SampleFormat::U16
+                )
+                && (r.min_sample_rate()..=r.max_sample_rate()).contains(&rate)
+        })
+        .min_by_key(|r| (r.sample_format() != default.sample_format(), r.channels()))
+        .map(|r| r.with_sample_rate(rate))
+        .ok_or_else(|| {
+            anyhow!(
+                "input device has no {} Hz config with {need}+ channels (see --list-devices)",
+                rate.0
+            )
+        })
+}
+
+fn capture_buffer_size(
+    supported: &SupportedBufferSize,
+    frames: Option<u32>,
+) -> anyhow::Result<BufferSize> {
+    let Some(frames) = frames else {
+        return Ok(BufferSize::Default);
+    };
+    if let SupportedBufferSize::Range { min, max } = *supported
+        && !(min..=max).contains(&frames)
+    {
+        bail!("--capture-buffer {frames} is outside the device's {min}-{max} frames");
+    }
+    Ok(BufferSize::Fixed(frames))
+}
+
+/// Like `push_interleaved`, but folds only the `select`ed channels (all when empty).
+fn push_selected<T: Sample<Float = f32> + Copy>(
+    data: &[T],
+    channels: usize,
+    select: &[u16],
+    prod: &mut ringbuf::HeapProd<[f32; 2]>,
+) {
+    if select.is_empty() {
+        return push_interleaved(data, channels, prod);
+    }
+    for frame in data.chunks(channels) {
+        let picked = select
+            .iter()
+            .filter_map(|&c| frame.get(c as usize))
+            .map(|s| s.to_float_sample());
+        let _ = prod.try_push(fold_stereo(picked));
+    }
+}
+
+fn push_interleaved<T: Sample<Float = f32> + Copy>(
+    data: &[T],
+    channels: usize,
+    prod: &mut ringbuf::HeapProd<[f32; 2]>,
+) {
+    for frame in data.chunks(channels) {
+        let _ = prod.try_push(fold_stereo(frame.iter().map(|s| s.to_float_sample())));
+    }
+}
+
+/// Folds one multichannel frame to `[left, right]`. Mono is duplicated to both
+/// sides; beyond two channels, even-indexed channels average into the left and
+/// odd-indexed ones into the right.
+fn fold_stereo(samples: impl Iterator<Item = f32>) -> [f32; 2] {
+    let mut acc = [0.0f32; 2];
+    let mut counts = [0u32; 2];
+    for (i, s) in samples.enumerate() {
+        acc[i & 1] += s;
+        counts[i & 1] += 1;
+    }
+    let left = acc[0] / counts[0].max(1) as f32;
+    if counts[1] == 0 {
+        return [left, left];
+    }
+    [left, acc[1] / counts[1] as f32]
+}
+
+/// Streams pre-decoded stereo frames into the ring buffer at wall-clock pace so
+/// the analyzer sees the same arrival timing as a live capture.
+fn feed_samples_realtime(
+    samples: &[[f32; 2]],
+    sample_rate_hz: u32,
+    looped: bool,
+    stop: &AtomicBool,
+    prod: &mut ringbuf::HeapProd<[f32; 2]>,
+) {
+    let start = Instant::now();
+    let mut pushed = 0u64;
+    let mut pos = 0usize;
+
+    while !stop.load(Ordering::Relaxed) {
+        let due = (start.elapsed().as_secs_f64() * sample_rate_hz as f64) as u64;
+        while pushed < due {
+            if pos >= samples.len() {
+                if !looped {
+                    return;
+                }
+                pos = 0;
+            }
+            let _ = prod.try_push(samples[pos]);
+            pos += 1;
+            pushed += 1;
+        }
+        thread::sleep(Duration::from_millis(2));
+    }
+}
+
+/// Decodes interleaved little-endian PCM from `reader` and pushes whole frames
+/// through `push_interleaved`. Partial frames are carried over between reads.
+fn read_pcm_stream(
+    mut reader: impl Read,
+    format: PcmFormat,
+    channels: usize,
+    stop: &AtomicBool,
+    prod: &mut ringbuf::HeapProd<[f32; 2]>,
+) {
+    let frame_bytes = format.bytes_per_sample() * channels;
+    let mut buf = vec![0u8; frame_bytes * 1024];
+    let mut filled = 0usize;
+    let mut f32_frames = Vec::<f32>::new();
+    let mut i16_frames = Vec::<i16>::new();
+
+    while !stop.load(Ordering::Relaxed) {
+        let n = match reader.read(&mut buf[filled..]) {
+            Ok(0) => return,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return,
+        };
+        filled += n;
+        let usable = filled - filled % frame_bytes;
+
+        match format {
+            PcmFormat::F32le => {
+                f32_frames.clear();
+                f32_frames.extend(
+                    buf[..usable]
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                );
+                push_interleaved(&f32_frames, channels, prod);
+            }
+            PcmFormat::S16le => {
+                i16_frames.clear();
+                i16_frames.extend(
+                    buf[..usable]
+                        .chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]])),
+                );
+                push_interleaved(&i16_frames, channels, prod);
+            }
+        }
+
+        buf.copy_within(usable..filled, 0);
+        filled -= usable;
+    }
+}
+
+/// Reads a PCM16 or Float32 RIFF/WAVE file and downmixes it to mono.
+pub fn read_wav_mono_f32(path: &Path) -> anyhow::Result<(u32, Vec<f32>)> {
+    let (sample_rate_hz, frames) = read_wav_stereo_f32(path)?;
+    Ok((
+        sample_rate_hz,
+        frames.iter().map(|[l, r]| (l + r) * 0.5).collect(),
+    ))
+}
+
+/// Reads a PCM16 or Float32 RIFF/WAVE file as `[left, right]` frames (see
+/// `fold_stereo` for mono and multichannel files).
+pub fn read_wav_stereo_f32(path: &Path) -> anyhow::Result<(u32, Vec<[f32; 2]>)> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < 44 {
+        bail!("wav too small");
+    }
+    if &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        bail!("not a RIFF/WAVE file");
+    }
+
+    let mut fmt_audio_format = 0u16;
+    let mut fmt_channels = 0u16;
+    let mut fmt_sample_rate = 0u32;
+    let mut fmt_bits = 0u16;
+    let mut data: Option<&[u8]> = None;
+
+    let mut pos = 12usize;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size =
+            u32::from_le_bytes([bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]])
+                as usize;
+        let start = pos + 8;
+        let end = start.saturating_add(size);
+        if end > bytes.len() {
+            break;
+        }
+
+        if id == b"fmt " {
+            if size < 16 {
+                bail!("invalid fmt chunk");
+            }
+            fmt_audio_format = u16::from_le_bytes([bytes[start], bytes[start + 1]]);
+            fmt_channels = u16::from_le_bytes([bytes[start + 2], bytes[start + 3]]);
+            fmt_sample_rate = u32::from_le_bytes([
+                bytes[start + 4],
+                bytes[start + 5],
+                bytes[start + 6],
+                bytes[start + 7],
+            ]);
+            fmt_bits = u16::from_le_bytes([bytes[start + 14], bytes[start + 15]]);
+        } else if id == b"data" {
+            data = Some(&bytes[start..end]);
+        }
+
+        pos = end + (size % 2);
+    }
+
+    let data = data.context("missing data chunk")?;
+    if fmt_channels == 0 {
+        bail!("invalid channel count");
+    }
+    if fmt_sample_rate == 0 {
+        bail!("invalid sample rate");
+    }
+
+    let ch = fmt_channels as usize;
+    match (fmt_audio_format, fmt_bits) {
+        (1, 16) => {
+            let frames = data.len() / 2 / ch;
+            let mut out = Vec::<[f32; 2]>::with_capacity(frames);
+            for frame in data.chunks_exact(2 * ch).take(frames) {
+                let lr = fold_stereo(
+                    frame
+                        .chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0),
+                );
+                out.push(lr.map(|s| s.clamp(-1.0, 1.0)));
+            }
+            Ok((fmt_sample_rate, out))
+        }
+        (3, 32) => {
+            let frames = data.len() / 4 / ch;
+            let mut out = Vec::<[f32; 2]>::with_capacity(frames);
+            for frame in data.chunks_exact(4 * ch).take(frames) {
+                let lr = fold_stereo(
+                    frame
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                );
+                out.push(lr.map(|s| s.clamp(-1.0, 1.0)));
+            }
+            Ok((fmt_sample_rate, out))
+        }
+        _ => bail!(
+            "unsupported wav format: audio_format={} bits={} (supported: PCM16, Float32)",
+            fmt_audio_format,
+            fmt_bits
+        ),
+    }
+}
+
+#[cfg(target_os = "macos")]
+struct SystemAudioHandler {
+    prod: std::sync::Mutex<ringbuf::HeapProd<[f32; 2]>>,
+}
 
 #[cfg(target_os = "macos")]
 impl SCStreamOutputTrait for SystemAudioHandler {
@@ -433,7 +2109,7 @@ fn start_system_audio_stream(handler: impl SCStreamOutputTrait + 'static) -> any
 }
 
 #[cfg(target_os = "macos")]
-fn push_audio_f32(list: &AudioBufferList, channels: usize, prod: &mut ringbuf::HeapProd<f32>) {
+fn push_audio_f32(list: &AudioBufferList, channels: usize, prod: &mut ringbuf::HeapProd<[f32; 2]>) {
     if list.num_buffers() == 1 {
         if let Some(buf) = list.get(0) {
             let data = buf.data();
@@ -447,18 +2123,14 @@ fn push_audio_f32(list: &AudioBufferList, channels: usize, prod: &mut ringbuf::H
             let ch = (buf.number_channels as usize).max(1);
             let ch = ch.max(channels);
             for frame in samples.chunks_exact(ch) {
-                let mut acc = 0.0f32;
-                for s in frame.iter().take(channels) {
-                    acc += *s;
-                }
-                let mono = (acc / channels as f32).clamp(-1.0, 1.0);
-                let _ = prod.try_push(mono);
+                let lr = fold_stereo(frame.iter().take(channels).copied());
+                let _ = prod.try_push(lr.map(|s| s.clamp(-1.0, 1.0)));
             }
         }
         return;
     }
 
-    // Planar: one buffer per channel (common), or multiple buffers we average.
+    // Planar: one buffer per channel (common), folded to stereo like interleaved data.
     let mut chans: Vec<&[f32]> = Vec::new();
     for buf in list.iter() {
         let data = buf.data();
@@ -478,238 +2150,919 @@ fn push_audio_f32(list: &AudioBufferList, channels: usize, prod: &mut ringbuf::H
     }
     let frames = chans.iter().map(|c| c.len()).min().unwrap_or(0);
     for i in 0..frames {
-        let mut acc = 0.0f32;
-        let mut k = 0u32;
-        for c in chans.iter().take(channels) {
-            acc += c[i];
-            k += 1;
+        let lr = fold_stereo(chans.iter().take(channels).map(|c| c[i]));
+        let _ = prod.try_push(lr.map(|s| s.clamp(-1.0, 1.0)));
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn push_audio_i16(list: &AudioBufferList, channels: usize, prod: &mut ringbuf::HeapProd<[f32; 2]>) {
+    if list.num_buffers() == 1 {
+        if let Some(buf) = list.get(0) {
+            let data = buf.data();
+            if data.is_empty() {
+                return;
+            }
+            let n = data.len() / 2;
+            debug_assert!(data.as_ptr().align_offset(std::mem::align_of::<i16>()) == 0);
+            let samples: &[i16] = unsafe { std::slice::from_raw_parts(data.as_ptr().cast(), n) };
+
+            let ch = (buf.number_channels as usize).max(1);
+            let ch = ch.max(channels);
+            for frame in samples.chunks_exact(ch) {
+                let lr = fold_stereo(frame.iter().take(channels).map(|s| *s as f32 / 32768.0));
+                let _ = prod.try_push(lr.map(|s| s.clamp(-1.0, 1.0)));
+            }
+        }
+        return;
+    }
+
+    let mut chans: Vec<&[i16]> = Vec::new();
+    for buf in list.iter() {
+        let data = buf.data();
+        if data.is_empty() {
+            continue;
         }
-        if k == 0 {
+        let n = data.len() / 2;
+        debug_assert!(data.as_ptr().align_offset(std::mem::align_of::<i16>()) == 0);
+        let samples: &[i16] = unsafe { std::slice::from_raw_parts(data.as_ptr().cast(), n) };
+        chans.push(samples);
+        if chans.len() >= channels {
+            break;
+        }
+    }
+    if chans.is_empty() {
+        return;
+    }
+    let frames = chans.iter().map(|c| c.len()).min().unwrap_or(0);
+    for i in 0..frames {
+        let lr = fold_stereo(chans.iter().take(channels).map(|c| c[i] as f32 / 32768.0));
+        let _ = prod.try_push(lr.map(|s| s.clamp(-1.0, 1.0)));
+    }
+}
+
+fn analyze_loop(
+    cons: &mut ringbuf::HeapCons<[f32; 2]>,
+    analyzer: &mut Analyzer,
+    recorder: &RecorderSlot,
+    stop: &AtomicBool,
+    features: &AtomicAudioFeatures,
+    snapshot: &AtomicAudioSnapshot,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        let mut got_any = false;
+        while let Some(frame) = cons.try_pop() {
+            got_any = true;
+            if let Some(f) = analyzer.push_frame(frame) {
+                features.store(f);
+                record_frame(recorder, &f);
+                snapshot.store(analyzer.spectrum(), analyzer.waveform(), analyzer.side());
+            }
+        }
+
+        if !got_any {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+    flush_recorder(recorder);
+}
+
+/// The `--record-features` log, shared by the `AudioSystem` and the thread
+/// that writes it so the supervisor can move it onto a reopened stream.
+type RecorderSlot = Arc<Mutex<Option<FeatureRecorder>>>;
+
+fn open_recorder(path: Option<&Path>, sample_rate_hz: u32) -> anyhow::Result<RecorderSlot> {
+    let recorder = path
+        .map(|p| FeatureRecorder::create(p, sample_rate_hz))
+        .transpose()?;
+    Ok(Arc::new(Mutex::new(recorder)))
+}
+
+/// Appends `f` to the recording, if any. A failed write (disk full, file
+/// removed) ends the recording rather than the audio thread.
+fn record_frame(recorder: &RecorderSlot, f: &AudioFeatures) {
+    let mut slot = recorder.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(rec) = slot.as_mut()
+        && rec.record(f).is_err()
+    {
+        *slot = None;
+    }
+}
+
+fn flush_recorder(recorder: &RecorderSlot) {
+    if let Some(rec) = recorder.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        let _ = rec.flush();
+    }
+}
+
+/// Stores recorded frames into `features` on their original timeline.
+fn replay_features(
+    frames: &[(u64, AudioFeatures)],
+    looped: bool,
+    stop: &AtomicBool,
+    features: &AtomicAudioFeatures,
+    recorder: &RecorderSlot,
+) {
+    let mut start = Instant::now();
+    let mut pos = 0usize;
+    while !stop.load(Ordering::Relaxed) {
+        if pos >= frames.len() {
+            if !looped {
+                break;
+            }
+            pos = 0;
+            start = Instant::now();
+        }
+        let (t_us, f) = frames[pos];
+        let due = Duration::from_micros(t_us);
+        let elapsed = start.elapsed();
+        if elapsed < due {
+            thread::sleep((due - elapsed).min(Duration::from_millis(2)));
+            continue;
+        }
+        features.store(f);
+        record_frame(recorder, &f);
+        pos += 1;
+    }
+    flush_recorder(recorder);
+}
+
+/// Streaming feature extractor behind every audio source. Feed it stereo frames
+/// in chunks of any size, live or offline, and it produces one `AudioFeatures`
+/// per hop; the same input and config always yield the same features.
+pub struct Analyzer {
+    cfg: AnalyzerConfig,
+    sample_rate_hz: u32,
+    layout: AnalysisLayout,
+    fft: Arc<dyn rustfft::Fft<f32>>,
+    fft_buf: Vec<Complex<f32>>,
+    mags: Vec<f32>,
+    prev_mags: Vec<f32>,
+
+    // `scratch` holds the mid (mono) signal every mono feature is computed from;
+    // the per-channel windows only feed the stereo features.
+    scratch: Vec<f32>,
+    left: Vec<f32>,
+    right: Vec<f32>,
+    write_pos: usize,
+    filled: usize,
+    since_last: usize,
+
+    flux_avg: f32,
+    flux_hist: [f32; 3],
+    rms_s: f32,
+    bands_s: [f32; MAX_BANDS],
+    centroid_s: f32,
+    flatness_s: f32,
+    rms_lr_s: [f32; 2],
+    width_s: f32,
+    spectrum_raw: Vec<f32>,
+    spectrum: Vec<f32>,
+    waveform: Vec<f32>,
+    side: Vec<f32>,
+    agc: Agc,
+    tempo: TempoTracker,
+    drums: [DrumDetector; 3],
+    peaks: Vec<Peak>,
+    chroma_s: [f32; 12],
+    key: KeyTracker,
+    loudness: LoudnessMeter,
+    /// Consecutive hops whose raw RMS sat below `silence_rms`.
+    quiet_hops: usize,
+    silence_hops: usize,
+    silence_rms: f32,
+    features: AudioFeatures,
+}
+
+impl Analyzer {
+    pub fn new(cfg: AnalyzerConfig, sample_rate_hz: u32) -> anyhow::Result<Self> {
+        cfg.validate()?;
+        if sample_rate_hz == 0 {
+            bail!("sample rate must be > 0");
+        }
+        let n = cfg.fft_size;
+        let hop = cfg.hop;
+        let frame_rate = sample_rate_hz as f32 / hop as f32;
+        let bins = cfg.spectrum_bins.clamp(MIN_SPECTRUM_BINS, MAX_SPECTRUM_BINS);
+        let mut planner = FftPlanner::<f32>::new();
+        Ok(Self {
+            layout: AnalysisLayout::new(&cfg, sample_rate_hz),
+            fft: planner.plan_fft_forward(n),
+            fft_buf: vec![Complex { re: 0.0, im: 0.0 }; n],
+            mags: vec![0.0; n / 2],
+            prev_mags: vec![0.0; n / 2],
+            scratch: vec![0.0; n],
+            left: vec![0.0; n],
+            right: vec![0.0; n],
+            write_pos: 0,
+            filled: 0,
+            since_last: 0,
+            flux_avg: 0.0,
+            flux_hist: [0.0; 3],
+            rms_s: 0.0,
+            bands_s: [0.0; MAX_BANDS],
+            centroid_s: 0.0,
+            flatness_s: 0.0,
+            rms_lr_s: [0.0; 2],
+            width_s: 0.0,
+            spectrum_raw: vec![0.0; bins],
+            spectrum: vec![0.0; bins],
+            waveform: vec![0.0; n],
+            side: vec![0.0; n],
+            agc: Agc::new(cfg.agc, frame_rate),
+            tempo: TempoTracker::new(frame_rate),
+            drums: std::array::from_fn(|_| DrumDetector::new(hop as f32 / sample_rate_hz as f32)),
+            peaks: Vec::with_capacity(n / 4),
+            chroma_s: [0.0; 12],
+            key: KeyTracker::new(frame_rate),
+            loudness: LoudnessMeter::new(sample_rate_hz, hop),
+            quiet_hops: 0,
+            silence_hops: (cfg.silence_hold_s * frame_rate).ceil() as usize,
+            silence_rms: 10f32.powf(cfg.silence_threshold_db / 20.0),
+            features: AudioFeatures::default(),
+            cfg,
+            sample_rate_hz,
+        })
+    }
+
+    pub fn config(&self) -> &AnalyzerConfig {
+        &self.cfg
+    }
+
+    pub fn sample_rate_hz(&self) -> u32 {
+        self.sample_rate_hz
+    }
+
+    /// Appends one `[left, right]` frame. Returns the new features when the
+    /// frame completes a hop, `None` otherwise.
+    pub fn push_frame(&mut self, [l, r]: [f32; 2]) -> Option<AudioFeatures> {
+        let n = self.scratch.len();
+        self.scratch[self.write_pos] = (l + r) * 0.5;
+        self.left[self.write_pos] = l;
+        self.right[self.write_pos] = r;
+        self.loudness.push([l, r]);
+        self.write_pos = (self.write_pos + 1) % n;
+        if self.filled < n {
+            self.filled += 1;
+        }
+        self.since_last += 1;
+        if self.filled < n || self.since_last < self.cfg.hop {
+            return None;
+        }
+        self.since_last = 0;
+        self.analyze();
+        Some(self.features)
+    }
+
+    /// Feeds a block of frames, calling `on_hop` with the analyzer after every
+    /// completed hop (so the spectrum and waveform can be read alongside).
+    pub fn process(&mut self, frames: &[[f32; 2]], mut on_hop: impl FnMut(&Self)) {
+        for &frame in frames {
+            if self.push_frame(frame).is_some() {
+                on_hop(self);
+            }
+        }
+    }
+
+    /// Features from the most recent hop (defaults before the first one).
+    pub fn features(&self) -> AudioFeatures {
+        self.features
+    }
+
+    /// Log-spaced spectrum (`spectrum_bins` values, 0..1) from the most recent hop.
+    pub fn spectrum(&self) -> &[f32] {
+        &self.spectrum
+    }
+
+    /// Mid signal of the most recent window, oldest sample first.
+    pub fn waveform(&self) -> &[f32] {
+        &self.waveform
+    }
+
+    /// Side signal (`(l - r) / 2`) of the most recent window, oldest sample first.
+    pub fn side(&self) -> &[f32] {
+        &self.side
+    }
+
+    fn analyze(&mut self) {
+        let n = self.scratch.len();
+        let sample_rate_hz = self.sample_rate_hz;
+        let (rms, bands, flux, drum_flux, centroid, flatness) = analyze_window(
+            &self.scratch,
+            self.write_pos,
+            &self.layout,
+            &self.fft,
+            &mut self.fft_buf,
+            &mut self.mags,
+            &mut self.prev_mags,
+            sample_rate_hz,
+        );
+        // Silence is judged on the raw level, before AGC lifts the noise floor.
+        let quiet = rms < self.silence_rms;
+        self.quiet_hops = if quiet { self.quiet_hops.saturating_add(1) } else { 0 };
+        let (rms, bands, flux, gain) = self.agc.apply(rms, bands, flux, quiet);
+
+        let flux_hist = &mut self.flux_hist;
+        flux_hist[0] = flux_hist[1];
+        flux_hist[1] = flux_hist[2];
+        flux_hist[2] = flux;
+        self.flux_avg = self.flux_avg * 0.95 + flux * 0.05;
+
+        // Peak detection with 1-step latency.
+        let peak = flux_hist[1] > flux_hist[0] && flux_hist[1] > flux_hist[2];
+        let thr = (self.flux_avg * 1.45).max(1e-6);
+        let beat = peak && flux_hist[1] > thr;
+        let beat_strength = ((flux_hist[1] - thr) / (thr + 1e-6)).clamp(0.0, 1.0);
+
+        self.rms_s = self.rms_s * 0.85 + rms * 0.15;
+        for (s, b) in self.bands_s.iter_mut().zip(bands) {
+            *s = *s * 0.85 + b * 0.15;
+        }
+        self.centroid_s = self.centroid_s * 0.9 + centroid * 0.1;
+        self.flatness_s = self.flatness_s * 0.9 + flatness * 0.1;
+
+        let (bpm, beat_phase, bar_phase) = self.tempo.push(flux, bands[0] + bands[1]);
+        let drums = &mut self.drums;
+        let [kick, snare, hat] = std::array::from_fn(|i| drums[i].push(drum_flux[i]));
+
+        let (rms_lr, width) = stereo_window(&self.left, &self.right);
+        for (s, v) in self.rms_lr_s.iter_mut().zip(rms_lr) {
+            *s = *s * 0.85 + (v * gain).min(1.0) * 0.15;
+        }
+        self.width_s = self.width_s * 0.9 + width * 0.1;
+        let [rms_left, rms_right] = self.rms_lr_s;
+        let balance = if rms_left + rms_right > 1e-4 {
+            ((rms_right - rms_left) / (rms_right + rms_left)).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+
+        tonal_peaks(&self.mags, &self.layout, &mut self.peaks);
+        let chroma = chroma_from_peaks(&self.peaks);
+        for (s, c) in self.chroma_s.iter_mut().zip(chroma) {
+            *s = *s * 0.85 + c * 0.15;
+        }
+        // At very low sample rates the tonal range lies past the last bin.
+        let (lo, hi) = self.layout.tonal_bins;
+        let mean_mag = if hi > lo + 1 {
+            self.mags[lo..hi].iter().sum::<f32>() / (hi - lo) as f32
+        } else {
+            0.0
+        };
+        let pitch_hz = dominant_pitch(&mut self.peaks, mean_mag);
+        let (key, key_minor, key_confidence, key_change) = self.key.push(&chroma);
+        let (lufs_momentary, lufs_short_term, crest_db) = self.loudness.finish_block();
+
+        self.features = AudioFeatures {
+            rms: self.rms_s,
+            bands: self.bands_s,
+            band_count: self.layout.band_bins.len(),
+            onset: flux,
+            beat,
+            beat_strength,
+            centroid: self.centroid_s,
+            flatness: self.flatness_s,
+            bpm,
+            beat_phase,
+            bar_phase,
+            kick,
+            snare,
+            hat,
+            rms_left,
+            rms_right,
+            balance,
+            stereo_width: self.width_s,
+            gain,
+            chroma: self.chroma_s,
+            pitch_hz,
+            key,
+            key_minor,
+            key_confidence,
+            key_change,
+            lufs_momentary,
+            lufs_short_term,
+            crest_db,
+            silent: self.quiet_hops > 0 && self.quiet_hops >= self.silence_hops,
+        };
+
+        // Bars jump up immediately and fall back smoothly.
+        log_spectrum_bins(&self.mags, sample_rate_hz, n, &mut self.spectrum_raw);
+        for (s, &v) in self.spectrum.iter_mut().zip(&self.spectrum_raw) {
+            *s = if v > *s { v } else { *s * 0.82 + v * 0.18 };
+        }
+        for (i, (w, sd)) in self.waveform.iter_mut().zip(self.side.iter_mut()).enumerate() {
+            let k = (self.write_pos + i) % n;
+            *w = self.scratch[k];
+            *sd = (self.left[k] - self.right[k]) * 0.5;
+        }
+    }
+}
+
+/// Fixed band gain ahead of the `tanh` compression; the AGC's reference point.
+const BAND_GAIN: f32 = 0.01;
+/// Time constant (seconds) for the AGC peak followers to release.
+const AGC_RELEASE_S: f32 = 6.0;
+const AGC_MIN_GAIN: f32 = 1.0 / 16.0;
+const AGC_MAX_GAIN: f32 = 32.0;
+
+/// Automatic gain control for bands, RMS and onset. Each channel keeps a peak
+/// follower (instant attack, slow release) and is scaled so recent peaks land on
+/// the same point of its curve whatever the input level. The followers hold
+/// while the input is below the silence threshold, so a pause doesn't ramp the
+/// gain up until the noise floor reads as signal. With AGC off the fixed curves
+/// are used unchanged.
+struct Agc {
+    enabled: bool,
+    release: f32,
+    band_peaks: [f32; MAX_BANDS],
+    rms_peak: f32,
+    flux_peak: f32,
+}
+
+impl Agc {
+    fn new(enabled: bool, frame_rate: f32) -> Self {
+        Self {
+            enabled,
+            release: (-1.0 / (AGC_RELEASE_S * frame_rate.max(1.0))).exp(),
+            band_peaks: [0.0; MAX_BANDS],
+            rms_peak: 0.0,
+            flux_peak: 0.0,
+        }
+    }
+
+    /// Tracks `x` (unless `hold`) and returns the gain that maps its recent peak
+    /// onto `target` (both in units of the fixed curve).
+    fn follow(peak: &mut f32, x: f32, release: f32, target: f32, hold: bool) -> f32 {
+        if !hold {
+            *peak = x.max(*peak * release);
+        }
+        (target / peak.max(1e-9)).clamp(AGC_MIN_GAIN, AGC_MAX_GAIN)
+    }
+
+    /// Takes linear RMS, band means and onset flux and returns the compressed
+    /// 0..1 features plus the RMS gain applied. `quiet` holds the followers.
+    fn apply(
+        &mut self,
+        rms: f32,
+        bands: [f32; MAX_BANDS],
+        flux: f32,
+        quiet: bool,
+    ) -> (f32, [f32; MAX_BANDS], f32, f32) {
+        if !self.enabled {
+            return (rms, bands.map(|b| (b * BAND_GAIN).tanh()), flux.tanh(), 1.0);
+        }
+        let release = self.release;
+        let rms_gain = Self::follow(&mut self.rms_peak, rms, release, 0.5, quiet);
+        let flux_gain = Self::follow(&mut self.flux_peak, flux, release, 1.2, quiet);
+        let bands = std::array::from_fn(|i| {
+            let b = bands[i] * BAND_GAIN;
+            let g = Self::follow(&mut self.band_peaks[i], b, release, 1.2, quiet);
+            (b * g).tanh()
+        });
+        ((rms * rms_gain).min(1.0), bands, (flux * flux_gain).tanh(), rms_gain)
+    }
+}
+
+/// Per-channel RMS and side/mid width of one analysis window.
+fn stereo_window(left: &[f32], right: &[f32]) -> ([f32; 2], f32) {
+    let mut acc = [0.0f32; 4];
+    for (&l, &r) in left.iter().zip(right) {
+        let (mid, side) = ((l + r) * 0.5, (l - r) * 0.5);
+        acc[0] += l * l;
+        acc[1] += r * r;
+        acc[2] += mid * mid;
+        acc[3] += side * side;
+    }
+    let n = left.len().min(right.len()).max(1) as f32;
+    let [l, r, mid, side] = acc.map(|a| (a / n).sqrt().clamp(0.0, 1.0));
+    let width = if mid + side > 1e-4 { side / (mid + side) } else { 0.0 };
+    ([l, r], width)
+}
+
+/// Window coefficients and band bin ranges precomputed from an `AnalyzerConfig`.
+struct AnalysisLayout {
+    window: Vec<f32>,
+    /// Scales magnitudes to what a 1024-point Hann window would report, so the
+    /// fixed feature curves hold for any FFT size and window.
+    mag_norm: f32,
+    /// Fractional FFT bin range `[lo, hi)` per band.
+    band_bins: Vec<(f32, f32)>,
+    hz_per_bin: f32,
+    /// Bin range `[lo, hi)` searched for tonal peaks (chroma and pitch).
+    tonal_bins: (usize, usize),
+}
+
+impl AnalysisLayout {
+    fn new(cfg: &AnalyzerConfig, sample_rate_hz: u32) -> Self {
+        let n = cfg.fft_size;
+        let window = window_coefficients(cfg.window, n);
+        let coherent_gain = window.iter().sum::<f32>() / n as f32;
+        let hz_per_bin = sample_rate_hz as f32 / n as f32;
+        Self {
+            mag_norm: (ANALYZER_WINDOW as f32 / n as f32) * (0.5 / coherent_gain.max(1e-3)),
+            band_bins: cfg
+                .band_edges_hz
+                .windows(2)
+                .take(MAX_BANDS)
+                .map(|e| (e[0] / hz_per_bin, e[1] / hz_per_bin))
+                .collect(),
+            hz_per_bin,
+            tonal_bins: (
+                ((TONAL_RANGE_HZ.0 / hz_per_bin).ceil() as usize).max(1),
+                ((TONAL_RANGE_HZ.1 / hz_per_bin) as usize).min(n / 2 - 1),
+            ),
+            window,
+        }
+    }
+}
+
+/// Frequency range searched for spectral peaks when estimating chroma and pitch.
+const TONAL_RANGE_HZ: (f32, f32) = (100.0, 5000.0);
+/// Fundamentals considered by the pitch estimate.
+const PITCH_RANGE_HZ: (f32, f32) = (60.0, 1500.0);
+/// Peaks below this fraction of the strongest one are ignored.
+const PEAK_FLOOR: f32 = 0.02;
+/// Strongest peaks kept for the pitch search.
+const PITCH_MAX_PEAKS: usize = 24;
+/// A pitch is reported only if its fundamental stands this far above the mean
+/// magnitude of the tonal range.
+const PITCH_SALIENCE: f32 = 6.0;
+
+/// Spectral peak: interpolated frequency (Hz) and magnitude.
+#[derive(Clone, Copy)]
+struct Peak {
+    hz: f32,
+    mag: f32,
+}
+
+/// Local maxima in `mags` over the tonal range, refined by parabolic
+/// interpolation so partials land on the right pitch class even with coarse bins.
+fn tonal_peaks(mags: &[f32], layout: &AnalysisLayout, out: &mut Vec<Peak>) {
+    out.clear();
+    let (lo, hi) = layout.tonal_bins;
+    if hi <= lo + 1 {
+        return;
+    }
+    let max = mags[lo..hi].iter().copied().fold(0.0f32, f32::max);
+    if max <= 1e-6 {
+        return;
+    }
+    for k in lo.max(1)..hi {
+        let (a, b, c) = (mags[k - 1], mags[k], mags[k + 1]);
+        if b <= a || b < c || b < max * PEAK_FLOOR {
             continue;
         }
-        let mono = (acc / k as f32).clamp(-1.0, 1.0);
-        let _ = prod.try_push(mono);
+        let den = a - 2.0 * b + c;
+        let delta = if den.abs() > 1e-12 { (0.5 * (a - c) / den).clamp(-0.5, 0.5) } else { 0.0 };
+        out.push(Peak {
+            hz: (k as f32 + delta) * layout.hz_per_bin,
+            mag: b - 0.25 * (a - c) * delta,
+        });
     }
 }
 
-#[cfg(target_os = "macos")]
-fn push_audio_i16(list: &AudioBufferList, channels: usize, prod: &mut ringbuf::HeapProd<f32>) {
-    if list.num_buffers() == 1 {
-        if let Some(buf) = list.get(0) {
-            let data = buf.data();
-            if data.is_empty() {
-                return;
-            }
-            let n = data.len() / 2;
-            debug_assert!(data.as_ptr().align_offset(std::mem::align_of::<i16>()) == 0);
-            let samples: &[i16] = unsafe { std::slice::from_raw_parts(data.as_ptr().cast(), n) };
+fn pitch_class(hz: f32) -> usize {
+    let midi = 69.0 + 12.0 * (hz / 440.0).log2();
+    (midi.round() as i32).rem_euclid(12) as usize
+}
 
-            let ch = (buf.number_channels as usize).max(1);
-            let ch = ch.max(channels);
-            for frame in samples.chunks_exact(ch) {
-                let mut acc = 0.0f32;
-                for s in frame.iter().take(channels) {
-                    acc += (*s as f32) / 32768.0;
-                }
-                let mono = (acc / channels as f32).clamp(-1.0, 1.0);
-                let _ = prod.try_push(mono);
-            }
+/// Peak magnitude per pitch class, scaled so the strongest class is 1.
+fn chroma_from_peaks(peaks: &[Peak]) -> [f32; 12] {
+    let mut chroma = [0.0f32; 12];
+    for p in peaks {
+        chroma[pitch_class(p.hz)] += p.mag;
+    }
+    let max = chroma.iter().copied().fold(0.0f32, f32::max);
+    if max > 1e-6 {
+        for c in &mut chroma {
+            *c /= max;
         }
-        return;
     }
+    chroma
+}
 
-    let mut chans: Vec<&[i16]> = Vec::new();
-    for buf in list.iter() {
-        let data = buf.data();
-        if data.is_empty() {
+/// Dominant pitch by harmonic summation over the strongest peaks: each candidate
+/// fundamental collects the peaks sitting near its first five harmonics.
+fn dominant_pitch(peaks: &mut [Peak], mean_mag: f32) -> f32 {
+    peaks.sort_unstable_by(|a, b| b.mag.total_cmp(&a.mag));
+    let top = &peaks[..peaks.len().min(PITCH_MAX_PEAKS)];
+    let mut best = (0.0f32, 0.0f32);
+    for f0 in top {
+        if !(PITCH_RANGE_HZ.0..=PITCH_RANGE_HZ.1).contains(&f0.hz) || f0.mag < mean_mag * PITCH_SALIENCE {
             continue;
         }
-        let n = data.len() / 2;
-        debug_assert!(data.as_ptr().align_offset(std::mem::align_of::<i16>()) == 0);
-        let samples: &[i16] = unsafe { std::slice::from_raw_parts(data.as_ptr().cast(), n) };
-        chans.push(samples);
-        if chans.len() >= channels {
-            break;
+        let score = top
+            .iter()
+            .filter_map(|p| {
+                let h = (p.hz / f0.hz).round();
+                let off = (p.hz / (f0.hz * h) - 1.0).abs();
+                ((1.0..=5.0).contains(&h) && off < 0.03).then(|| p.mag / h)
+            })
+            .sum::<f32>();
+        if score > best.1 {
+            best = (f0.hz, score);
         }
     }
-    if chans.is_empty() {
-        return;
+    best.0
+}
+
+/// Time constant (seconds) of the chroma average the key is estimated from.
+const KEY_WINDOW_S: f32 = 8.0;
+/// Profile correlation needed before a first key is reported.
+const KEY_LOCK_MIN: f32 = 0.5;
+/// How much better another key must fit before the estimate moves.
+const KEY_SWITCH_MARGIN: f32 = 0.05;
+const KEY_CHANGE_DECAY_S: f32 = 0.6;
+
+/// Krumhansl-Kessler key profiles, tonic first.
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Slow key/mode estimate: correlates a long-running chroma average against the
+/// 24 rotated key profiles, with hysteresis so the key only moves when another
+/// one fits clearly better.
+struct KeyTracker {
+    avg: [f32; 12],
+    alpha: f32,
+    key: Option<(u8, bool)>,
+    confidence: f32,
+    change: f32,
+    change_decay: f32,
+}
+
+impl KeyTracker {
+    fn new(frame_rate: f32) -> Self {
+        let frame_rate = frame_rate.max(1.0);
+        Self {
+            avg: [0.0; 12],
+            alpha: 1.0 - (-1.0 / (KEY_WINDOW_S * frame_rate)).exp(),
+            key: None,
+            confidence: 0.0,
+            change: 0.0,
+            change_decay: (-1.0 / (KEY_CHANGE_DECAY_S * frame_rate)).exp(),
+        }
     }
-    let frames = chans.iter().map(|c| c.len()).min().unwrap_or(0);
-    for i in 0..frames {
-        let mut acc = 0.0f32;
-        let mut k = 0u32;
-        for c in chans.iter().take(channels) {
-            acc += (c[i] as f32) / 32768.0;
-            k += 1;
+
+    fn correlation(chroma: &[f32; 12], tonic: usize, minor: bool) -> f32 {
+        let profile = if minor { &MINOR_PROFILE } else { &MAJOR_PROFILE };
+        let mx = chroma.iter().sum::<f32>() / 12.0;
+        let my = profile.iter().sum::<f32>() / 12.0;
+        let (mut sxy, mut sxx, mut syy) = (0.0f32, 0.0f32, 0.0f32);
+        for (i, &x) in chroma.iter().enumerate() {
+            let y = profile[(i + 12 - tonic) % 12];
+            sxy += (x - mx) * (y - my);
+            sxx += (x - mx) * (x - mx);
+            syy += (y - my) * (y - my);
         }
-        if k == 0 {
-            continue;
+        if sxx <= 1e-12 { 0.0 } else { sxy / (sxx * syy).sqrt() }
+    }
+
+    /// Folds in one frame's chroma; returns `(key, minor, confidence, change)`.
+    fn push(&mut self, chroma: &[f32; 12]) -> (u8, bool, f32, f32) {
+        self.change *= self.change_decay;
+        // Silent or toneless frames leave the estimate alone.
+        if chroma.iter().sum::<f32>() > 1e-3 {
+            for (a, &c) in self.avg.iter_mut().zip(chroma) {
+                *a += (c - *a) * self.alpha;
+            }
+            let mut best = (0u8, false, f32::MIN);
+            for tonic in 0..12u8 {
+                for minor in [false, true] {
+                    let r = Self::correlation(&self.avg, tonic as usize, minor);
+                    if r > best.2 {
+                        best = (tonic, minor, r);
+                    }
+                }
+            }
+            let (tonic, minor, r) = best;
+            match self.key {
+                None if r >= KEY_LOCK_MIN => self.key = Some((tonic, minor)),
+                Some((k, m)) if (k, m) != (tonic, minor) => {
+                    let current = Self::correlation(&self.avg, k as usize, m);
+                    if r > current + KEY_SWITCH_MARGIN {
+                        self.key = Some((tonic, minor));
+                        self.change = 1.0;
+                    }
+                }
+                _ => {}
+            }
+            if let Some((k, m)) = self.key {
+                self.confidence = Self::correlation(&self.avg, k as usize, m).clamp(0.0, 1.0);
+            }
         }
-        let mono = (acc / k as f32).clamp(-1.0, 1.0);
-        let _ = prod.try_push(mono);
+        let (key, minor) = self.key.unwrap_or((0, false));
+        (key, minor, self.confidence, self.change)
     }
 }
 
-fn analyze_loop(
-    cons: &mut ringbuf::HeapCons<f32>,
-    sample_rate_hz: u32,
-    stop: &AtomicBool,
-    features: &AtomicAudioFeatures,
-) {
-    // Smaller windows reduce analysis latency (especially noticeable on system audio capture).
-    let n = 1024usize;
-    let hop = 256usize;
+/// EBU R128 window lengths in seconds.
+const MOMENTARY_WINDOW_S: f64 = 0.4;
+const SHORT_TERM_WINDOW_S: f64 = 3.0;
+
+/// Direct-form I biquad with `a0` normalized to 1.
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
 
-    let mut scratch = vec![0.0f32; n];
-    let mut write_pos = 0usize;
-    let mut filled = 0usize;
-    let mut since_last = 0usize;
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
 
-    let hann = (0..n)
-        .map(|i| 0.5 - 0.5 * ((2.0 * PI * i as f32) / (n as f32)).cos())
-        .collect::<Vec<_>>();
+/// ITU-R BS.1770 K-weighting (head-effect high shelf, then the RLB high-pass),
+/// designed for any sample rate rather than the tabulated 48 kHz coefficients.
+fn k_weighting(sample_rate_hz: u32) -> [Biquad; 2] {
+    let fs = sample_rate_hz as f64;
+
+    let k = (std::f64::consts::PI * 1_681.974_450_955_533 / fs).tan();
+    let q = 0.707_175_236_955_419_6;
+    let vh = 10f64.powf(3.999_843_853_973_347 / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Biquad::default()
+    };
 
-    let mut planner = FftPlanner::<f32>::new();
-    let fft = planner.plan_fft_forward(n);
-    let mut fft_buf = vec![Complex { re: 0.0, im: 0.0 }; n];
-    let mut mags = vec![0.0f32; n / 2];
-    let mut prev_mags = vec![0.0f32; n / 2];
+    let k = (std::f64::consts::PI * 38.135_470_876_024_44 / fs).tan();
+    let q = 0.500_327_037_323_877_3;
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Biquad::default()
+    };
+    [shelf, high_pass]
+}
 
-    let mut flux_avg = 0.0f32;
-    let mut flux_hist = [0.0f32; 3];
+/// Sums over one hop of input.
+#[derive(Clone, Copy, Default)]
+struct LoudnessBlock {
+    /// K-weighted energy summed over both channels.
+    weighted: f64,
+    /// Unweighted energy summed over both channels.
+    energy: f64,
+    peak: f32,
+    frames: u32,
+}
 
-    let mut rms_s = 0.0f32;
-    let mut bands_s = [0.0f32; 8];
-    let mut centroid_s = 0.0f32;
-    let mut flatness_s = 0.0f32;
+/// Momentary and short-term loudness plus crest factor, kept as a history of
+/// hop-sized blocks so both windows slide with the analyzer.
+struct LoudnessMeter {
+    filters: [[Biquad; 2]; 2],
+    block: LoudnessBlock,
+    history: VecDeque<LoudnessBlock>,
+    momentary_blocks: usize,
+    short_term_blocks: usize,
+}
 
-    while !stop.load(Ordering::Relaxed) {
-        let mut got_any = false;
-        while let Some(s) = cons.try_pop() {
-            got_any = true;
-            scratch[write_pos] = s;
-            write_pos = (write_pos + 1) % n;
-            if filled < n {
-                filled += 1;
-            }
-            since_last += 1;
-            if filled == n && since_last >= hop {
-                since_last = 0;
-                let (rms, bands, flux, centroid, flatness) = analyze_window(
-                    &scratch,
-                    write_pos,
-                    &hann,
-                    &fft,
-                    &mut fft_buf,
-                    &mut mags,
-                    &mut prev_mags,
-                    sample_rate_hz,
-                );
+impl LoudnessMeter {
+    fn new(sample_rate_hz: u32, hop: usize) -> Self {
+        let blocks = |seconds: f64| {
+            ((seconds * sample_rate_hz as f64 / hop.max(1) as f64).round() as usize).max(1)
+        };
+        let short_term_blocks = blocks(SHORT_TERM_WINDOW_S);
+        Self {
+            filters: [k_weighting(sample_rate_hz); 2],
+            block: LoudnessBlock::default(),
+            history: VecDeque::with_capacity(short_term_blocks + 1),
+            momentary_blocks: blocks(MOMENTARY_WINDOW_S),
+            short_term_blocks,
+        }
+    }
 
-                flux_hist[0] = flux_hist[1];
-                flux_hist[1] = flux_hist[2];
-                flux_hist[2] = flux;
-                flux_avg = flux_avg * 0.95 + flux * 0.05;
+    fn push(&mut self, frame: [f32; 2]) {
+        for ([shelf, high_pass], s) in self.filters.iter_mut().zip(frame) {
+            let x = s as f64;
+            let z = high_pass.process(shelf.process(x));
+            self.block.weighted += z * z;
+            self.block.energy += x * x;
+            self.block.peak = self.block.peak.max(s.abs());
+        }
+        self.block.frames += 1;
+    }
 
-                // Peak detection with 1-step latency.
-                let peak = flux_hist[1] > flux_hist[0] && flux_hist[1] > flux_hist[2];
-                let thr = (flux_avg * 1.45).max(1e-6);
-                let beat = peak && flux_hist[1] > thr;
-                let beat_strength = ((flux_hist[1] - thr) / (thr + 1e-6)).clamp(0.0, 1.0);
+    /// Closes the current block; returns `(momentary LUFS, short-term LUFS, crest dB)`.
+    fn finish_block(&mut self) -> (f32, f32, f32) {
+        self.history.push_back(std::mem::take(&mut self.block));
+        while self.history.len() > self.short_term_blocks {
+            self.history.pop_front();
+        }
+        let momentary_from = self.history.len().saturating_sub(self.momentary_blocks);
+        let momentary = Self::lufs(self.history.range(momentary_from..));
+        let short_term = Self::lufs(self.history.iter());
 
-                rms_s = rms_s * 0.85 + rms * 0.15;
-                for i in 0..bands_s.len() {
-                    bands_s[i] = bands_s[i] * 0.85 + bands[i] * 0.15;
-                }
-                centroid_s = centroid_s * 0.9 + centroid * 0.1;
-                flatness_s = flatness_s * 0.9 + flatness * 0.1;
+        let (energy, peak, frames) = self.history.iter().fold((0.0, 0.0f32, 0u32), |acc, b| {
+            (acc.0 + b.energy, acc.1.max(b.peak), acc.2 + b.frames)
+        });
+        let rms = (energy / (2 * frames.max(1)) as f64).sqrt() as f32;
+        let crest_db = if rms > 1e-5 { 20.0 * (peak / rms).log10() } else { 0.0 };
+        (momentary, short_term, crest_db)
+    }
 
-                features.store(AudioFeatures {
-                    rms: rms_s,
-                    bands: bands_s,
-                    onset: flux,
-                    beat,
-                    beat_strength,
-                    centroid: centroid_s,
-                    flatness: flatness_s,
-                });
-            }
+    fn lufs<'a>(blocks: impl Iterator<Item = &'a LoudnessBlock>) -> f32 {
+        let (weighted, frames) = blocks.fold((0.0, 0u32), |acc, b| (acc.0 + b.weighted, acc.1 + b.frames));
+        let mean = weighted / frames.max(1) as f64;
+        if mean <= 0.0 {
+            return LUFS_FLOOR;
         }
+        ((-0.691 + 10.0 * mean.log10()) as f32).max(LUFS_FLOOR)
+    }
+}
 
-        if !got_any {
-            thread::sleep(Duration::from_millis(1));
-        }
+/// Mean magnitude over each band's bins (skipping DC). Bands narrower than one
+/// bin read the magnitude interpolated at their centre.
+fn band_means(mags: &[f32], band_bins: &[(f32, f32)], out: &mut [f32; MAX_BANDS]) {
+    let last = mags.len().saturating_sub(1);
+    out.fill(0.0);
+    if last == 0 {
+        return;
+    }
+    for (dst, &(b0, b1)) in out.iter_mut().zip(band_bins) {
+        let k0 = (b0.ceil() as usize).max(1);
+        let k1 = (b1.ceil() as usize).min(last + 1);
+        *dst = if k1 > k0 {
+            mags[k0..k1].iter().sum::<f32>() / (k1 - k0) as f32
+        } else {
+            let c = ((b0 + b1) * 0.5).clamp(1.0, last as f32);
+            let k = c.floor() as usize;
+            let frac = c - k as f32;
+            mags[k] * (1.0 - frac) + mags[(k + 1).min(last)] * frac
+        };
     }
 }
 
 fn analyze_window(
     scratch: &[f32],
     write_pos: usize,
-    hann: &[f32],
+    layout: &AnalysisLayout,
     fft: &std::sync::Arc<dyn rustfft::Fft<f32>>,
     fft_buf: &mut [Complex<f32>],
     mags: &mut [f32],
     prev_mags: &mut [f32],
     sample_rate_hz: u32,
-) -> (f32, [f32; 8], f32, f32, f32) {
+) -> (f32, [f32; MAX_BANDS], f32, [f32; 3], f32, f32) {
     let n = fft_buf.len();
     let half = mags.len();
+    let sr = sample_rate_hz as f32;
+    let drum_bins = DRUM_BANDS_HZ.map(|(lo, hi)| {
+        let bin_of = |hz: f32| ((hz * n as f32 / sr) as usize).clamp(1, half);
+        (bin_of(lo), bin_of(hi))
+    });
 
     let mut rms_acc = 0.0f32;
     for i in 0..n {
         let s = scratch[(write_pos + i) % n];
         rms_acc += s * s;
-        fft_buf[i].re = s * hann[i];
+        fft_buf[i].re = s * layout.window[i];
         fft_buf[i].im = 0.0;
     }
     let rms = (rms_acc / n as f32).sqrt().clamp(0.0, 1.0);
 
     fft.process(fft_buf);
     for (i, c) in fft_buf.iter().take(half).enumerate() {
-        mags[i] = (c.re * c.re + c.im * c.im).sqrt();
+        mags[i] = (c.re * c.re + c.im * c.im).sqrt() * layout.mag_norm;
     }
 
     let mut flux = 0.0f32;
+    let mut drum_flux = [0.0f32; 3];
     for i in 0..half {
         let d = mags[i] - prev_mags[i];
         if d > 0.0 {
             flux += d;
+            for (acc, &(lo, hi)) in drum_flux.iter_mut().zip(&drum_bins) {
+                if (lo..hi).contains(&i) {
+                    *acc += d;
+                }
+            }
         }
         prev_mags[i] = mags[i];
     }
-    // Keep onset magnitude roughly stable when FFT size changes.
-    let flux_scale = 0.002 * (1024.0 / (half as f32).max(1.0));
-    flux = (flux * flux_scale).tanh();
-
-    // Bands: sub, bass, lowmid, mid, highmid, treb, air, presence
-    let edges_hz = [20.0, 60.0, 150.0, 400.0, 1000.0, 2500.0, 6000.0, 12000.0, 20000.0];
-    let mut bands = [0.0f32; 8];
-    let mut counts = [0u32; 8];
-    let sr = sample_rate_hz as f32;
-    for i in 1..half {
-        let f = (i as f32) * sr / (n as f32);
-        if f < edges_hz[0] {
-            continue;
-        }
-        if f >= edges_hz[8] {
-            break;
-        }
-        let mut band = 0usize;
-        while band + 1 < edges_hz.len() - 1 && f >= edges_hz[band + 1] {
-            band += 1;
-        }
-        let m = mags[i];
-        bands[band] += m;
-        counts[band] += 1;
-    }
-    for i in 0..bands.len() {
-        let denom = counts[i].max(1) as f32;
-        // Log-ish compression -> 0..1
-        let e = (bands[i] / denom) * 0.01;
-        bands[i] = e.tanh();
+    // Left linear; `Agc` applies the gain and compression.
+    flux *= 0.004;
+    for ((acc, &(lo, hi)), gain) in drum_flux.iter_mut().zip(&drum_bins).zip(DRUM_FLUX_GAIN) {
+        *acc = (*acc / hi.saturating_sub(lo).max(1) as f32 * gain).tanh();
     }
 
+    // Mean magnitude per band, left linear like the onset flux.
+    let mut bands = [0.0f32; MAX_BANDS];
+    band_means(mags, &layout.band_bins, &mut bands);
+
     // Spectral centroid (0..1)
     let mut num = 0.0f32;
     let mut den = 0.0f32;
@@ -744,5 +3097,779 @@ fn analyze_window(
         0.0
     };
 
-    (rms, bands, flux, centroid, flatness)
+    (rms, bands, flux, drum_flux, centroid, flatness)
+}
+
+/// Kick, snare/clap and hi-hat frequency ranges for the per-drum onset detectors.
+const DRUM_BANDS_HZ: [(f32, f32); 3] = [(30.0, 150.0), (180.0, 4000.0), (7000.0, 16000.0)];
+/// Per-bin flux gains; higher bands carry less energy per bin.
+const DRUM_FLUX_GAIN: [f32; 3] = [0.01, 0.03, 0.08];
+
+/// Adaptive-threshold peak picker over one band-limited flux signal.
+///
+/// Uses the same 1-hop-latency local-maximum test as the broadband beat detector,
+/// but holds each hit as a decaying envelope so frame-rate consumers don't miss it.
+struct DrumDetector {
+    hist: [f32; 3],
+    avg: f32,
+    env: f32,
+    decay: f32,
+}
+
+impl DrumDetector {
+    fn new(hop_s: f32) -> Self {
+        Self {
+            hist: [0.0; 3],
+            avg: 0.0,
+            env: 0.0,
+            // ~90 ms time constant regardless of hop size.
+            decay: (-hop_s / 0.09).exp(),
+        }
+    }
+
+    fn push(&mut self, flux: f32) -> f32 {
+        self.hist = [self.hist[1], self.hist[2], flux];
+        self.avg = self.avg * 0.95 + flux * 0.05;
+
+        // The floor keeps leakage from other bands from registering as hits.
+        let thr = (self.avg * 1.6).max(0.03);
+        let peak = self.hist[1] > self.hist[0] && self.hist[1] > self.hist[2];
+        self.env *= self.decay;
+        if peak && self.hist[1] > thr {
+            let strength = ((self.hist[1] - thr) / (thr + 1e-6)).clamp(0.0, 1.0);
+            self.env = self.env.max(0.5 + 0.5 * strength);
+        }
+        self.env
+    }
+}
+
+const TEMPO_MIN_BPM: f32 = 60.0;
+const TEMPO_MAX_BPM: f32 = 200.0;
+const TEMPO_HISTORY_S: f32 = 8.0;
+
+/// Autocorrelation tempo tracker over the spectral-flux onset envelope.
+///
+/// Every half second the envelope history is autocorrelated to pick a beat period
+/// (weighted towards 120 BPM to keep octave errors down), then comb-filtered at that
+/// period to find where the last beat fell. A free-running phase oscillator is nudged
+/// towards that estimate, so syncopated hits between beats don't pull the phase around.
+/// Downbeats are picked from the beat slot with the strongest low-band accent.
+struct TempoTracker {
+    frame_rate: f32,
+    env: Vec<f32>,
+    pos: usize,
+    filled: usize,
+    lin: Vec<f32>,
+    since_estimate: usize,
+    bpm: f32,
+    candidate_bpm: f32,
+    candidate_hits: u8,
+    misses: u8,
+    phase: f32,
+    pending: f32,
+    beat_count: u32,
+    prev_bass: f32,
+    accent_open: bool,
+    accent_peak: f32,
+    accents: [f32; 4],
+    downbeat: u32,
+}
+
+impl TempoTracker {
+    fn new(frame_rate: f32) -> Self {
+        let len = (frame_rate * TEMPO_HISTORY_S).ceil().max(16.0) as usize;
+        Self {
+            frame_rate,
+            env: vec![0.0; len],
+            pos: 0,
+            filled: 0,
+            lin: Vec::with_capacity(len),
+            since_estimate: 0,
+            bpm: 0.0,
+            candidate_bpm: 0.0,
+            candidate_hits: 0,
+            misses: 0,
+            phase: 0.0,
+            pending: 0.0,
+            beat_count: 0,
+            prev_bass: 0.0,
+            accent_open: false,
+            accent_peak: 0.0,
+            accents: [0.0; 4],
+            downbeat: 0,
+        }
+    }
+
+    /// Feeds one analysis hop and returns `(bpm, beat_phase, bar_phase)`.
+    fn push(&mut self, onset: f32, bass: f32) -> (f32, f32, f32) {
+        self.env[self.pos] = onset;
+        self.pos = (self.pos + 1) % self.env.len();
+        self.filled = (self.filled + 1).min(self.env.len());
+        let bass_onset = (bass - self.prev_bass).max(0.0);
+        self.prev_bass = bass;
+
+        self.since_estimate += 1;
+        if self.since_estimate as f32 >= self.frame_rate * 0.5 {
+            self.since_estimate = 0;
+            self.estimate();
+        }
+        if self.bpm <= 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+
+        // Advance the oscillator, bleeding in phase corrections without ever running
+        // backwards (consumers detect beats from the phase wrapping around).
+        let inc = self.bpm / 60.0 / self.frame_rate;
+        let corr = (self.pending * 0.12).clamp(-0.5 * inc, 0.5 * inc);
+        self.pending -= corr;
+        self.phase += inc + corr;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+            self.beat_count = self.beat_count.wrapping_add(1);
+            self.accent_open = true;
+            self.accent_peak = 0.0;
+        }
+        if self.accent_open {
+            self.accent_peak = self.accent_peak.max(bass_onset);
+            if self.phase >= 0.15 {
+                self.accent_open = false;
+                let slot = (self.beat_count % 4) as usize;
+                self.accents[slot] = self.accents[slot] * 0.85 + self.accent_peak * 0.15;
+                let (best, best_v) = self
+                    .accents
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .fold((0, f32::MIN), |a, (i, v)| if v > a.1 { (i, v) } else { a });
+                if best_v > self.accents[self.downbeat as usize] * 1.1 {
+                    self.downbeat = best as u32;
+                }
+            }
+        }
+
+        let beat_in_bar = self.beat_count.wrapping_sub(self.downbeat) % 4;
+        let bar_phase = (beat_in_bar as f32 + self.phase) * 0.25;
+        (self.bpm, self.phase, bar_phase)
+    }
+
+    fn estimate(&mut self) {
+        let n = self.filled;
+        if (n as f32) < self.frame_rate * 3.0 {
+            return;
+        }
+
+        // Chronological, mean-removed copy of the envelope.
+        let len = self.env.len();
+        let start = (self.pos + len - n) % len;
+        self.lin.clear();
+        self.lin.extend((0..n).map(|i| self.env[(start + i) % len]));
+        let mean = self.lin.iter().sum::<f32>() / n as f32;
+        let x = self.lin.iter().map(|v| v - mean).collect::<Vec<_>>();
+        let r0 = x.iter().map(|v| v * v).sum::<f32>();
+
+        let lag_min = ((self.frame_rate * 60.0 / TEMPO_MAX_BPM).floor() as usize).max(2);
+        let lag_max = ((self.frame_rate * 60.0 / TEMPO_MIN_BPM).ceil() as usize).min(n / 2);
+        let mut best = None::<(usize, f32, f32)>;
+        let mut scores = vec![0.0f32; lag_max + 2];
+        if r0 > 1e-6 {
+            for lag in lag_min..=lag_max {
+                let mut acc = 0.0f32;
+                for i in lag..n {
+                    acc += x[i] * x[i - lag];
+                }
+                let r = acc / (n - lag) as f32 * n as f32 / r0;
+                let bpm = 60.0 * self.frame_rate / lag as f32;
+                let octave = (bpm / 120.0).log2();
+                let score = r * (-0.5 * octave * octave).exp();
+                scores[lag] = score;
+                if best.is_none_or(|(_, s, _)| score > s) {
+                    best = Some((lag, score, r));
+                }
+            }
+        }
+
+        let Some((lag, _, _)) = best.filter(|&(_, _, r)| r > 0.1) else {
+            self.misses = self.misses.saturating_add(1);
+            if self.misses >= 4 {
+                self.bpm = 0.0;
+                self.candidate_hits = 0;
+            }
+            return;
+        };
+        self.misses = 0;
+
+        // Parabolic refinement of the peak lag.
+        let mut lag_f = lag as f32;
+        if lag > lag_min && lag < lag_max {
+            let (a, b, c) = (scores[lag - 1], scores[lag], scores[lag + 1]);
+            let den = a - 2.0 * b + c;
+            if den.abs() > 1e-9 {
+                lag_f += (0.5 * (a - c) / den).clamp(-0.5, 0.5);
+            }
+        }
+        let est = 60.0 * self.frame_rate / lag_f;
+
+        let was_locked = self.bpm > 0.0;
+        if !was_locked {
+            self.bpm = est;
+        } else if (est / self.bpm - 1.0).abs() < 0.04 {
+            self.bpm = self.bpm * 0.8 + est * 0.2;
+            self.candidate_hits = 0;
+        } else if self.candidate_hits > 0 && (est / self.candidate_bpm - 1.0).abs() < 0.04 {
+            self.candidate_hits += 1;
+            if self.candidate_hits >= 3 {
+                self.bpm = est;
+                self.candidate_hits = 0;
+            }
+        } else {
+            self.candidate_bpm = est;
+            self.candidate_hits = 1;
+        }
+
+        // Comb over the raw envelope to see how long ago the last beat landed.
+        let period = 60.0 * self.frame_rate / self.bpm;
+        let span = period.round().max(1.0) as usize;
+        let mut best_offset = 0usize;
+        let mut best_energy = f32::MIN;
+        for offset in 0..span.min(n) {
+            let mut energy = 0.0f32;
+            let mut w = 1.0f32;
+            let mut k = 0usize;
+            loop {
+                let back = offset + (k as f32 * period).round() as usize;
+                if back >= n {
+                    break;
+                }
+                energy += self.lin[n - 1 - back] * w;
+                w *= 0.8;
+                k += 1;
+            }
+            if energy > best_energy {
+                best_energy = energy;
+                best_offset = offset;
+            }
+        }
+        let target = best_offset as f32 / period;
+        if was_locked {
+            let mut err = target - self.phase;
+            err -= err.round();
+            self.pending = err;
+        } else {
+            self.phase = target.fract();
+            self.pending = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out at most `step` bytes per read to exercise partial-frame carry-over.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = self.step.min(out.len()).min(self.data.len() - self.pos);
+            out[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    static INDEXED_OPENS: Mutex<Vec<Option<String>>> = Mutex::new(Vec::new());
+
+    /// Opens the synth in place of a capture device, as if `--device 2` picked
+    /// an input named "USB Interface".
+    fn indexed_open(cfg: &Config) -> anyhow::Result<AudioSystem> {
+        INDEXED_OPENS.lock().unwrap().push(cfg.device.clone());
+        let mut synth = cfg.clone();
+        synth.source = AudioSource::Synth;
+        let mut audio = AudioSystem::new(&synth)?;
+        if cfg.device.as_deref() == Some("2") {
+            audio.device_name = Some("USB Interface".to_string());
+        }
+        Ok(audio)
+    }
+
+    #[test]
+    fn supervisor_reopens_an_indexed_device_by_its_name() {
+        use clap::Parser as _;
+
+        let cfg = Config::try_parse_from(["tui-visualizer", "--device", "2"]).expect("parse");
+        let mut sup = AudioSupervisor::with_opener(&cfg, indexed_open).expect("initial open");
+        sup.stream_fault().expect("stream running").raise("device unplugged");
+        let t0 = Instant::now();
+        assert!(matches!(sup.poll(t0), Some(SupervisorEvent::Lost(_))));
+        assert_eq!(sup.poll(t0 + REOPEN_DELAY), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut event = None;
+        while event.is_none() && sup.is_reopening() && Instant::now() < deadline {
+            event = sup.poll(t0 + REOPEN_DELAY);
+            thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(event, Some(SupervisorEvent::Reconnected("USB Interface".into())));
+        assert_eq!(
+            *INDEXED_OPENS.lock().unwrap(),
+            [Some("2".to_string()), Some("USB Interface".to_string())]
+        );
+    }
+
+    fn drain(cons: &mut ringbuf::HeapCons<[f32; 2]>) -> Vec<[f32; 2]> {
+        let mut out = Vec::new();
+        while let Some(s) = cons.try_pop() {
+            out.push(s);
+        }
+        out
+    }
+
+    #[test]
+    fn pcm_stream_s16le_stereo_keeps_channels_across_partial_reads() {
+        let frames: [[i16; 2]; 3] = [[16384, 16384], [-32768, 0], [0, 16384]];
+        let data = frames
+            .iter()
+            .flatten()
+            .flat_map(|s| s.to_le_bytes())
+            .collect::<Vec<_>>();
+        let (mut prod, mut cons) = HeapRb::<[f32; 2]>::new(64).split();
+        let stop = AtomicBool::new(false);
+
+        read_pcm_stream(
+            Trickle { data, pos: 0, step: 3 },
+            PcmFormat::S16le,
+            2,
+            &stop,
+            &mut prod,
+        );
+
+        let got = drain(&mut cons);
+        assert_eq!(got, vec![[0.5, 0.5], [-1.0, 0.0], [0.0, 0.5]]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn monitor_source_listing_keeps_only_monitors() {
+        let text = "0\talsa_output.pci.analog-stereo.monitor\tmodule-alsa-card.c\ts16le 2ch 44100Hz\tSUSPENDED\n\
+                    1\talsa_input.pci.analog-stereo\tmodule-alsa-card.c\ts16le 2ch 44100Hz\tRUNNING\n\
+                    2\tbrotviz_test.monitor\tmodule-null-sink.c\tfloat32le 2ch 48000Hz\tIDLE\n";
+        assert_eq!(
+            parse_monitor_sources(text),
+            vec![
+                "alsa_output.pci.analog-stereo.monitor".to_string(),
+                "brotviz_test.monitor".to_string(),
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn exited_capture_child_reports_status_and_stderr() {
+        use std::process::{Command, Stdio};
+
+        let mut child = Command::new("sh")
+            .args(["-c", "echo 'Stream error: No such entity' >&2; exit 1"])
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        let (tail, reader) = spawn_stderr_tail(child.stderr.take().expect("stderr"));
+        let status = child.wait().expect("wait");
+        reader.join().expect("stderr reader");
+        let message = parec_exit_message(status, &tail);
+        assert!(message.starts_with("parec exited with"), "{message}");
+        assert!(message.ends_with(": Stream error: No such entity"), "{message}");
+    }
+
+    #[test]
+    fn pcm_stream_f32le_mono_passes_samples_through() {
+        let samples = [0.25f32, -0.75, 1.0];
+        let data = samples.iter().flat_map(|s| s.to_le_bytes()).collect::<Vec<_>>();
+        let (mut prod, mut cons) = HeapRb::<[f32; 2]>::new(64).split();
+        let stop = AtomicBool::new(false);
+
+        read_pcm_stream(
+            Trickle { data, pos: 0, step: 5 },
+            PcmFormat::F32le,
+            1,
+            &stop,
+            &mut prod,
+        );
+
+        assert_eq!(drain(&mut cons), samples.map(|s| [s, s]).to_vec());
+    }
+
+    /// Drives the tracker with an impulse train at `bpm`, optionally with an off-beat
+    /// hit and a louder bass accent on every fourth beat; returns the last output and
+    /// the frame index of the most recent on-beat impulse.
+    fn run_click_track(bpm: f32, offbeats: bool, seconds: f32) -> ((f32, f32, f32), usize, f32) {
+        let frame_rate = 48_000.0 / 256.0;
+        let period = 60.0 * frame_rate / bpm;
+        let mut tracker = TempoTracker::new(frame_rate);
+        let frames = (seconds * frame_rate) as usize;
+        let mut out = (0.0, 0.0, 0.0);
+        let mut last_beat = 0usize;
+        for i in 0..frames {
+            let pos = i as f32 / period;
+            let beat_idx = pos.round();
+            let on_beat = (pos - beat_idx).abs() * period < 0.5;
+            let off_beat = offbeats && (pos.fract() - 0.75).abs() * period < 0.5;
+            let downbeat = on_beat && (beat_idx as u32) % 4 == 0;
+            let onset = if on_beat {
+                last_beat = i;
+                0.8
+            } else if off_beat {
+                0.5
+            } else {
+                0.02
+            };
+            let bass = if downbeat { 0.9 } else if on_beat { 0.4 } else { 0.05 };
+            out = tracker.push(onset, bass);
+        }
+        (out, frames - 1 - last_beat, period)
+    }
+
+    fn phase_distance(a: f32, b: f32) -> f32 {
+        let d = (a - b).rem_euclid(1.0);
+        d.min(1.0 - d)
+    }
+
+    #[test]
+    fn tempo_tracker_locks_onto_click_track() {
+        let ((bpm, beat_phase, _), since_beat, period) = run_click_track(128.0, false, 12.0);
+        assert!((bpm - 128.0).abs() < 2.0, "bpm={bpm}");
+        let expected = since_beat as f32 / period;
+        assert!(phase_distance(beat_phase, expected) < 0.1, "phase={beat_phase} expected={expected}");
+    }
+
+    #[test]
+    fn tempo_tracker_ignores_syncopated_offbeats() {
+        let ((bpm, beat_phase, _), since_beat, period) = run_click_track(100.0, true, 12.0);
+        assert!((bpm - 100.0).abs() < 2.0, "bpm={bpm}");
+        let expected = since_beat as f32 / period;
+        assert!(phase_distance(beat_phase, expected) < 0.1, "phase={beat_phase} expected={expected}");
+    }
+
+    #[test]
+    fn tempo_tracker_finds_accented_downbeat() {
+        let ((_, beat_phase, bar_phase), since_beat, period) = run_click_track(120.0, false, 16.0);
+        let beats_total = (16.0 * 48_000.0 / 256.0 - 1.0 - since_beat as f32) / period;
+        let beat_in_bar = (beats_total.round() as u32 % 4) as f32;
+        let expected = (beat_in_bar + beat_phase) * 0.25;
+        assert!(phase_distance(bar_phase, expected) < 0.05, "bar={bar_phase} expected={expected}");
+    }
+
+    #[test]
+    fn tempo_tracker_reports_no_lock_on_silence() {
+        let mut tracker = TempoTracker::new(48_000.0 / 256.0);
+        let mut out = (1.0, 1.0, 1.0);
+        for _ in 0..2000 {
+            out = tracker.push(0.0, 0.0);
+        }
+        assert_eq!(out, (0.0, 0.0, 0.0));
+    }
+
+    /// Runs decaying tone bursts at `hz` through the analyzer windows and returns the
+    /// peak kick/snare/hat envelopes seen after the first second.
+    fn drum_peaks_for_bursts(hz: f32) -> [f32; 3] {
+        let sr = 48_000u32;
+        let (n, hop) = (1024usize, 256usize);
+        let samples = (0..sr as usize * 3)
+            .map(|i| {
+                let t = i as f32 / sr as f32;
+                let since = t % 0.5;
+                let env = (since / 0.003).min(1.0) * (-since / 0.04).exp();
+                (2.0 * PI * hz * t).sin() * env * 0.8
+            })
+            .collect::<Vec<_>>();
+
+        let layout = AnalysisLayout::new(&AnalyzerConfig::default(), sr);
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(n);
+        let mut fft_buf = vec![Complex { re: 0.0, im: 0.0 }; n];
+        let mut mags = vec![0.0f32; n / 2];
+        let mut prev_mags = vec![0.0f32; n / 2];
+        let mut drums = std::array::from_fn::<_, 3, _>(|_| DrumDetector::new(hop as f32 / sr as f32));
+
+        let mut peaks = [0.0f32; 3];
+        for end in (n..samples.len()).step_by(hop) {
+            let (_, _, _, drum_flux, _, _) = analyze_window(
+                &samples[end - n..end],
+                0,
+                &layout,
+                &fft,
+                &mut fft_buf,
+                &mut mags,
+                &mut prev_mags,
+                sr,
+            );
+            for i in 0..3 {
+                let env = drums[i].push(drum_flux[i]);
+                if end > sr as usize {
+                    peaks[i] = peaks[i].max(env);
+                }
+            }
+        }
+        peaks
+    }
+
+    #[test]
+    fn drum_detectors_separate_low_mid_and_high_hits() {
+        let [kick, snare, hat] = drum_peaks_for_bursts(60.0);
+        assert!(kick > 0.5 && snare < 0.2 && hat < 0.2, "low: {kick} {snare} {hat}");
+
+        let [kick, snare, hat] = drum_peaks_for_bursts(1000.0);
+        assert!(snare > 0.5 && kick < 0.2 && hat < 0.2, "mid: {kick} {snare} {hat}");
+
+        let [kick, snare, hat] = drum_peaks_for_bursts(10_000.0);
+        assert!(hat > 0.5 && kick < 0.2 && snare < 0.2, "high: {kick} {snare} {hat}");
+    }
+
+    #[test]
+    fn audio_snapshot_round_trips_and_pads_short_input() {
+        let shared = AtomicAudioSnapshot::new(4, 3);
+        shared.store(&[0.1, 0.2, 0.3, 0.4, 0.5], &[-0.5], &[0.25, 0.5, 0.75, 1.0]);
+
+        let mut out = AudioSnapshot::default();
+        shared.load_into(&mut out);
+        assert_eq!(out.spectrum, vec![0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(out.waveform, vec![-0.5, 0.0, 0.0]);
+        assert_eq!(out.side, vec![0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn agc_levels_quiet_and_loud_input_alike() {
+        let frame_rate = 48_000.0 / 256.0;
+        let run = |scale: f32, enabled: bool| {
+            let mut agc = Agc::new(enabled, frame_rate);
+            let mut out = (0.0, [0.0; MAX_BANDS], 0.0, 0.0);
+            for i in 0..(frame_rate as usize * 4) {
+                // A pulsing mix so the peak followers see some dynamics.
+                let pulse = if i % 48 < 6 { 1.0 } else { 0.4 };
+                let classic = std::array::from_fn::<_, 8, _>(|b| 300.0 / (b + 1) as f32 * pulse * scale);
+                let bands = AudioFeatures::pad_bands(&classic);
+                out = agc.apply(0.3 * pulse * scale, bands, 2.0 * pulse * scale, false);
+            }
+            out
+        };
+
+        let (quiet_rms, quiet_bands, quiet_flux, quiet_gain) = run(0.1, true);
+        let (loud_rms, loud_bands, loud_flux, loud_gain) = run(3.0, true);
+        assert!((quiet_rms - loud_rms).abs() < 0.02, "rms {quiet_rms} vs {loud_rms}");
+        assert!((quiet_flux - loud_flux).abs() < 0.02, "flux {quiet_flux} vs {loud_flux}");
+        for (q, l) in quiet_bands.iter().zip(&loud_bands) {
+            assert!((q - l).abs() < 0.02, "bands {quiet_bands:?} vs {loud_bands:?}");
+        }
+        assert!(quiet_gain > 10.0 && loud_gain < 1.0, "gains {quiet_gain} / {loud_gain}");
+
+        // Off: the fixed curve, so quiet input stays small and loud input saturates.
+        let (_, quiet_fixed, _, gain) = run(0.02, false);
+        let (_, loud_fixed, _, _) = run(3.0, false);
+        assert_eq!(gain, 1.0);
+        assert!(quiet_fixed[7] < 0.05 && loud_fixed[0] > 0.99);
+    }
+
+    #[test]
+    fn band_edges_follow_spacing_and_count() {
+        assert_eq!(band_edges_hz(BandSpacing::Classic, 8, 20.0, 20_000.0), CLASSIC_BAND_EDGES_HZ.to_vec());
+
+        for spacing in [BandSpacing::Classic, BandSpacing::Log, BandSpacing::Linear, BandSpacing::Mel] {
+            let edges = band_edges_hz(spacing, 16, 20.0, 20_000.0);
+            assert_eq!(edges.len(), 17);
+            assert!((edges[0] - 20.0).abs() < 1e-3 && (edges[16] - 20_000.0).abs() < 1.0);
+            assert!(edges.windows(2).all(|e| e[0] < e[1]), "{spacing:?}: {edges:?}");
+        }
+        // Log spends the most bands on the low end, linear the fewest.
+        let below_200 = |spacing| {
+            band_edges_hz(spacing, 16, 20.0, 20_000.0)
+                .iter()
+                .filter(|&&f| f < 200.0)
+                .count()
+        };
+        assert!(below_200(BandSpacing::Log) > below_200(BandSpacing::Mel));
+        assert!(below_200(BandSpacing::Mel) > below_200(BandSpacing::Linear));
+    }
+
+    #[test]
+    fn narrow_bands_read_interpolated_magnitude() {
+        let mags = (0..512).map(|i| i as f32).collect::<Vec<_>>();
+        let mut out = [0.0f32; MAX_BANDS];
+        // One band narrower than a bin, one spanning bins 10..20.
+        band_means(&mags, &[(3.2, 3.6), (10.0, 20.0)], &mut out);
+        assert!((out[0] - 3.4).abs() < 1e-4);
+        assert!((out[1] - 14.5).abs() < 1e-4);
+        assert_eq!(out[2], 0.0);
+    }
+
+    #[test]
+    fn band8_maps_any_layout_onto_classic_bands() {
+        let mut f = AudioFeatures::default();
+        f.bands = AudioFeatures::pad_bands(&[0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7]);
+        assert_eq!(f.bands8(), [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7]);
+        assert_eq!(f.band8_range(5..8), 5..8);
+
+        let ramp = (0..16).map(|i| i as f32 / 15.0).collect::<Vec<_>>();
+        f.bands = AudioFeatures::pad_bands(&ramp);
+        f.band_count = 16;
+        let b = f.bands8();
+        assert!(b.windows(2).all(|w| w[0] < w[1]), "{b:?}");
+        assert!(b[0] < 0.1 && b[7] > 0.9);
+        assert_eq!(f.band8_range(0..2), 0..4);
+
+        f.band_count = 2;
+        assert_eq!(f.band8_range(0..1), 0..1);
+        assert_eq!(f.band8_range(7..8), 1..2);
+    }
+
+    #[test]
+    fn analyzer_config_rejects_bad_windows_and_edges() {
+        let ok = AnalyzerConfig::default();
+        assert!(ok.validate().is_ok());
+        assert_eq!(ok.band_count(), 8);
+
+        let bad = [
+            AnalyzerConfig { fft_size: 1000, ..ok.clone() },
+            AnalyzerConfig { fft_size: 128, ..ok.clone() },
+            AnalyzerConfig { hop: 0, ..ok.clone() },
+            AnalyzerConfig { hop: 2048, ..ok.clone() },
+            AnalyzerConfig { band_edges_hz: vec![100.0], ..ok.clone() },
+            AnalyzerConfig { band_edges_hz: vec![100.0, 50.0, 200.0], ..ok.clone() },
+            AnalyzerConfig { band_edges_hz: (0..40).map(|i| 20.0 + i as f32).collect(), ..ok.clone() },
+        ];
+        for cfg in bad {
+            assert!(cfg.validate().is_err(), "{cfg:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn fold_stereo_duplicates_mono_and_pairs_extra_channels() {
+        assert_eq!(fold_stereo([0.5].into_iter()), [0.5, 0.5]);
+        assert_eq!(fold_stereo([0.5, -0.25].into_iter()), [0.5, -0.25]);
+        // 4.0 layout: front-left/right plus rear-left/right.
+        assert_eq!(fold_stereo([0.5, 0.25, 0.1, -0.25].into_iter()), [0.3, 0.0]);
+        assert_eq!(fold_stereo(std::iter::empty()), [0.0, 0.0]);
+    }
+
+    fn range(channels: u16, rates: (u32, u32), format: SampleFormat) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(
+            channels,
+            SampleRate(rates.0),
+            SampleRate(rates.1),
+            SupportedBufferSize::Range { min: 64, max: 4096 },
+            format,
+        )
+    }
+
+    #[test]
+    fn capture_config_keeps_the_default_until_rate_or_channels_rule_it_out() {
+        let ranges = [
+            range(2, (44_100, 48_000), SampleFormat::F32),
+            range(8, (44_100, 96_000), SampleFormat::I16),
+            range(8, (44_100, 96_000), SampleFormat::F32),
+            range(4, (96_000, 96_000), SampleFormat::F32),
+        ];
+        let default = ranges[0].with_sample_rate(SampleRate(48_000));
+        let capture = |rate, channels: Option<&str>| CaptureArgs {
+            capture_rate: rate,
+            capture_channels: channels.map(|c| c.parse().expect("valid selection")),
+            capture_buffer: None,
+        };
+
+        let cfg = capture_config(default.clone(), &ranges, &capture(None, Some("1-2"))).unwrap();
+        assert_eq!(cfg, default);
+        // Channels 3-4 need a 4+ channel stream: the default's format wins over I16.
+        let cfg = capture_config(default.clone(), &ranges, &capture(None, Some("3-4"))).unwrap();
+        assert_eq!(
+            (cfg.channels(), cfg.sample_format(), cfg.sample_rate().0),
+            (8, SampleFormat::F32, 48_000)
+        );
+        // At 96 kHz the 4-channel config is the smallest that fits.
+        let cfg = capture_config(default.clone(), &ranges, &capture(Some(96_000), Some("4"))).unwrap();
+        assert_eq!((cfg.channels(), cfg.sample_rate().0), (4, 96_000));
+        assert!(capture_config(default.clone(), &ranges, &capture(Some(22_050), None)).is_err());
+        assert!(capture_config(default, &ranges, &capture(None, Some("9"))).is_err());
+    }
+
+    #[test]
+    fn capture_buffer_size_is_checked_against_the_device_range() {
+        let range = SupportedBufferSize::Range { min: 64, max: 4096 };
+        assert_eq!(capture_buffer_size(&range, None).unwrap(), BufferSize::Default);
+        assert_eq!(capture_buffer_size(&range, Some(256)).unwrap(), BufferSize::Fixed(256));
+        assert!(capture_buffer_size(&range, Some(8192)).is_err());
+        assert_eq!(
+            capture_buffer_size(&SupportedBufferSize::Unknown, Some(8192)).unwrap(),
+            BufferSize::Fixed(8192)
+        );
+    }
+
+    #[test]
+    fn push_selected_folds_only_the_chosen_channels() {
+        let (mut prod, mut cons) = HeapRb::<[f32; 2]>::new(8).split();
+        // Two 4-channel frames; pick channels 3-4 (0-based 2 and 3).
+        let data = [0.1f32, 0.2, 0.5, -0.5, 0.1, 0.2, 0.25, 0.75];
+        push_selected(&data, 4, &[2, 3], &mut prod);
+        push_selected(&data[..4], 4, &[2], &mut prod);
+        assert_eq!(cons.try_pop(), Some([0.5, -0.5]));
+        assert_eq!(cons.try_pop(), Some([0.25, 0.75]));
+        assert_eq!(cons.try_pop(), Some([0.5, 0.5]));
+        assert_eq!(cons.try_pop(), None);
+    }
+
+    #[test]
+    fn describe_config_ranges_lists_each_config_once() {
+        let ranges = [
+            range(2, (44_100, 96_000), SampleFormat::F32),
+            range(2, (44_100, 96_000), SampleFormat::F32),
+            range(1, (48_000, 48_000), SampleFormat::I16),
+        ];
+        assert_eq!(
+            describe_config_ranges(ranges.into_iter()),
+            ["2 ch f32 44100-96000 Hz, buffer 64-4096", "1 ch i16 48000 Hz, buffer 64-4096"]
+        );
+    }
+
+    #[test]
+    fn stereo_window_measures_level_and_width() {
+        let tone = (0..1024)
+            .map(|i| (i as f32 * 0.07).sin() * 0.5)
+            .collect::<Vec<_>>();
+        let silent = vec![0.0f32; tone.len()];
+        let inverted = tone.iter().map(|s| -s).collect::<Vec<_>>();
+
+        let ([l, r], width) = stereo_window(&tone, &tone);
+        assert!((l - r).abs() < 1e-6 && l > 0.3);
+        assert!(width < 1e-3, "identical channels should be mono, got {width}");
+
+        let ([l, r], width) = stereo_window(&tone, &silent);
+        assert!(l > 0.3 && r == 0.0);
+        assert!((width - 0.5).abs() < 1e-3, "hard pan width {width}");
+
+        let (_, width) = stereo_window(&tone, &inverted);
+        assert!(width > 0.999, "out-of-phase width {width}");
+
+        assert_eq!(stereo_window(&silent, &silent), ([0.0, 0.0], 0.0));
+    }
+
+    #[test]
+    fn log_spectrum_bins_stay_in_range_and_rise_with_frequency_of_peak() {
+        let mut low = [0.0f32; 48];
+        let mut high = [0.0f32; 48];
+        let mut mags = vec![0.0f32; 512];
+        mags[5] = 300.0; // ~234 Hz at 48 kHz / 1024
+        log_spectrum_bins(&mags, 48_000, 1024, &mut low);
+        mags[5] = 0.0;
+        mags[200] = 300.0; // ~9.4 kHz
+        log_spectrum_bins(&mags, 48_000, 1024, &mut high);
+
+        let argmax = |v: &[f32]| {
+            v.iter()
+                .enumerate()
+                .fold((0, f32::MIN), |a, (i, &x)| if x > a.1 { (i, x) } else { a })
+                .0
+        };
+        assert!(low.iter().chain(&high).all(|v| (0.0..=1.0).contains(v)));
+        assert!(argmax(&low) < argmax(&high));
+        assert!(low[argmax(&low)] > 0.9 && high[argmax(&high)] > 0.9);
+    }
 }
@@ -1,8 +1,9 @@
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use tui_visualizer::audio::AudioFeatures;
+use tui_visualizer::audio::AudioSnapshot;
 use tui_visualizer::config::{Quality, SwitchMode};
+use tui_visualizer::synth::{section_audio, synth_audio, synth_snapshot};
 use tui_visualizer::visual::{make_presets, CameraPathMode, PresetEngine, RenderCtx, VisualEngine};
 
 #[cfg(target_os = "macos")]
@@ -168,89 +169,6 @@ fn parse_bool(s: &str) -> Option<bool> {
     }
 }
 
-fn synth_audio(t: f32, step: usize) -> AudioFeatures {
-    let bass = ((t * 1.9).sin() * 0.5 + 0.5).powf(1.15);
-    let mid = ((t * 2.8 + 0.7).sin() * 0.5 + 0.5).powf(1.08);
-    let treb = ((t * 5.2 + 1.3).sin() * 0.5 + 0.5).powf(1.02);
-
-    let hard_hit = step % 24 == 0;
-    let soft_hit = step % 12 == 0;
-
-    let onset = if hard_hit {
-        0.92
-    } else if soft_hit {
-        0.58
-    } else {
-        (treb * 0.35 + mid * 0.25).min(0.5)
-    };
-
-    let beat_strength = if hard_hit {
-        0.95
-    } else if soft_hit {
-        0.55
-    } else {
-        0.0
-    };
-
-    AudioFeatures {
-        rms: (0.12 + bass * 0.42 + mid * 0.30 + treb * 0.20).clamp(0.0, 1.0),
-        bands: [
-            (bass * 0.95).clamp(0.0, 1.0),
-            bass.clamp(0.0, 1.0),
-            (bass * 0.6 + mid * 0.35).clamp(0.0, 1.0),
-            mid.clamp(0.0, 1.0),
-            (mid * 0.5 + treb * 0.45).clamp(0.0, 1.0),
-            treb.clamp(0.0, 1.0),
-            (treb * 0.9).clamp(0.0, 1.0),
-            (treb * 0.75 + mid * 0.2).clamp(0.0, 1.0),
-        ],
-        onset,
-        beat: hard_hit || soft_hit,
-        beat_strength,
-        centroid: (0.2 + treb * 0.6 + mid * 0.15).clamp(0.0, 1.0),
-        flatness: (0.15 + treb * 0.55).clamp(0.0, 1.0),
-    }
-}
-
-fn section_audio(step: usize, total_steps: usize) -> AudioFeatures {
-    let t = step as f32 / 60.0;
-    let mut audio = synth_audio(t, step);
-    let section = (step.saturating_mul(4)) / total_steps.max(1);
-    match section {
-        0 => {
-            audio.rms = (0.08 + audio.rms * 0.22).clamp(0.0, 1.0);
-            audio.onset = (audio.onset * 0.25).clamp(0.0, 1.0);
-            audio.beat = step % 48 == 0;
-            audio.beat_strength = if audio.beat { 0.35 } else { 0.0 };
-        }
-        1 => {
-            audio.rms = (0.18 + audio.rms * 0.40).clamp(0.0, 1.0);
-            audio.onset = (audio.onset * 0.55 + 0.1).clamp(0.0, 1.0);
-            audio.beat = step % 24 == 0;
-            if audio.beat {
-                audio.beat_strength = 0.58;
-            }
-        }
-        2 => {
-            audio.rms = (0.28 + audio.rms * 0.55).clamp(0.0, 1.0);
-            audio.onset = (audio.onset * 0.7 + 0.16).clamp(0.0, 1.0);
-            audio.beat = step % 16 == 0 || audio.beat;
-            if audio.beat {
-                audio.beat_strength = audio.beat_strength.max(0.72);
-            }
-        }
-        _ => {
-            audio.rms = (0.38 + audio.rms * 0.60).clamp(0.0, 1.0);
-            audio.onset = (audio.onset * 0.85 + 0.24).clamp(0.0, 1.0);
-            audio.beat = step % 12 == 0 || audio.beat;
-            if audio.beat {
-                audio.beat_strength = audio.beat_strength.max(0.88);
-            }
-        }
-    }
-    audio
-}
-
 fn set_camera_path_mode(engine: &mut PresetEngine, target: CameraPathMode) {
     for _ in 0..6 {
         if engine.camera_path_mode() == target {
@@ -270,10 +188,12 @@ fn bench_section_aware_switching(args: &Args) {
     let mut lit = 0usize;
     let mut switches = 0usize;
     let mut last_name = engine.preset_name().to_string();
+    let mut snapshot = AudioSnapshot::default();
 
     for f in 0..frames {
         now += Duration::from_millis(40);
         let audio = section_audio(f, frames);
+        synth_snapshot(&audio, &mut snapshot);
         engine.update_auto_switch(now, &audio);
         let ctx = RenderCtx {
             now,
@@ -285,8 +205,12 @@ fn bench_section_aware_switching(args: &Args) {
             beat_pulse: if audio.beat { (0.6 + audio.beat_strength * 0.4).min(1.0) } else { 0.0 },
             fractal_zoom_mul: 1.0,
             safe: args.safe,
+            stereo: false,
             quality: args.quality,
             scale: args.scale,
+            spectrum: &snapshot.spectrum,
+            waveform: &snapshot.waveform,
+            side: &snapshot.side,
         };
         let px = engine.render(ctx, args.quality, args.scale);
         if px.chunks_exact(4).any(|p| p[0] != 0 || p[1] != 0 || p[2] != 0) {
@@ -331,6 +255,7 @@ fn bench_camera_path_modes(args: &Args) {
         args.w,
         args.h
     );
+    let mut snapshot = AudioSnapshot::default();
     for (mi, mode) in modes.iter().copied().enumerate() {
         set_camera_path_mode(&mut engine, mode);
 
@@ -339,6 +264,7 @@ fn bench_camera_path_modes(args: &Args) {
         for f in 0..frames {
             let t = f as f32 / 60.0;
             let audio = synth_audio(t + mi as f32 * 0.031, f + mi * 17);
+            synth_snapshot(&audio, &mut snapshot);
             let ctx = RenderCtx {
                 now: Instant::now(),
                 t,
@@ -349,8 +275,12 @@ fn bench_camera_path_modes(args: &Args) {
                 beat_pulse: if audio.beat { (0.6 + audio.beat_strength * 0.4).min(1.0) } else { 0.0 },
                 fractal_zoom_mul: 1.0,
                 safe: args.safe,
+                stereo: false,
                 quality: args.quality,
                 scale: args.scale,
+                spectrum: &snapshot.spectrum,
+                waveform: &snapshot.waveform,
+                side: &snapshot.side,
             };
             let px = engine.render(ctx, args.quality, args.scale);
             if px.chunks_exact(4).any(|p| p[0] != 0 || p[1] != 0 || p[2] != 0) {
@@ -376,6 +306,7 @@ fn bench_cpu(args: &Args) -> Result<()> {
     let mut total_frames = 0usize;
     let mut black_presets = Vec::<String>::new();
     let mut slow_presets = Vec::<(String, f64)>::new();
+    let mut snapshot = AudioSnapshot::default();
 
     println!(
         "CPU benchmark: presets={} frames/preset={} size={}x{} quality={:?} scale={} quick={}",
@@ -399,6 +330,7 @@ fn bench_cpu(args: &Args) -> Result<()> {
         for f in 0..args.frames {
             let t = f as f32 / 60.0;
             let audio = synth_audio(t, f);
+            synth_snapshot(&audio, &mut snapshot);
             let ctx = RenderCtx {
                 now: Instant::now(),
                 t,
@@ -409,8 +341,12 @@ fn bench_cpu(args: &Args) -> Result<()> {
                 beat_pulse: if audio.beat { (0.6 + audio.beat_strength * 0.4).min(1.0) } else { 0.0 },
                 fractal_zoom_mul: 1.0,
                 safe: args.safe,
+                stereo: false,
                 quality: args.quality,
                 scale: args.scale,
+                spectrum: &snapshot.spectrum,
+                waveform: &snapshot.waveform,
+                side: &snapshot.side,
             };
             p.render(&ctx, &prev, &mut out);
             if out.chunks_exact(4).any(|px| px[0] != 0 || px[1] != 0 || px[2] != 0) {
@@ -459,11 +395,11 @@ fn bench_cpu(args: &Args) -> Result<()> {
 
 #[cfg(target_os = "macos")]
 fn bench_metal(args: &Args) -> Result<()> {
-    let presets = make_presets();
-    let names = presets.iter().map(|p| p.name()).collect::<Vec<_>>();
+    let names = make_presets().iter().map(|p| p.name()).collect::<Vec<_>>();
 
     let mut total_time = Duration::ZERO;
     let mut total_frames = 0usize;
+    let mut snapshot = AudioSnapshot::default();
 
     println!(
         "Metal benchmark: presets={} frames/preset={} size={}x{} quality={:?} scale={}",
@@ -471,7 +407,7 @@ fn bench_metal(args: &Args) -> Result<()> {
     );
 
     for idx in 0..names.len() {
-        let mut eng = MetalEngine::new(names.clone(), idx, false, SwitchMode::Manual, 16, 20.0)?;
+        let mut eng = MetalEngine::new(make_presets(), idx, false, SwitchMode::Manual, 16, 20.0)?;
         eng.resize(args.w, args.h);
         let name = eng.preset_name();
 
@@ -481,6 +417,7 @@ fn bench_metal(args: &Args) -> Result<()> {
         for f in 0..args.frames {
             let t = f as f32 / 60.0;
             let audio = synth_audio(t, f);
+            synth_snapshot(&audio, &mut snapshot);
             let ctx = RenderCtx {
                 now: Instant::now(),
                 t,
@@ -491,8 +428,12 @@ fn bench_metal(args: &Args) -> Result<()> {
                 beat_pulse: if audio.beat { (0.6 + audio.beat_strength * 0.4).min(1.0) } else { 0.0 },
                 fractal_zoom_mul: 1.0,
                 safe: args.safe,
+                stereo: false,
                 quality: args.quality,
                 scale: args.scale,
+                spectrum: &snapshot.spectrum,
+                waveform: &snapshot.waveform,
+                side: &snapshot.side,
             };
             let px = eng.render(ctx, args.quality, args.scale);
             if px.chunks_exact(4).any(|p| p[0] != 0 || p[1] != 0 || p[2] != 0) {
@@ -1,10 +1,8 @@
 use std::cmp::Ordering;
-use std::f32::consts::PI;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Context, Result};
-use rustfft::num_complex::Complex;
-use rustfft::FftPlanner;
+use tui_visualizer::audio::{read_wav_mono_f32, Analyzer, AnalyzerConfig};
 
 #[derive(Debug, Clone)]
 struct Args {
@@ -15,6 +13,7 @@ struct Args {
     early_ms: f32,
     late_ms: f32,
     fail_over_ms: Option<f32>,
+    agc: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +42,7 @@ fn parse_args_from(argv: Vec<String>) -> Args {
         early_ms: 80.0,
         late_ms: 350.0,
         fail_over_ms: None,
+        agc: AnalyzerConfig::default().agc,
     };
 
     let mut i = 0usize;
@@ -90,6 +90,12 @@ fn parse_args_from(argv: Vec<String>) -> Args {
                 }
                 i += 2;
             }
+            ("--agc", Some(x)) => {
+                if let Ok(v) = x.parse::<bool>() {
+                    args.agc = v;
+                }
+                i += 2;
+            }
             _ => i += 1,
         }
     }
@@ -105,7 +111,11 @@ fn main() -> Result<()> {
         return Err(anyhow!("wav had no samples"));
     }
 
-    let detected = detect_beats_like_runtime(&samples, sample_rate_hz);
+    let analyzer_cfg = AnalyzerConfig {
+        agc: args.agc,
+        ..AnalyzerConfig::default()
+    };
+    let detected = detect_beats_like_runtime(&samples, sample_rate_hz, &analyzer_cfg)?;
     let expected = (0..args.pulse_count)
         .map(|i| args.pulse_start_s + i as f32 * args.pulse_interval_s)
         .collect::<Vec<_>>();
@@ -154,7 +164,10 @@ fn main() -> Result<()> {
             .join(", ");
         println!("  first_deltas_ms: [{}]", preview);
     }
-    println!("  analyzer window/hop: 1024/256");
+    println!(
+        "  analyzer window/hop: {}/{}  agc: {}",
+        analyzer_cfg.fft_size, analyzer_cfg.hop, analyzer_cfg.agc
+    );
 
     if let Some(limit) = args.fail_over_ms {
         if report.p95_ms > limit {
@@ -233,197 +246,30 @@ fn percentile(sorted: &[f32], p: f32) -> f32 {
     sorted[idx.min(sorted.len() - 1)]
 }
 
-fn detect_beats_like_runtime(samples: &[f32], sample_rate_hz: u32) -> Vec<f32> {
-    let n = 1024usize;
-    let hop = 256usize;
+/// Runs the runtime analyzer over the file and returns the beat times, dropping
+/// repeats within 90 ms.
+fn detect_beats_like_runtime(
+    samples: &[f32],
+    sample_rate_hz: u32,
+    cfg: &AnalyzerConfig,
+) -> Result<Vec<f32>> {
+    let mut analyzer = Analyzer::new(cfg.clone(), sample_rate_hz)?;
     let sr = sample_rate_hz as f32;
-
-    let hann = (0..n)
-        .map(|i| 0.5 - 0.5 * ((2.0 * PI * i as f32) / (n as f32)).cos())
-        .collect::<Vec<_>>();
-
-    let mut planner = FftPlanner::<f32>::new();
-    let fft = planner.plan_fft_forward(n);
-    let mut fft_buf = vec![Complex { re: 0.0, im: 0.0 }; n];
-    let mut mags = vec![0.0f32; n / 2];
-    let mut prev_mags = vec![0.0f32; n / 2];
-
-    let mut scratch = vec![0.0f32; n];
-    let mut write_pos = 0usize;
-    let mut filled = 0usize;
-    let mut since_last = 0usize;
-
-    let mut flux_avg = 0.0f32;
-    let mut flux_hist = [0.0f32; 3];
     let mut beats = Vec::<f32>::new();
 
     for (idx, &s) in samples.iter().enumerate() {
-        scratch[write_pos] = s;
-        write_pos = (write_pos + 1) % n;
-        if filled < n {
-            filled += 1;
-        }
-        since_last += 1;
-
-        if filled == n && since_last >= hop {
-            since_last = 0;
-            let flux = analyze_flux_window(
-                &scratch,
-                write_pos,
-                &hann,
-                &fft,
-                &mut fft_buf,
-                &mut mags,
-                &mut prev_mags,
-            );
-
-            flux_hist[0] = flux_hist[1];
-            flux_hist[1] = flux_hist[2];
-            flux_hist[2] = flux;
-            flux_avg = flux_avg * 0.95 + flux * 0.05;
-
-            let peak = flux_hist[1] > flux_hist[0] && flux_hist[1] > flux_hist[2];
-            let thr = (flux_avg * 1.45).max(1e-6);
-            let beat = peak && flux_hist[1] > thr;
-            if beat {
-                let t = idx as f32 / sr;
-                if beats.last().map(|last| t - *last > 0.09).unwrap_or(true) {
-                    beats.push(t);
-                }
-            }
-        }
-    }
-
-    beats
-}
-
-fn analyze_flux_window(
-    scratch: &[f32],
-    write_pos: usize,
-    hann: &[f32],
-    fft: &std::sync::Arc<dyn rustfft::Fft<f32>>,
-    fft_buf: &mut [Complex<f32>],
-    mags: &mut [f32],
-    prev_mags: &mut [f32],
-) -> f32 {
-    let n = fft_buf.len();
-    let half = mags.len();
-
-    for i in 0..n {
-        let s = scratch[(write_pos + i) % n];
-        fft_buf[i].re = s * hann[i];
-        fft_buf[i].im = 0.0;
-    }
-
-    fft.process(fft_buf);
-    for (i, c) in fft_buf.iter().take(half).enumerate() {
-        mags[i] = (c.re * c.re + c.im * c.im).sqrt();
-    }
-
-    let mut flux = 0.0f32;
-    for i in 0..half {
-        let d = mags[i] - prev_mags[i];
-        if d > 0.0 {
-            flux += d;
-        }
-        prev_mags[i] = mags[i];
-    }
-
-    let flux_scale = 0.002 * (1024.0 / (half as f32).max(1.0));
-    (flux * flux_scale).tanh()
-}
-
-fn read_wav_mono_f32(path: &PathBuf) -> Result<(u32, Vec<f32>)> {
-    let bytes = std::fs::read(path)?;
-    if bytes.len() < 44 {
-        return Err(anyhow!("wav too small"));
-    }
-    if &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
-        return Err(anyhow!("not a RIFF/WAVE file"));
-    }
-
-    let mut fmt_audio_format = 0u16;
-    let mut fmt_channels = 0u16;
-    let mut fmt_sample_rate = 0u32;
-    let mut fmt_bits = 0u16;
-    let mut data: Option<&[u8]> = None;
-
-    let mut pos = 12usize;
-    while pos + 8 <= bytes.len() {
-        let id = &bytes[pos..pos + 4];
-        let size =
-            u32::from_le_bytes([bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]])
-                as usize;
-        let start = pos + 8;
-        let end = start.saturating_add(size);
-        if end > bytes.len() {
-            break;
-        }
-
-        if id == b"fmt " {
-            if size < 16 {
-                return Err(anyhow!("invalid fmt chunk"));
+        let Some(features) = analyzer.push_frame([s, s]) else {
+            continue;
+        };
+        if features.beat {
+            let t = idx as f32 / sr;
+            if beats.last().map(|last| t - *last > 0.09).unwrap_or(true) {
+                beats.push(t);
             }
-            fmt_audio_format = u16::from_le_bytes([bytes[start], bytes[start + 1]]);
-            fmt_channels = u16::from_le_bytes([bytes[start + 2], bytes[start + 3]]);
-            fmt_sample_rate = u32::from_le_bytes([
-                bytes[start + 4],
-                bytes[start + 5],
-                bytes[start + 6],
-                bytes[start + 7],
-            ]);
-            fmt_bits = u16::from_le_bytes([bytes[start + 14], bytes[start + 15]]);
-        } else if id == b"data" {
-            data = Some(&bytes[start..end]);
         }
-
-        pos = end + (size % 2);
     }
 
-    let data = data.context("missing data chunk")?;
-    if fmt_channels == 0 {
-        return Err(anyhow!("invalid channel count"));
-    }
-
-    match (fmt_audio_format, fmt_bits) {
-        (1, 16) => {
-            let ch = fmt_channels as usize;
-            let total = data.len() / 2;
-            let frames = total / ch;
-            let mut out = Vec::<f32>::with_capacity(frames);
-            for i in 0..frames {
-                let mut acc = 0.0f32;
-                for c in 0..ch {
-                    let o = (i * ch + c) * 2;
-                    let s = i16::from_le_bytes([data[o], data[o + 1]]) as f32 / 32768.0;
-                    acc += s;
-                }
-                out.push((acc / ch as f32).clamp(-1.0, 1.0));
-            }
-            Ok((fmt_sample_rate, out))
-        }
-        (3, 32) => {
-            let ch = fmt_channels as usize;
-            let total = data.len() / 4;
-            let frames = total / ch;
-            let mut out = Vec::<f32>::with_capacity(frames);
-            for i in 0..frames {
-                let mut acc = 0.0f32;
-                for c in 0..ch {
-                    let o = (i * ch + c) * 4;
-                    let s = f32::from_le_bytes([data[o], data[o + 1], data[o + 2], data[o + 3]]);
-                    acc += s;
-                }
-                out.push((acc / ch as f32).clamp(-1.0, 1.0));
-            }
-            Ok((fmt_sample_rate, out))
-        }
-        _ => Err(anyhow!(
-            "unsupported wav format: audio_format={} bits={} (supported: PCM16, Float32)",
-            fmt_audio_format,
-            fmt_bits
-        )),
-    }
+    Ok(beats)
 }
 
 #[cfg(test)]
@@ -443,6 +289,7 @@ mod tests {
         assert!((args.early_ms - 80.0).abs() < 1e-6);
         assert!((args.late_ms - 350.0).abs() < 1e-6);
         assert_eq!(args.fail_over_ms, None);
+        assert!(!args.agc);
     }
 
     #[test]
@@ -461,6 +308,8 @@ mod tests {
                 "0",
                 "--fail-over-ms",
                 "0",
+                "--agc",
+                "true",
             ]
             .iter()
             .map(|s| s.to_string())
@@ -473,6 +322,7 @@ mod tests {
         assert!((args.early_ms - 0.0).abs() < 1e-6);
         assert!((args.late_ms - 1.0).abs() < 1e-6);
         assert_eq!(args.fail_over_ms, Some(0.1));
+        assert!(args.agc);
     }
 
     #[test]
@@ -1,4 +1,4 @@
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, ValueEnum};
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "tui-visualizer", version, about = "Psychedelic, audio-reactive terminal visualizer (Ghostty/macOS v1)")]
@@ -6,6 +6,36 @@ pub struct Config {
     #[arg(long, value_enum, default_value_t = AudioSource::Mic)]
     pub source: AudioSource,
 
+    #[arg(long)]
+    pub input: Option<String>,
+
+    #[arg(long, default_value_t = false)]
+    pub input_loop: bool,
+
+    #[arg(long, value_name = "PATH")]
+    pub record_features: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = SynthPattern::Sections)]
+    pub synth_pattern: SynthPattern,
+
+    #[arg(long, default_value_t = 124.0)]
+    pub synth_bpm: f32,
+
+    #[arg(long, value_enum, default_value_t = PcmFormat::F32le)]
+    pub stdin_format: PcmFormat,
+
+    #[arg(long, default_value_t = 48_000)]
+    pub stdin_rate: u32,
+
+    #[arg(long, default_value_t = 2)]
+    pub stdin_channels: u16,
+
+    #[command(flatten)]
+    pub analyzer: AnalyzerArgs,
+
+    #[arg(long, default_value_t = false)]
+    pub stereo_split: bool,
+
     #[arg(long, value_enum, default_value_t = EngineMode::Metal)]
     pub engine: EngineMode,
 
@@ -39,9 +69,13 @@ pub struct Config {
     #[arg(long, default_value_t = false)]
     pub list_devices: bool,
 
+    /// Input device by `--list-devices` index or name substring.
     #[arg(long)]
     pub device: Option<String>,
 
+    #[command(flatten)]
+    pub capture: CaptureArgs,
+
     #[arg(long, default_value_t = false)]
     pub safe: bool,
 
@@ -66,6 +100,15 @@ pub struct Config {
     #[arg(long)]
     pub preset_graph: Option<String>,
 
+    /// Extra directory of `.preset` definition files loaded after the built-ins.
+    #[arg(long)]
+    pub preset_dir: Option<String>,
+
+    /// Seed for every random choice (preset noise, transitions, shuffle, graph
+    /// `chance` edges); random per run when unset.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
     pub sync_updates: bool,
 
@@ -80,12 +123,171 @@ pub struct Config {
 
     #[arg(long, value_enum, default_value_t = SystemDataMode::Off)]
     pub system_data: SystemDataMode,
+
+    /// Drive the visuals from a slow synthetic feature stream while the input is silent.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
+    pub idle: bool,
+
+    /// How far to dim the frame while idle (0 = no dimming, 1 = black).
+    #[arg(long, default_value_t = 0.3)]
+    pub idle_dim: f32,
+
+    /// Seconds between preset changes while idle with auto-switch on (0 = never).
+    #[arg(long, default_value_t = 20.0)]
+    pub idle_attract: f32,
+}
+
+/// Analysis flags shared by the visualizer and the offline tools, so a batch
+/// export can be run with exactly the live settings.
+#[derive(Args, Debug, Clone)]
+pub struct AnalyzerArgs {
+    #[arg(long, default_value_t = 64)]
+    pub spectrum_bins: usize,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
+    pub agc: bool,
+
+    #[arg(long, default_value_t = 1024)]
+    pub fft_size: usize,
+
+    #[arg(long, default_value_t = 256)]
+    pub hop: usize,
+
+    #[arg(long, value_enum, default_value_t = WindowFunction::Hann)]
+    pub window: WindowFunction,
+
+    #[arg(long, default_value_t = 8)]
+    pub bands: usize,
+
+    #[arg(long, value_enum, default_value_t = BandSpacing::Classic)]
+    pub band_spacing: BandSpacing,
+
+    #[arg(long, value_delimiter = ',')]
+    pub band_edges: Vec<f32>,
+
+    #[arg(long, default_value_t = -60.0, allow_negative_numbers = true)]
+    pub silence_threshold_db: f32,
+
+    /// Seconds below the silence threshold before the input counts as silent.
+    #[arg(long, default_value_t = 1.5)]
+    pub silence_hold: f32,
+}
+
+/// Stream settings for `--source mic`; anything left unset uses the device default.
+#[derive(Args, Debug, Clone, Default)]
+pub struct CaptureArgs {
+    /// Capture sample rate in Hz.
+    #[arg(long, value_name = "HZ")]
+    pub capture_rate: Option<u32>,
+
+    /// Input channels to analyze, 1-based (`3-4`, `1,2` or `5`); default all.
+    #[arg(long, value_name = "CHANNELS")]
+    pub capture_channels: Option<ChannelSelection>,
+
+    /// Driver buffer size in frames.
+    #[arg(long, value_name = "FRAMES")]
+    pub capture_buffer: Option<u32>,
+}
+
+/// A set of input channels, stored 0-based in the order given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSelection(Vec<u16>);
+
+impl ChannelSelection {
+    pub fn indices(&self) -> &[u16] {
+        &self.0
+    }
+
+    /// Channel count a stream needs to carry every selected channel.
+    pub fn min_channels(&self) -> u16 {
+        self.0.iter().max().map_or(0, |&c| c + 1)
+    }
+}
+
+impl std::str::FromStr for ChannelSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let channel = |t: &str| match t.trim().parse::<u16>() {
+            Ok(c) if c >= 1 => Ok(c - 1),
+            _ => Err(format!("invalid channel {t:?} (channels start at 1)")),
+        };
+        let mut out = Vec::new();
+        for part in s.split(',') {
+            match part.split_once('-') {
+                Some((lo, hi)) => {
+                    let (lo, hi) = (channel(lo)?, channel(hi)?);
+                    if lo > hi {
+                        return Err(format!("channel range {part:?} runs backwards"));
+                    }
+                    out.extend(lo..=hi);
+                }
+                None => out.push(channel(part)?),
+            }
+        }
+        Ok(Self(out))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum AudioSource {
     Mic,
     System,
+    #[value(alias = "wav")]
+    File,
+    #[value(alias = "pipe")]
+    Stdin,
+    Replay,
+    /// Tempo-synced synthetic features; no audio input needed.
+    Synth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SynthPattern {
+    #[value(name = "four-on-the-floor", alias = "four", alias = "house")]
+    FourOnTheFloor,
+    #[value(alias = "breaks")]
+    Breakbeat,
+    /// Beatless swells.
+    Ambient,
+    /// Calm, groove, build-up and drop, eight bars each.
+    Sections,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PcmFormat {
+    #[value(alias = "f32")]
+    F32le,
+    #[value(alias = "s16")]
+    S16le,
+}
+
+impl PcmFormat {
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            Self::F32le => 4,
+            Self::S16le => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WindowFunction {
+    Hann,
+    Hamming,
+    Blackman,
+    #[value(alias = "none", alias = "rectangular")]
+    Rect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BandSpacing {
+    /// The fixed sub/bass/low-mid/mid/high-mid/treble/air/presence split (8 bands;
+    /// other counts fall back to `log`).
+    Classic,
+    Log,
+    Linear,
+    Mel,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -1,9 +1,21 @@
-use crate::audio::AudioFeatures;
+use crate::audio::{AudioFeatures, MAX_BANDS};
 use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 use std::path::Path;
 
-pub const FEATURE_KEY_COUNT: usize = 17;
+pub const FEATURE_KEY_COUNT: usize = 40 + MAX_BANDS;
+
+const BAND_KEY_NAMES: [&str; MAX_BANDS] = [
+    "band0", "band1", "band2", "band3", "band4", "band5", "band6", "band7", "band8", "band9",
+    "band10", "band11", "band12", "band13", "band14", "band15", "band16", "band17", "band18",
+    "band19", "band20", "band21", "band22", "band23", "band24", "band25", "band26", "band27",
+    "band28", "band29", "band30", "band31",
+];
+
+const CHROMA_KEY_NAMES: [&str; 12] = [
+    "chroma0", "chroma1", "chroma2", "chroma3", "chroma4", "chroma5", "chroma6", "chroma7",
+    "chroma8", "chroma9", "chroma10", "chroma11",
+];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FeatureKey {
@@ -13,17 +25,40 @@ pub enum FeatureKey {
     BeatStrength,
     Centroid,
     Flatness,
-    Band0,
-    Band1,
-    Band2,
-    Band3,
-    Band4,
-    Band5,
-    Band6,
-    Band7,
+    /// Band `n` of the configured layout; reads 0 past the configured band count.
+    Band(u8),
     Bass,
     Mid,
     Treble,
+    Bpm,
+    BeatPhase,
+    BarPhase,
+    Kick,
+    Snare,
+    Hat,
+    RmsLeft,
+    RmsRight,
+    Balance,
+    StereoWidth,
+    /// Dominant pitch, 50..2000 Hz on a log scale; 0 when nothing is pitched.
+    Pitch,
+    /// Pitch class of the dominant pitch (C = 0, steps of 1/12).
+    PitchClass,
+    /// Estimated key tonic (C = 0, steps of 1/12).
+    Key,
+    /// 1 for a major key, 0 for minor.
+    KeyMode,
+    KeyConfidence,
+    /// Envelope that jumps to 1 when the estimated key changes.
+    KeyChange,
+    /// Chroma energy of pitch class `n` (C = 0).
+    Chroma(u8),
+    /// Momentary (400 ms) K-weighted loudness, -40..-8 LUFS.
+    Loudness,
+    /// Short-term (3 s) K-weighted loudness, -40..-8 LUFS.
+    LoudnessShort,
+    /// Crest factor, 3 dB (flat) .. 18 dB (punchy).
+    Crest,
 }
 
 impl FeatureKey {
@@ -35,18 +70,38 @@ impl FeatureKey {
             "beat_strength" => Some(Self::BeatStrength),
             "centroid" => Some(Self::Centroid),
             "flatness" => Some(Self::Flatness),
-            "band0" => Some(Self::Band0),
-            "band1" => Some(Self::Band1),
-            "band2" => Some(Self::Band2),
-            "band3" => Some(Self::Band3),
-            "band4" => Some(Self::Band4),
-            "band5" => Some(Self::Band5),
-            "band6" => Some(Self::Band6),
-            "band7" => Some(Self::Band7),
             "bass" => Some(Self::Bass),
             "mid" => Some(Self::Mid),
             "treble" => Some(Self::Treble),
-            _ => None,
+            "bpm" => Some(Self::Bpm),
+            "beat_phase" => Some(Self::BeatPhase),
+            "bar_phase" => Some(Self::BarPhase),
+            "kick" => Some(Self::Kick),
+            "snare" => Some(Self::Snare),
+            "hat" | "hihat" => Some(Self::Hat),
+            "rms_left" => Some(Self::RmsLeft),
+            "rms_right" => Some(Self::RmsRight),
+            "balance" | "pan" => Some(Self::Balance),
+            "stereo_width" | "width" => Some(Self::StereoWidth),
+            "pitch" => Some(Self::Pitch),
+            "pitch_class" => Some(Self::PitchClass),
+            "key" => Some(Self::Key),
+            "key_mode" | "mode" => Some(Self::KeyMode),
+            "key_confidence" => Some(Self::KeyConfidence),
+            "key_change" => Some(Self::KeyChange),
+            "loudness" | "lufs" => Some(Self::Loudness),
+            "loudness_short" => Some(Self::LoudnessShort),
+            "crest" | "dynamics" => Some(Self::Crest),
+            _ => BAND_KEY_NAMES
+                .iter()
+                .position(|&name| name == s)
+                .map(|n| Self::Band(n as u8))
+                .or_else(|| {
+                    CHROMA_KEY_NAMES
+                        .iter()
+                        .position(|&name| name == s)
+                        .map(|n| Self::Chroma(n as u8))
+                }),
         }
     }
 
@@ -58,39 +113,65 @@ impl FeatureKey {
             Self::BeatStrength => "beat_strength",
             Self::Centroid => "centroid",
             Self::Flatness => "flatness",
-            Self::Band0 => "band0",
-            Self::Band1 => "band1",
-            Self::Band2 => "band2",
-            Self::Band3 => "band3",
-            Self::Band4 => "band4",
-            Self::Band5 => "band5",
-            Self::Band6 => "band6",
-            Self::Band7 => "band7",
+            Self::Band(n) => BAND_KEY_NAMES[n as usize],
             Self::Bass => "bass",
             Self::Mid => "mid",
             Self::Treble => "treble",
+            Self::Bpm => "bpm",
+            Self::BeatPhase => "beat_phase",
+            Self::BarPhase => "bar_phase",
+            Self::Kick => "kick",
+            Self::Snare => "snare",
+            Self::Hat => "hat",
+            Self::RmsLeft => "rms_left",
+            Self::RmsRight => "rms_right",
+            Self::Balance => "balance",
+            Self::StereoWidth => "stereo_width",
+            Self::Pitch => "pitch",
+            Self::PitchClass => "pitch_class",
+            Self::Key => "key",
+            Self::KeyMode => "key_mode",
+            Self::KeyConfidence => "key_confidence",
+            Self::KeyChange => "key_change",
+            Self::Chroma(n) => CHROMA_KEY_NAMES[n as usize],
+            Self::Loudness => "loudness",
+            Self::LoudnessShort => "loudness_short",
+            Self::Crest => "crest",
         }
     }
 
     fn index(self) -> usize {
         match self {
-            Self::Rms => 0,
-            Self::Onset => 1,
-            Self::BeatGate => 2,
-            Self::BeatStrength => 3,
-            Self::Centroid => 4,
-            Self::Flatness => 5,
-            Self::Band0 => 6,
-            Self::Band1 => 7,
-            Self::Band2 => 8,
-            Self::Band3 => 9,
-            Self::Band4 => 10,
-            Self::Band5 => 11,
-            Self::Band6 => 12,
-            Self::Band7 => 13,
-            Self::Bass => 14,
-            Self::Mid => 15,
-            Self::Treble => 16,
+            Self::Band(n) => n as usize,
+            Self::Rms => MAX_BANDS,
+            Self::Onset => MAX_BANDS + 1,
+            Self::BeatGate => MAX_BANDS + 2,
+            Self::BeatStrength => MAX_BANDS + 3,
+            Self::Centroid => MAX_BANDS + 4,
+            Self::Flatness => MAX_BANDS + 5,
+            Self::Bass => MAX_BANDS + 6,
+            Self::Mid => MAX_BANDS + 7,
+            Self::Treble => MAX_BANDS + 8,
+            Self::Bpm => MAX_BANDS + 9,
+            Self::BeatPhase => MAX_BANDS + 10,
+            Self::BarPhase => MAX_BANDS + 11,
+            Self::Kick => MAX_BANDS + 12,
+            Self::Snare => MAX_BANDS + 13,
+            Self::Hat => MAX_BANDS + 14,
+            Self::RmsLeft => MAX_BANDS + 15,
+            Self::RmsRight => MAX_BANDS + 16,
+            Self::Balance => MAX_BANDS + 17,
+            Self::StereoWidth => MAX_BANDS + 18,
+            Self::Pitch => MAX_BANDS + 19,
+            Self::PitchClass => MAX_BANDS + 20,
+            Self::Key => MAX_BANDS + 21,
+            Self::KeyMode => MAX_BANDS + 22,
+            Self::KeyConfidence => MAX_BANDS + 23,
+            Self::KeyChange => MAX_BANDS + 24,
+            Self::Chroma(n) => MAX_BANDS + 25 + n as usize,
+            Self::Loudness => MAX_BANDS + 37,
+            Self::LoudnessShort => MAX_BANDS + 38,
+            Self::Crest => MAX_BANDS + 39,
         }
     }
 }
@@ -109,22 +190,49 @@ impl ExpandedFeatureVector {
         values[FeatureKey::BeatStrength.index()] = clamp01(audio.beat_strength);
         values[FeatureKey::Centroid.index()] = clamp01(audio.centroid);
         values[FeatureKey::Flatness.index()] = clamp01(audio.flatness);
-        values[FeatureKey::Band0.index()] = clamp01(audio.bands[0]);
-        values[FeatureKey::Band1.index()] = clamp01(audio.bands[1]);
-        values[FeatureKey::Band2.index()] = clamp01(audio.bands[2]);
-        values[FeatureKey::Band3.index()] = clamp01(audio.bands[3]);
-        values[FeatureKey::Band4.index()] = clamp01(audio.bands[4]);
-        values[FeatureKey::Band5.index()] = clamp01(audio.bands[5]);
-        values[FeatureKey::Band6.index()] = clamp01(audio.bands[6]);
-        values[FeatureKey::Band7.index()] = clamp01(audio.bands[7]);
-
-        let bass = (audio.bands[0] + audio.bands[1] + audio.bands[2]) / 3.0;
-        let mid = (audio.bands[2] + audio.bands[3] + audio.bands[4]) / 3.0;
-        let treble = (audio.bands[5] + audio.bands[6] + audio.bands[7]) / 3.0;
+        for (n, &b) in audio.bands.iter().enumerate().take(audio.band_count) {
+            values[FeatureKey::Band(n as u8).index()] = clamp01(b);
+        }
+
+        let b = audio.bands8();
+        let bass = (b[0] + b[1] + b[2]) / 3.0;
+        let mid = (b[2] + b[3] + b[4]) / 3.0;
+        let treble = (b[5] + b[6] + b[7]) / 3.0;
         values[FeatureKey::Bass.index()] = clamp01(bass);
         values[FeatureKey::Mid.index()] = clamp01(mid);
         values[FeatureKey::Treble.index()] = clamp01(treble);
 
+        // Tempo maps 60..200 BPM onto 0..1; stays 0 while the tracker has no lock.
+        if audio.bpm > 0.0 {
+            values[FeatureKey::Bpm.index()] = clamp01((audio.bpm - 60.0) / 140.0);
+        }
+        values[FeatureKey::BeatPhase.index()] = clamp01(audio.beat_phase);
+        values[FeatureKey::BarPhase.index()] = clamp01(audio.bar_phase);
+        values[FeatureKey::Kick.index()] = clamp01(audio.kick);
+        values[FeatureKey::Snare.index()] = clamp01(audio.snare);
+        values[FeatureKey::Hat.index()] = clamp01(audio.hat);
+        values[FeatureKey::RmsLeft.index()] = clamp01(audio.rms_left);
+        values[FeatureKey::RmsRight.index()] = clamp01(audio.rms_right);
+        // Balance maps hard left..hard right onto 0..1, so centre is 0.5.
+        values[FeatureKey::Balance.index()] = clamp01(audio.balance * 0.5 + 0.5);
+        values[FeatureKey::StereoWidth.index()] = clamp01(audio.stereo_width);
+
+        if audio.pitch_hz > 0.0 {
+            values[FeatureKey::Pitch.index()] = clamp01((audio.pitch_hz / 50.0).log2() / 40f32.log2());
+            let midi = 69.0 + 12.0 * (audio.pitch_hz / 440.0).log2();
+            values[FeatureKey::PitchClass.index()] = (midi.round() as i32).rem_euclid(12) as f32 / 12.0;
+        }
+        values[FeatureKey::Key.index()] = f32::from(audio.key % 12) / 12.0;
+        values[FeatureKey::KeyMode.index()] = if audio.key_minor { 0.0 } else { 1.0 };
+        values[FeatureKey::KeyConfidence.index()] = clamp01(audio.key_confidence);
+        values[FeatureKey::KeyChange.index()] = clamp01(audio.key_change);
+        for (n, &c) in audio.chroma.iter().enumerate() {
+            values[FeatureKey::Chroma(n as u8).index()] = clamp01(c);
+        }
+        values[FeatureKey::Loudness.index()] = audio.loudness();
+        values[FeatureKey::LoudnessShort.index()] = audio.loudness_short();
+        values[FeatureKey::Crest.index()] = audio.dynamics();
+
         Self { values }
     }
 
@@ -172,6 +280,15 @@ impl ControlCurve {
     }
 }
 
+/// Prefix of routes that drive a parameter of the active preset
+/// (`preset.freq`, `preset.feedback.zoom`) instead of a global control.
+pub const PRESET_PARAM_PREFIX: &str = "preset.";
+
+/// The preset parameter a `preset.<param>` control name targets.
+pub fn preset_param_control(control: &str) -> Option<&str> {
+    control.strip_prefix(PRESET_PARAM_PREFIX)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ControlRoute {
     pub control: String,
@@ -246,6 +363,12 @@ impl ControlMatrix {
             }
 
             let control = tokens[1].to_string();
+            if preset_param_control(&control) == Some("") {
+                return Err(ControlMatrixError::Parse {
+                    line: line_no,
+                    message: "preset route needs a parameter name: preset.<param>".to_string(),
+                });
+            }
             let source =
                 FeatureKey::parse(tokens[2]).ok_or_else(|| ControlMatrixError::Parse {
                     line: line_no,
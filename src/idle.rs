@@ -0,0 +1,99 @@
+//! Idle ("attract") behaviour while the analyzer reports silence: a slow
+//! synthetic feature stream keeps the visuals moving, the frame can dim, and
+//! presets rotate on a timer. The first non-silent frame ends it.
+
+use crate::audio::AudioFeatures;
+use std::time::{Duration, Instant};
+
+/// Time the dimming takes to reach its full depth once idle starts.
+const DIM_FADE_S: f32 = 3.0;
+
+pub struct IdleMode {
+    dim: f32,
+    attract_every: Option<Duration>,
+    idle_since: Option<Instant>,
+    last_rotation: Instant,
+}
+
+impl IdleMode {
+    /// `dim` is the fraction of brightness removed while idle; `attract_s` is
+    /// the preset rotation interval, 0 to disable rotation.
+    pub fn new(dim: f32, attract_s: f32) -> Self {
+        Self {
+            dim: if dim.is_finite() { dim.clamp(0.0, 1.0) } else { 0.0 },
+            attract_every: (attract_s.is_finite() && attract_s > 0.0)
+                .then(|| Duration::from_secs_f32(attract_s.max(1.0))),
+            idle_since: None,
+            last_rotation: Instant::now(),
+        }
+    }
+
+    /// Feeds the latest silence flag; returns whether the app is idle.
+    pub fn update(&mut self, now: Instant, silent: bool) -> bool {
+        match (silent, self.idle_since) {
+            (true, None) => {
+                self.idle_since = Some(now);
+                self.last_rotation = now;
+            }
+            (false, Some(_)) => self.idle_since = None,
+            _ => {}
+        }
+        self.idle_since.is_some()
+    }
+
+    /// Brightness multiplier for the rendered frame: eases from 1 down to
+    /// `1 - dim` after idle starts and is 1 again as soon as sound returns.
+    pub fn brightness(&self, now: Instant) -> f32 {
+        let Some(since) = self.idle_since else {
+            return 1.0;
+        };
+        let x = (now.duration_since(since).as_secs_f32() / DIM_FADE_S).clamp(0.0, 1.0);
+        1.0 - self.dim * x * x * (3.0 - 2.0 * x)
+    }
+
+    /// True once per attract interval while idle.
+    pub fn attract_due(&mut self, now: Instant) -> bool {
+        let (Some(_), Some(every)) = (self.idle_since, self.attract_every) else {
+            return false;
+        };
+        if now.duration_since(self.last_rotation) < every {
+            return false;
+        }
+        self.last_rotation = now;
+        true
+    }
+}
+
+/// Synthetic features for idle motion: slow, beatless drifts in level and
+/// spectral shape so presets keep breathing without any input.
+pub fn idle_features(t: f32) -> AudioFeatures {
+    let mut bands = [0.0f32; 8];
+    for (i, b) in bands.iter_mut().enumerate() {
+        let phase = i as f32 * 0.8;
+        *b = 0.22 + 0.14 * (t * 0.31 + phase).sin() + 0.06 * (t * 0.13 - phase * 0.5).sin();
+    }
+    let swell = 0.5 + 0.5 * (t * 0.17).sin();
+    AudioFeatures {
+        rms: 0.16 + 0.08 * swell,
+        bands: AudioFeatures::pad_bands(&bands),
+        band_count: bands.len(),
+        centroid: 0.35 + 0.15 * (t * 0.11).sin(),
+        flatness: 0.3,
+        rms_left: 0.16 + 0.08 * swell,
+        rms_right: 0.16 + 0.08 * swell,
+        balance: 0.3 * (t * 0.07).sin(),
+        stereo_width: 0.2,
+        silent: true,
+        ..AudioFeatures::default()
+    }
+}
+
+/// Scales the RGB channels of an RGBA buffer by `brightness`.
+pub fn dim_rgba(buf: &mut [u8], brightness: f32) {
+    let k = (brightness.clamp(0.0, 1.0) * 256.0) as u32;
+    for px in buf.chunks_exact_mut(4) {
+        for c in &mut px[..3] {
+            *c = ((*c as u32 * k) >> 8) as u8;
+        }
+    }
+}
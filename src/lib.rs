@@ -3,10 +3,15 @@ pub mod audio;
 pub mod capability;
 pub mod config;
 pub mod control_matrix;
+pub mod idle;
 pub mod lyrics;
+pub mod preset_file;
 pub mod preset_graph;
+pub mod preset_id;
 pub mod prefs;
+pub mod recording;
 pub mod render;
+pub mod synth;
 pub mod system_data;
 pub mod terminal;
 pub mod theme_pack;
@@ -0,0 +1,291 @@
+use std::fmt;
+use std::path::Path;
+
+/// A user preset read from a `.preset` file: a field algorithm with its
+/// parameters, a palette, feedback settings and post-FX scaling. Algorithm and
+/// palette names are resolved by `visual::check_definition`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresetDefinition {
+    pub name: String,
+    pub algo: String,
+    /// `algo.<param>` values in file order; pairs such as `center` hold two numbers.
+    pub params: Vec<(String, Vec<f32>)>,
+    pub palette: String,
+    pub feedback: FeedbackSpec,
+    pub post_fx: PostFxSpec,
+    pub meta: MetaSpec,
+}
+
+/// Optional metadata overrides. The category defaults to the algorithm's,
+/// energy and motion to `medium`, flash risk to `low`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetaSpec {
+    pub category: Option<String>,
+    pub energy: Option<String>,
+    pub motion: Option<String>,
+    pub flash_risk: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Feedback tunnel settings; `enabled = false` draws every frame from scratch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedbackSpec {
+    pub enabled: bool,
+    pub fade: f32,
+    pub warp: f32,
+    pub warp_freq: f32,
+    pub zoom: f32,
+}
+
+impl Default for FeedbackSpec {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fade: 0.9,
+            warp: 0.02,
+            warp_freq: 2.2,
+            zoom: 1.4,
+        }
+    }
+}
+
+/// Multipliers on the audio-driven post-FX amounts; 1.0 keeps the built-in
+/// routing, 0.0 switches that effect off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostFxSpec {
+    pub mix: f32,
+    pub chroma: f32,
+    pub scanline: f32,
+    pub vignette: f32,
+    pub bloom: f32,
+}
+
+impl Default for PostFxSpec {
+    fn default() -> Self {
+        Self {
+            mix: 1.0,
+            chroma: 1.0,
+            scanline: 1.0,
+            vignette: 1.0,
+            bloom: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetFileError {
+    Io(String),
+    Parse { line: usize, message: String },
+    MissingField(&'static str),
+    InvalidValue { field: &'static str, message: String },
+}
+
+impl fmt::Display for PresetFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(msg) => write!(f, "I/O error: {msg}"),
+            Self::Parse { line, message } => write!(f, "parse error at line {line}: {message}"),
+            Self::MissingField(field) => write!(f, "missing required field: {field}"),
+            Self::InvalidValue { field, message } => {
+                write!(f, "invalid value for {field}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PresetFileError {}
+
+impl PresetDefinition {
+    pub fn parse(text: &str) -> Result<Self, PresetFileError> {
+        let mut name: Option<String> = None;
+        let mut algo: Option<String> = None;
+        let mut palette: Option<String> = None;
+        let mut params: Vec<(String, Vec<f32>)> = Vec::new();
+        let mut feedback = FeedbackSpec::default();
+        let mut post_fx = PostFxSpec::default();
+        let mut meta = MetaSpec::default();
+        let mut seen = Vec::<String>::new();
+
+        for (line_idx, raw) in text.lines().enumerate() {
+            let line_no = line_idx + 1;
+            let trimmed = raw.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = trimmed.split_once('=').ok_or(PresetFileError::Parse {
+                line: line_no,
+                message: "expected <key>=<value>".to_string(),
+            })?;
+            let key = key.trim();
+            let value = value.trim();
+            if seen.iter().any(|k| k == key) {
+                return Err(PresetFileError::Parse {
+                    line: line_no,
+                    message: format!("duplicate '{key}' field"),
+                });
+            }
+            seen.push(key.to_string());
+
+            match key {
+                "name" => name = Some(value.to_string()),
+                "algo" => algo = Some(value.to_ascii_lowercase()),
+                "palette" => palette = Some(value.to_ascii_lowercase()),
+                "feedback" => {
+                    feedback.enabled = match value {
+                        "tunnel" => true,
+                        "none" => false,
+                        _ => {
+                            return Err(PresetFileError::Parse {
+                                line: line_no,
+                                message: "feedback must be 'tunnel' or 'none'".to_string(),
+                            })
+                        }
+                    }
+                }
+                "feedback.fade" => feedback.fade = parse_f32(value, line_no, key)?,
+                "feedback.warp" => feedback.warp = parse_f32(value, line_no, key)?,
+                "feedback.warp_freq" => feedback.warp_freq = parse_f32(value, line_no, key)?,
+                "feedback.zoom" => feedback.zoom = parse_f32(value, line_no, key)?,
+                "postfx.mix" => post_fx.mix = parse_f32(value, line_no, key)?,
+                "postfx.chroma" => post_fx.chroma = parse_f32(value, line_no, key)?,
+                "postfx.scanline" => post_fx.scanline = parse_f32(value, line_no, key)?,
+                "postfx.vignette" => post_fx.vignette = parse_f32(value, line_no, key)?,
+                "postfx.bloom" => post_fx.bloom = parse_f32(value, line_no, key)?,
+                "category" => meta.category = Some(value.to_ascii_lowercase()),
+                "energy" => meta.energy = Some(value.to_ascii_lowercase()),
+                "motion" => meta.motion = Some(value.to_ascii_lowercase()),
+                "flash_risk" => meta.flash_risk = Some(value.to_ascii_lowercase()),
+                "tags" => {
+                    meta.tags = value
+                        .split(',')
+                        .map(|t| t.trim().to_ascii_lowercase())
+                        .filter(|t| !t.is_empty())
+                        .collect();
+                }
+                _ => match key.strip_prefix("algo.") {
+                    Some(param) if !param.is_empty() => {
+                        params.push((param.to_string(), parse_csv_f32(value, line_no, key)?));
+                    }
+                    _ => {
+                        return Err(PresetFileError::Parse {
+                            line: line_no,
+                            message: format!("unknown key '{key}'"),
+                        });
+                    }
+                },
+            }
+        }
+
+        let definition = Self {
+            name: name.ok_or(PresetFileError::MissingField("name"))?,
+            algo: algo.ok_or(PresetFileError::MissingField("algo"))?,
+            params,
+            palette: palette.ok_or(PresetFileError::MissingField("palette"))?,
+            feedback,
+            post_fx,
+            meta,
+        };
+
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PresetFileError> {
+        let text =
+            std::fs::read_to_string(path.as_ref()).map_err(|e| PresetFileError::Io(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!("name={}", self.name),
+            format!("algo={}", self.algo),
+        ];
+        for (param, values) in &self.params {
+            let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+            lines.push(format!("algo.{param}={}", values.join(",")));
+        }
+        lines.push(format!("palette={}", self.palette));
+        let fb = &self.feedback;
+        lines.push(format!("feedback={}", if fb.enabled { "tunnel" } else { "none" }));
+        lines.push(format!("feedback.fade={}", fb.fade));
+        lines.push(format!("feedback.warp={}", fb.warp));
+        lines.push(format!("feedback.warp_freq={}", fb.warp_freq));
+        lines.push(format!("feedback.zoom={}", fb.zoom));
+        let fx = &self.post_fx;
+        lines.push(format!("postfx.mix={}", fx.mix));
+        lines.push(format!("postfx.chroma={}", fx.chroma));
+        lines.push(format!("postfx.scanline={}", fx.scanline));
+        lines.push(format!("postfx.vignette={}", fx.vignette));
+        lines.push(format!("postfx.bloom={}", fx.bloom));
+        let meta = &self.meta;
+        for (key, value) in [
+            ("category", &meta.category),
+            ("energy", &meta.energy),
+            ("motion", &meta.motion),
+            ("flash_risk", &meta.flash_risk),
+        ] {
+            if let Some(value) = value {
+                lines.push(format!("{key}={value}"));
+            }
+        }
+        if !meta.tags.is_empty() {
+            lines.push(format!("tags={}", meta.tags.join(",")));
+        }
+        lines.join("\n")
+    }
+
+    pub fn validate(&self) -> Result<(), PresetFileError> {
+        if self.name.trim().is_empty() {
+            return Err(PresetFileError::InvalidValue {
+                field: "name",
+                message: "name must not be empty".to_string(),
+            });
+        }
+        if !(0.0..=1.0).contains(&self.feedback.fade) {
+            return Err(PresetFileError::InvalidValue {
+                field: "feedback.fade",
+                message: "must be in [0,1]".to_string(),
+            });
+        }
+        if self.feedback.zoom <= 0.0 {
+            return Err(PresetFileError::InvalidValue {
+                field: "feedback.zoom",
+                message: "must be > 0".to_string(),
+            });
+        }
+        let fx = &self.post_fx;
+        for (field, v) in [
+            ("postfx.mix", fx.mix),
+            ("postfx.chroma", fx.chroma),
+            ("postfx.scanline", fx.scanline),
+            ("postfx.vignette", fx.vignette),
+            ("postfx.bloom", fx.bloom),
+        ] {
+            if !(0.0..=2.0).contains(&v) {
+                return Err(PresetFileError::InvalidValue {
+                    field,
+                    message: "must be in [0,2]".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_f32(s: &str, line: usize, field: &str) -> Result<f32, PresetFileError> {
+    match s.parse::<f32>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(PresetFileError::Parse {
+            line,
+            message: format!("invalid float for {field}"),
+        }),
+    }
+}
+
+fn parse_csv_f32(s: &str, line: usize, field: &str) -> Result<Vec<f32>, PresetFileError> {
+    s.split(',')
+        .map(|part| parse_f32(part.trim(), line, field))
+        .collect()
+}
@@ -1,6 +1,9 @@
+use crate::audio::AudioFeatures;
+use crate::preset_id::PresetRef;
 use std::collections::HashMap;
 use std::fmt;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PresetGraph {
@@ -11,7 +14,7 @@ pub struct PresetGraph {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GraphNode {
     pub id: String,
-    pub preset_index: usize,
+    pub preset: PresetRef,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -27,9 +30,28 @@ pub enum GraphOp {
     OnBeat,
     BeatStrengthGe(f32),
     RmsGe(f32),
+    BpmGe(f32),
+    OnBar,
     Chance(f32),
 }
 
+impl GraphOp {
+    /// Evaluates a non-random edge condition against live audio features.
+    /// `Chance` edges are resolved by `PresetGraphWalker` from its current roll.
+    pub fn matches(self, audio: &AudioFeatures) -> bool {
+        match self {
+            Self::Always => true,
+            Self::OnBeat => audio.beat,
+            Self::BeatStrengthGe(v) => audio.beat_strength >= v,
+            Self::RmsGe(v) => audio.rms >= v,
+            Self::BpmGe(v) => audio.bpm > 0.0 && audio.bpm >= v,
+            // The first sixteenth of a bar; the walker leaves the node on the first hit.
+            Self::OnBar => audio.bpm > 0.0 && audio.bar_phase < 0.0625,
+            Self::Chance(_) => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CompiledPresetGraph {
     pub nodes: Vec<CompiledNode>,
@@ -56,6 +78,7 @@ pub enum PresetGraphError {
     EmptyGraph,
     DuplicateNodeId(String),
     UnknownNodeRef { edge: usize, node_id: String },
+    UnresolvedPreset { node: String, preset: String },
     CycleDetected { at: String },
 }
 
@@ -69,6 +92,9 @@ impl fmt::Display for PresetGraphError {
             Self::UnknownNodeRef { edge, node_id } => {
                 write!(f, "edge #{edge} references unknown node '{node_id}'")
             }
+            Self::UnresolvedPreset { node, preset } => {
+                write!(f, "node '{node}' references unknown preset '{preset}'")
+            }
             Self::CycleDetected { at } => write!(f, "cycle detected at node '{at}'"),
         }
     }
@@ -99,7 +125,7 @@ impl PresetGraph {
                     if tokens.len() != 3 {
                         return Err(PresetGraphError::Parse {
                             line: line_no,
-                            message: "node expects: node <id> <preset_index>".to_string(),
+                            message: "node expects: node <id> <preset>".to_string(),
                         });
                     }
                     let id = tokens[1].to_string();
@@ -109,9 +135,11 @@ impl PresetGraph {
                             message: format!("invalid node id: {id}"),
                         });
                     }
-                    let preset_index =
-                        parse_usize(tokens[2], line_no, "invalid preset index for node")?;
-                    nodes.push(GraphNode { id, preset_index });
+                    let preset = PresetRef::parse(tokens[2]).ok_or(PresetGraphError::Parse {
+                        line: line_no,
+                        message: "invalid preset id or index for node".to_string(),
+                    })?;
+                    nodes.push(GraphNode { id, preset });
                 }
                 "edge" => {
                     if tokens.len() < 4 {
@@ -143,7 +171,25 @@ impl PresetGraph {
         Self::parse(&text)
     }
 
+    /// Compiles a graph whose nodes use preset positions only; nodes naming a
+    /// preset id fail with `UnresolvedPreset`. See `compile_with`.
     pub fn compile(&self) -> Result<CompiledPresetGraph, PresetGraphError> {
+        self.compile_by(|preset| match preset {
+            PresetRef::Index(idx) => Some(*idx),
+            PresetRef::Id(_) => None,
+        })
+    }
+
+    /// Compiles against the registry's preset ids (in order), so nodes may name
+    /// presets by id or by an in-range position.
+    pub fn compile_with(&self, preset_ids: &[String]) -> Result<CompiledPresetGraph, PresetGraphError> {
+        self.compile_by(|preset| preset.resolve(preset_ids))
+    }
+
+    fn compile_by(
+        &self,
+        resolve: impl Fn(&PresetRef) -> Option<usize>,
+    ) -> Result<CompiledPresetGraph, PresetGraphError> {
         if self.nodes.is_empty() {
             return Err(PresetGraphError::EmptyGraph);
         }
@@ -154,9 +200,13 @@ impl PresetGraph {
             if id_to_idx.insert(node.id.clone(), idx).is_some() {
                 return Err(PresetGraphError::DuplicateNodeId(node.id.clone()));
             }
+            let preset_index = resolve(&node.preset).ok_or_else(|| PresetGraphError::UnresolvedPreset {
+                node: node.id.clone(),
+                preset: node.preset.to_string(),
+            })?;
             compiled_nodes.push(CompiledNode {
                 id: node.id.clone(),
-                preset_index: node.preset_index,
+                preset_index,
             });
         }
 
@@ -194,6 +244,149 @@ impl PresetGraph {
     }
 }
 
+/// Shortest interval between chance re-rolls when no beat is detected, so a
+/// zero dwell does not turn chance edges into per-frame coin flips.
+const MIN_CHANCE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Runtime state machine over a compiled graph.
+///
+/// The walker sits on one node, and once the node has been held for the
+/// caller-provided minimum dwell it takes the first outgoing edge whose
+/// condition is satisfied. Chance edges leaving the same node split a single
+/// roll, so `chance 0.65` / `chance 0.35` behave as branch weights rather than
+/// per-frame coin flips. The roll is redrawn once per evaluation window (each
+/// detected beat, or each dwell period without one), so a node whose chance
+/// weights sum below 1 still leaves eventually. Terminal nodes loop back to the
+/// entry node so an arc keeps playing.
+#[derive(Debug, Clone)]
+pub struct PresetGraphWalker {
+    graph: CompiledPresetGraph,
+    current: usize,
+    entered_at: Option<Instant>,
+    roll: f32,
+    rolled_at: Option<Instant>,
+    /// Forked from the thread's global RNG on creation, so `fastrand::seed`
+    /// (`--seed`) fixes the chance rolls without other draws shifting them.
+    rng: fastrand::Rng,
+}
+
+impl PresetGraphWalker {
+    pub fn new(graph: CompiledPresetGraph) -> Self {
+        let current = graph.entry.min(graph.nodes.len().saturating_sub(1));
+        let mut rng = fastrand::Rng::new();
+        Self {
+            graph,
+            current,
+            entered_at: None,
+            roll: rng.f32(),
+            rolled_at: None,
+            rng,
+        }
+    }
+
+    pub fn graph(&self) -> &CompiledPresetGraph {
+        &self.graph
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    pub fn current(&self) -> &CompiledNode {
+        &self.graph.nodes[self.current]
+    }
+
+    pub fn enter(&mut self, node: usize, now: Instant) {
+        if node >= self.graph.nodes.len() {
+            return;
+        }
+        self.current = node;
+        self.entered_at = Some(now);
+        self.reroll(now);
+    }
+
+    fn reroll(&mut self, now: Instant) {
+        self.roll = self.rng.f32();
+        self.rolled_at = Some(now);
+    }
+
+    /// Re-anchors the walker after the active preset changed outside the graph
+    /// (manual stepping, playlist edits). Returns `false` when no node uses it.
+    pub fn sync_to_preset(&mut self, preset_index: usize, now: Instant) -> bool {
+        if self.current().preset_index == preset_index {
+            return true;
+        }
+        match self
+            .graph
+            .nodes
+            .iter()
+            .position(|n| n.preset_index == preset_index)
+        {
+            Some(node) => {
+                self.enter(node, now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn dwell(&self, now: Instant) -> Duration {
+        self.entered_at
+            .map(|t| now.saturating_duration_since(t))
+            .unwrap_or_default()
+    }
+
+    /// Returns the node the walker would move to right now, ignoring dwell.
+    pub fn select_next(&self, audio: &AudioFeatures) -> Option<usize> {
+        let edges = &self.graph.adjacency[self.current];
+        if edges.is_empty() {
+            return (self.current != self.graph.entry).then_some(self.graph.entry);
+        }
+
+        let mut chance_floor = 0.0f32;
+        for edge in edges {
+            let hit = match edge.op {
+                GraphOp::Chance(p) => {
+                    let lo = chance_floor;
+                    chance_floor += p;
+                    self.roll >= lo && self.roll < chance_floor
+                }
+                op => op.matches(audio),
+            };
+            if hit {
+                return Some(edge.to);
+            }
+        }
+        None
+    }
+
+    /// Advances the walker when the dwell has elapsed and an edge fires.
+    /// Returns the preset index of the newly entered node.
+    pub fn step(&mut self, now: Instant, audio: &AudioFeatures, min_dwell: Duration) -> Option<usize> {
+        if self.entered_at.is_none() {
+            self.entered_at = Some(now);
+            return None;
+        }
+        if self.dwell(now) < min_dwell {
+            return None;
+        }
+        let Some(next) = self.select_next(audio) else {
+            // Nothing fired this window: redraw the chance roll on the next beat,
+            // or after another dwell period when no beat is detected.
+            let window = min_dwell.max(MIN_CHANCE_WINDOW);
+            let window_elapsed = self
+                .rolled_at
+                .is_none_or(|t| now.saturating_duration_since(t) >= window);
+            if audio.beat || window_elapsed {
+                self.reroll(now);
+            }
+            return None;
+        };
+        self.enter(next, now);
+        Some(self.graph.nodes[next].preset_index)
+    }
+}
+
 fn parse_op(tokens: &[&str], line: usize) -> Result<GraphOp, PresetGraphError> {
     let op_name = tokens[0];
     match op_name {
@@ -207,6 +400,11 @@ fn parse_op(tokens: &[&str], line: usize) -> Result<GraphOp, PresetGraphError> {
             let v = parse_unit_interval(tokens, line, "rms_ge expects a value in [0,1]")?;
             Ok(GraphOp::RmsGe(v))
         }
+        "bpm_ge" => {
+            let v = parse_bounded(tokens, line, 0.0, 400.0, "bpm_ge expects a value in [0,400]")?;
+            Ok(GraphOp::BpmGe(v))
+        }
+        "on_bar" => expect_no_extra(tokens, line, GraphOp::OnBar),
         "chance" => {
             let v = parse_unit_interval(tokens, line, "chance expects a value in [0,1]")?;
             Ok(GraphOp::Chance(v))
@@ -229,6 +427,16 @@ fn expect_no_extra(tokens: &[&str], line: usize, op: GraphOp) -> Result<GraphOp,
 }
 
 fn parse_unit_interval(tokens: &[&str], line: usize, msg: &str) -> Result<f32, PresetGraphError> {
+    parse_bounded(tokens, line, 0.0, 1.0, msg)
+}
+
+fn parse_bounded(
+    tokens: &[&str],
+    line: usize,
+    lo: f32,
+    hi: f32,
+    msg: &str,
+) -> Result<f32, PresetGraphError> {
     if tokens.len() != 2 {
         return Err(PresetGraphError::Parse {
             line,
@@ -239,7 +447,7 @@ fn parse_unit_interval(tokens: &[&str], line: usize, msg: &str) -> Result<f32, P
         line,
         message: msg.to_string(),
     })?;
-    if !v.is_finite() || !(0.0..=1.0).contains(&v) {
+    if !v.is_finite() || !(lo..=hi).contains(&v) {
         return Err(PresetGraphError::Parse {
             line,
             message: msg.to_string(),
@@ -248,13 +456,6 @@ fn parse_unit_interval(tokens: &[&str], line: usize, msg: &str) -> Result<f32, P
     Ok(v)
 }
 
-fn parse_usize(s: &str, line: usize, msg: &str) -> Result<usize, PresetGraphError> {
-    s.parse::<usize>().map_err(|_| PresetGraphError::Parse {
-        line,
-        message: msg.to_string(),
-    })
-}
-
 fn is_ident(s: &str) -> bool {
     let mut chars = s.chars();
     let Some(first) = chars.next() else {
@@ -0,0 +1,78 @@
+//! Stable preset references shared by theme packs, preset graphs and playlist
+//! storage. Presets are identified by a slug id (frozen for built-ins, derived
+//! from the name for `.preset` files), so saved files keep pointing at the same
+//! preset when the registry order changes; bare numbers are still read as
+//! positions for files written before ids existed.
+
+use std::fmt;
+
+/// Slug id for a preset name: lowercase ASCII letters and digits, with every
+/// other run of characters collapsed into one `-`
+/// (`"Mandelbrot: Bass Zoom"` -> `"mandelbrot-bass-zoom"`).
+pub fn preset_slug(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() {
+            out.push(ch.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    while out.ends_with('-') {
+        out.pop();
+    }
+    out
+}
+
+/// A preset as written in a config file: its slug id, or a legacy position in
+/// the preset list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PresetRef {
+    Index(usize),
+    Id(String),
+}
+
+impl PresetRef {
+    /// Reads a number as a position and anything else made of letters, digits
+    /// and `-` as an id (case-insensitive). Returns `None` for other tokens.
+    pub fn parse(token: &str) -> Option<Self> {
+        let token = token.trim();
+        if let Ok(idx) = token.parse::<usize>() {
+            return Some(Self::Index(idx));
+        }
+        let valid = !token.is_empty() && token.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-');
+        valid.then(|| Self::Id(token.to_ascii_lowercase()))
+    }
+
+    /// Position of the referenced preset in `ids` (the registry's ids in order).
+    pub fn resolve(&self, ids: &[String]) -> Option<usize> {
+        match self {
+            Self::Index(idx) => (*idx < ids.len()).then_some(*idx),
+            Self::Id(id) => ids.iter().position(|x| x == id),
+        }
+    }
+}
+
+impl fmt::Display for PresetRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(idx) => write!(f, "{idx}"),
+            Self::Id(id) => f.write_str(id),
+        }
+    }
+}
+
+/// Resolves `refs` in order, skipping repeats of an already resolved preset.
+/// Returns the preset positions and the references that matched nothing.
+pub fn resolve_refs(refs: &[PresetRef], ids: &[String]) -> (Vec<usize>, Vec<PresetRef>) {
+    let mut indices = Vec::with_capacity(refs.len());
+    let mut unresolved = Vec::new();
+    for preset in refs {
+        match preset.resolve(ids) {
+            Some(idx) if !indices.contains(&idx) => indices.push(idx),
+            Some(_) => {}
+            None => unresolved.push(preset.clone()),
+        }
+    }
+    (indices, unresolved)
+}
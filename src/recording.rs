@@ -0,0 +1,189 @@
+//! Compact on-disk log of `AudioFeatures` frames, written by `--record-features`
+//! and played back by `--source replay`.
+//!
+//! Layout (little-endian): an 8-byte header (`BVFR`, format version `u16`, and
+//! reserved `u16`), the source sample rate as `u32`, then one record per stored
+//! frame: a `u16` payload length followed by the payload. The payload starts with
+//! the frame time in microseconds (`u64`), a flags byte, the band count and that
+//! many band values, then the scalar features in `SCALARS` order. Fields added
+//! later are appended to the payload, so readers fill anything missing from a
+//! shorter payload with defaults and skip anything past what they know.
+
+use crate::audio::{AudioFeatures, MAX_BANDS};
+use anyhow::{bail, Context};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+const MAGIC: &[u8; 4] = b"BVFR";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 12;
+
+const FLAG_BEAT: u8 = 1;
+const FLAG_SILENT: u8 = 2;
+
+type Scalar = (fn(&AudioFeatures) -> f32, fn(&mut AudioFeatures, f32));
+
+/// Scalar features in payload order. Only ever append to this list.
+const SCALARS: &[Scalar] = &[
+    (|f| f.rms, |f, v| f.rms = v),
+    (|f| f.onset, |f, v| f.onset = v),
+    (|f| f.beat_strength, |f, v| f.beat_strength = v),
+    (|f| f.centroid, |f, v| f.centroid = v),
+    (|f| f.flatness, |f, v| f.flatness = v),
+    (|f| f.bpm, |f, v| f.bpm = v),
+    (|f| f.beat_phase, |f, v| f.beat_phase = v),
+    (|f| f.bar_phase, |f, v| f.bar_phase = v),
+    (|f| f.kick, |f, v| f.kick = v),
+    (|f| f.snare, |f, v| f.snare = v),
+    (|f| f.hat, |f, v| f.hat = v),
+    (|f| f.rms_left, |f, v| f.rms_left = v),
+    (|f| f.rms_right, |f, v| f.rms_right = v),
+    (|f| f.balance, |f, v| f.balance = v),
+    (|f| f.stereo_width, |f, v| f.stereo_width = v),
+    (|f| f.gain, |f, v| f.gain = v),
+    (|f| f.pitch_hz, |f, v| f.pitch_hz = v),
+    (|f| f.key as f32, |f, v| f.key = (v as u8) % 12),
+    (|f| f.key_minor as u8 as f32, |f, v| f.key_minor = v >= 0.5),
+    (|f| f.key_confidence, |f, v| f.key_confidence = v),
+    (|f| f.key_change, |f, v| f.key_change = v),
+    (|f| f.chroma[0], |f, v| f.chroma[0] = v),
+    (|f| f.chroma[1], |f, v| f.chroma[1] = v),
+    (|f| f.chroma[2], |f, v| f.chroma[2] = v),
+    (|f| f.chroma[3], |f, v| f.chroma[3] = v),
+    (|f| f.chroma[4], |f, v| f.chroma[4] = v),
+    (|f| f.chroma[5], |f, v| f.chroma[5] = v),
+    (|f| f.chroma[6], |f, v| f.chroma[6] = v),
+    (|f| f.chroma[7], |f, v| f.chroma[7] = v),
+    (|f| f.chroma[8], |f, v| f.chroma[8] = v),
+    (|f| f.chroma[9], |f, v| f.chroma[9] = v),
+    (|f| f.chroma[10], |f, v| f.chroma[10] = v),
+    (|f| f.chroma[11], |f, v| f.chroma[11] = v),
+    (|f| f.lufs_momentary, |f, v| f.lufs_momentary = v),
+    (|f| f.lufs_short_term, |f, v| f.lufs_short_term = v),
+    (|f| f.crest_db, |f, v| f.crest_db = v),
+];
+
+/// Appends every frame it is given to a feature log, stamped with the time
+/// since the recorder was created.
+pub struct FeatureRecorder {
+    out: BufWriter<File>,
+    start: Instant,
+    payload: Vec<u8>,
+}
+
+impl FeatureRecorder {
+    pub fn create(path: &Path, sample_rate_hz: u32) -> anyhow::Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("create feature recording {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        out.write_all(&sample_rate_hz.to_le_bytes())?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+            payload: Vec::with_capacity(256),
+        })
+    }
+
+    pub fn record(&mut self, f: &AudioFeatures) -> std::io::Result<()> {
+        let t_us = self.start.elapsed().as_micros() as u64;
+        encode_frame(t_us, f, &mut self.payload);
+        self.out.write_all(&(self.payload.len() as u16).to_le_bytes())?;
+        self.out.write_all(&self.payload)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// A decoded feature log: frames in recording order with their offsets in
+/// microseconds from the start of the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureRecording {
+    pub sample_rate_hz: u32,
+    pub frames: Vec<(u64, AudioFeatures)>,
+}
+
+impl FeatureRecording {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("read feature recording {}", path.display()))?;
+        Self::parse(&bytes).with_context(|| format!("parse feature recording {}", path.display()))
+    }
+
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+            bail!("not a feature recording (missing BVFR header)");
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != VERSION {
+            bail!("unsupported feature recording version {version}");
+        }
+        let sample_rate_hz = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+
+        let mut frames = Vec::new();
+        let mut pos = HEADER_LEN;
+        while pos < bytes.len() {
+            if pos + 2 > bytes.len() {
+                bail!("truncated record length at byte {pos}");
+            }
+            let len = u16::from_le_bytes([bytes[pos], bytes[pos + 1]]) as usize;
+            let start = pos + 2;
+            let Some(payload) = bytes.get(start..start + len) else {
+                bail!("truncated record at byte {pos}");
+            };
+            frames.push(decode_frame(payload).with_context(|| format!("record at byte {pos}"))?);
+            pos = start + len;
+        }
+        Ok(Self {
+            sample_rate_hz,
+            frames,
+        })
+    }
+}
+
+fn encode_frame(t_us: u64, f: &AudioFeatures, out: &mut Vec<u8>) {
+    out.clear();
+    out.extend_from_slice(&t_us.to_le_bytes());
+    out.push(if f.beat { FLAG_BEAT } else { 0 } | if f.silent { FLAG_SILENT } else { 0 });
+    let bands = f.band_count.min(MAX_BANDS);
+    out.push(bands as u8);
+    for b in &f.bands[..bands] {
+        out.extend_from_slice(&b.to_le_bytes());
+    }
+    for (get, _) in SCALARS {
+        out.extend_from_slice(&get(f).to_le_bytes());
+    }
+}
+
+fn decode_frame(payload: &[u8]) -> anyhow::Result<(u64, AudioFeatures)> {
+    if payload.len() < 10 {
+        bail!("record shorter than its fixed header");
+    }
+    let t_us = u64::from_le_bytes(payload[0..8].try_into().expect("8 bytes"));
+    let mut f = AudioFeatures {
+        beat: payload[8] & FLAG_BEAT != 0,
+        silent: payload[8] & FLAG_SILENT != 0,
+        band_count: payload[9] as usize,
+        ..AudioFeatures::default()
+    };
+    if f.band_count > MAX_BANDS {
+        bail!("band count {} exceeds {MAX_BANDS}", f.band_count);
+    }
+    let mut values = payload[10..]
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]));
+    for b in &mut f.bands[..f.band_count] {
+        *b = values.next().context("record ends inside the band values")?;
+    }
+    for ((_, set), v) in SCALARS.iter().zip(values) {
+        set(&mut f, v);
+    }
+    Ok((t_us, f))
+}
+
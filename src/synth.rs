@@ -0,0 +1,289 @@
+//! Synthetic audio features: the generators the benchmark renders against, and
+//! the tempo-synced drum patterns behind `--source synth`.
+
+use crate::audio::{AudioFeatures, AudioSnapshot, ANALYZER_WINDOW};
+use crate::config::SynthPattern;
+
+/// Free-running synthetic features: sine-driven bands with a hard hit every 24
+/// steps and a soft one every 12. Used by the benchmark's render loops.
+pub fn synth_audio(t: f32, step: usize) -> AudioFeatures {
+    let bass = ((t * 1.9).sin() * 0.5 + 0.5).powf(1.15);
+    let mid = ((t * 2.8 + 0.7).sin() * 0.5 + 0.5).powf(1.08);
+    let treb = ((t * 5.2 + 1.3).sin() * 0.5 + 0.5).powf(1.02);
+
+    let hard_hit = step.is_multiple_of(24);
+    let soft_hit = step.is_multiple_of(12);
+
+    let onset = if hard_hit {
+        0.92
+    } else if soft_hit {
+        0.58
+    } else {
+        (treb * 0.35 + mid * 0.25).min(0.5)
+    };
+
+    let beat_strength = if hard_hit {
+        0.95
+    } else if soft_hit {
+        0.55
+    } else {
+        0.0
+    };
+
+    AudioFeatures {
+        rms: (0.12 + bass * 0.42 + mid * 0.30 + treb * 0.20).clamp(0.0, 1.0),
+        bands: AudioFeatures::pad_bands(&[
+            (bass * 0.95).clamp(0.0, 1.0),
+            bass.clamp(0.0, 1.0),
+            (bass * 0.6 + mid * 0.35).clamp(0.0, 1.0),
+            mid.clamp(0.0, 1.0),
+            (mid * 0.5 + treb * 0.45).clamp(0.0, 1.0),
+            treb.clamp(0.0, 1.0),
+            (treb * 0.9).clamp(0.0, 1.0),
+            (treb * 0.75 + mid * 0.2).clamp(0.0, 1.0),
+        ]),
+        onset,
+        beat: hard_hit || soft_hit,
+        beat_strength,
+        centroid: (0.2 + treb * 0.6 + mid * 0.15).clamp(0.0, 1.0),
+        flatness: (0.15 + treb * 0.55).clamp(0.0, 1.0),
+        kick: if hard_hit { 0.95 } else { 0.0 },
+        hat: if soft_hit && !hard_hit { 0.7 } else { (treb * 0.3).min(0.4) },
+        ..AudioFeatures::default()
+    }
+}
+
+/// Spectrum shaped from the synthetic bands plus a matching bass/mid/treble
+/// waveform. Fills `out` at its current sizes; empty buffers get 64 bins and an
+/// `ANALYZER_WINDOW`-sample waveform.
+pub fn synth_snapshot(audio: &AudioFeatures, out: &mut AudioSnapshot) {
+    if out.spectrum.is_empty() {
+        out.spectrum.resize(64, 0.0);
+    }
+    let bins = out.spectrum.len().max(2);
+    for (i, v) in out.spectrum.iter_mut().enumerate() {
+        let pos = i as f32 / (bins - 1) as f32 * 7.0;
+        let lo = pos.floor() as usize;
+        let hi = (lo + 1).min(7);
+        let frac = pos - lo as f32;
+        let band = audio.band8(lo) * (1.0 - frac) + audio.band8(hi) * frac;
+        let ripple = 0.85 + 0.15 * (i as f32 * 1.7 + audio.onset * 6.0).sin();
+        *v = (band * ripple).clamp(0.0, 1.0);
+    }
+
+    if out.waveform.is_empty() {
+        out.waveform.resize(ANALYZER_WINDOW, 0.0);
+    }
+    let n = out.waveform.len() as f32;
+    for (i, v) in out.waveform.iter_mut().enumerate() {
+        let x = i as f32 / n * std::f32::consts::TAU;
+        *v = (audio.band8(1) * (x * 2.0).sin()
+            + audio.band8(3) * 0.5 * (x * 11.0).sin()
+            + audio.band8(6) * 0.25 * (x * 53.0).sin())
+        .clamp(-1.0, 1.0);
+    }
+}
+
+/// `synth_audio` shaped into four rising sections (calm, groove, drive, impact)
+/// spread evenly over `total_steps`.
+pub fn section_audio(step: usize, total_steps: usize) -> AudioFeatures {
+    let t = step as f32 / 60.0;
+    let mut audio = synth_audio(t, step);
+    let section = (step.saturating_mul(4)) / total_steps.max(1);
+    match section {
+        0 => {
+            audio.rms = (0.08 + audio.rms * 0.22).clamp(0.0, 1.0);
+            audio.onset = (audio.onset * 0.25).clamp(0.0, 1.0);
+            audio.beat = step.is_multiple_of(48);
+            audio.beat_strength = if audio.beat { 0.35 } else { 0.0 };
+        }
+        1 => {
+            audio.rms = (0.18 + audio.rms * 0.40).clamp(0.0, 1.0);
+            audio.onset = (audio.onset * 0.55 + 0.1).clamp(0.0, 1.0);
+            audio.beat = step.is_multiple_of(24);
+            if audio.beat {
+                audio.beat_strength = 0.58;
+            }
+        }
+        2 => {
+            audio.rms = (0.28 + audio.rms * 0.55).clamp(0.0, 1.0);
+            audio.onset = (audio.onset * 0.7 + 0.16).clamp(0.0, 1.0);
+            audio.beat = step.is_multiple_of(16) || audio.beat;
+            if audio.beat {
+                audio.beat_strength = audio.beat_strength.max(0.72);
+            }
+        }
+        _ => {
+            audio.rms = (0.38 + audio.rms * 0.60).clamp(0.0, 1.0);
+            audio.onset = (audio.onset * 0.85 + 0.24).clamp(0.0, 1.0);
+            audio.beat = step.is_multiple_of(12) || audio.beat;
+            if audio.beat {
+                audio.beat_strength = audio.beat_strength.max(0.88);
+            }
+        }
+    }
+    audio
+}
+
+/// Sixteenth-note steps per bar.
+const STEPS: usize = 16;
+
+/// Which steps of a bar carry a hit, as a 16-bit mask (bit 0 = downbeat).
+#[derive(Clone, Copy)]
+struct Groove {
+    kick: u16,
+    snare: u16,
+    hat: u16,
+}
+
+const fn steps(list: &[usize]) -> u16 {
+    let mut mask = 0u16;
+    let mut i = 0;
+    while i < list.len() {
+        mask |= 1 << list[i];
+        i += 1;
+    }
+    mask
+}
+
+const FOUR_ON_THE_FLOOR: Groove = Groove {
+    kick: steps(&[0, 4, 8, 12]),
+    snare: steps(&[4, 12]),
+    hat: steps(&[2, 6, 10, 14]),
+};
+const BREAKBEAT: Groove = Groove {
+    kick: steps(&[0, 2, 10]),
+    snare: steps(&[4, 7, 9, 12, 15]),
+    hat: steps(&[0, 2, 4, 6, 8, 10, 12, 14]),
+};
+const SILENT: Groove = Groove {
+    kick: 0,
+    snare: 0,
+    hat: 0,
+};
+
+/// Bars per section when cycling through `SynthPattern::Sections`.
+const SECTION_BARS: f32 = 8.0;
+
+/// Tempo-synced synthetic features for `--source synth`. Output depends only on
+/// the time passed to `step`, so a given pattern and BPM always look the same.
+pub struct PatternSynth {
+    pattern: SynthPattern,
+    bpm: f32,
+    last_t: Option<f32>,
+}
+
+impl PatternSynth {
+    pub fn new(pattern: SynthPattern, bpm: f32) -> Self {
+        Self {
+            pattern,
+            bpm: if bpm.is_finite() { bpm.clamp(40.0, 240.0) } else { 120.0 },
+            last_t: None,
+        }
+    }
+
+    pub fn bpm(&self) -> f32 {
+        self.bpm
+    }
+
+    /// Features at `t` seconds; `beat` is set when a kick or snare landed since
+    /// the previous call.
+    pub fn step(&mut self, t: f32) -> AudioFeatures {
+        let prev = self.last_t.replace(t);
+        let step_s = 60.0 / self.bpm / 4.0;
+        let bar_s = step_s * STEPS as f32;
+        let bar = t / bar_s;
+
+        // Sections: calm, groove, a build-up whose low end falls away, then the drop.
+        let (groove, energy, build) = match self.pattern {
+            SynthPattern::FourOnTheFloor => (FOUR_ON_THE_FLOOR, 0.8, 0.0),
+            SynthPattern::Breakbeat => (BREAKBEAT, 0.75, 0.0),
+            SynthPattern::Ambient => (SILENT, 0.45, 0.0),
+            SynthPattern::Sections => {
+                let pos = (bar / SECTION_BARS) % 4.0;
+                match pos as usize {
+                    0 => (SILENT, 0.4, 0.0),
+                    1 => (BREAKBEAT, 0.6, 0.0),
+                    2 => (
+                        Groove {
+                            kick: 0,
+                            ..FOUR_ON_THE_FLOOR
+                        },
+                        0.55,
+                        pos.fract(),
+                    ),
+                    _ => (FOUR_ON_THE_FLOOR, 1.0, 0.0),
+                }
+            }
+        };
+
+        let since = |mask: u16| since_hit(mask, t, step_s);
+        let hit_since_prev =
+            |mask: u16| prev.is_some_and(|p| since(mask).is_some_and(|s| s < t - p));
+        let env = |mask: u16, decay: f32| since(mask).map_or(0.0, |s| (-s / decay).exp());
+        let kick = env(groove.kick, 0.12);
+        let snare = env(groove.snare, 0.15);
+        // A build-up fills in the hats until every sixteenth plays.
+        let hat_mask = if build > 0.5 { u16::MAX } else { groove.hat };
+        let hat = env(hat_mask, 0.05) * (1.0 + build);
+
+        let swell = 0.5 + 0.5 * (t * std::f32::consts::TAU / (bar_s * 4.0)).sin();
+        let pad = if groove.kick == 0 && groove.snare == 0 { 0.25 + 0.35 * swell } else { 0.15 + 0.1 * swell };
+        let low_cut = 1.0 - build;
+        let bands = [
+            (pad * 0.6 + 0.85 * kick) * low_cut,
+            (pad * 0.8 + 0.7 * kick) * low_cut,
+            pad + 0.3 * kick + 0.3 * snare,
+            pad + 0.5 * snare,
+            pad * 0.8 + 0.4 * snare + 0.2 * hat,
+            pad * 0.6 + 0.6 * hat + 0.3 * build,
+            pad * 0.5 + 0.7 * hat + 0.4 * build,
+            pad * 0.5 + 0.3 * snare + 0.4 * hat,
+        ]
+        .map(|b| (b * energy).clamp(0.0, 1.0));
+        let rms = ((0.25 + 0.35 * kick * low_cut + 0.2 * snare + 0.1 * hat + 0.2 * pad) * energy).clamp(0.0, 1.0);
+
+        let kick_hit = hit_since_prev(groove.kick);
+        let snare_hit = hit_since_prev(groove.snare);
+        let beating = groove.kick != 0 || groove.snare != 0;
+        AudioFeatures {
+            rms,
+            bands: AudioFeatures::pad_bands(&bands),
+            band_count: bands.len(),
+            onset: (kick.max(snare * 0.8).max(hat * 0.5) * energy).clamp(0.0, 1.0),
+            beat: kick_hit || snare_hit,
+            beat_strength: if kick_hit { 0.9 * energy } else if snare_hit { 0.6 * energy } else { 0.0 },
+            centroid: (0.25 + 0.35 * hat + 0.15 * snare + 0.2 * build).clamp(0.0, 1.0),
+            flatness: (0.15 + 0.4 * hat + 0.2 * snare).clamp(0.0, 1.0),
+            bpm: if beating { self.bpm } else { 0.0 },
+            beat_phase: if beating { (t / (step_s * 4.0)).fract() } else { 0.0 },
+            bar_phase: if beating { bar.fract() } else { 0.0 },
+            kick: kick * energy,
+            snare: snare * energy,
+            hat: hat.min(1.0) * energy,
+            rms_left: rms,
+            rms_right: rms,
+            balance: 0.2 * (t * 0.13).sin(),
+            stereo_width: 0.25 + 0.2 * swell,
+            // Loudness on the same -40..-8 LUFS span `AudioFeatures::loudness` reads;
+            // drums are punchy, pads and build-ups flat.
+            lufs_momentary: -40.0 + 32.0 * rms,
+            lufs_short_term: -40.0 + 32.0 * (0.5 * energy).min(1.0),
+            crest_db: if beating { 12.0 - 6.0 * build } else { 5.0 },
+            ..AudioFeatures::default()
+        }
+    }
+}
+
+/// Seconds since the most recent step in `mask` at or before `t`; `None` for an
+/// empty mask.
+fn since_hit(mask: u16, t: f32, step_s: f32) -> Option<f32> {
+    if mask == 0 || t < 0.0 {
+        return None;
+    }
+    let step = (t / step_s).floor() as i64;
+    (0..STEPS as i64).find_map(|back| {
+        let s = step - back;
+        (mask & (1 << s.rem_euclid(STEPS as i64)) != 0).then_some(t - s as f32 * step_s)
+    })
+}
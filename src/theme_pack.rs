@@ -1,3 +1,4 @@
+use crate::preset_id::{resolve_refs, PresetRef};
 use std::collections::HashSet;
 use std::fmt;
 use std::path::Path;
@@ -6,7 +7,8 @@ use std::path::Path;
 pub struct ThemePackManifest {
     pub name: String,
     pub tags: Vec<String>,
-    pub preset_indices: Vec<usize>,
+    /// Preset ids, or legacy positions, in the order the pack lists them.
+    pub presets: Vec<PresetRef>,
     pub transition: TransitionPrefs,
     pub intensity_default: f32,
     pub zoom_default: f32,
@@ -25,6 +27,7 @@ pub enum ThemePackError {
     Parse { line: usize, message: String },
     MissingField(&'static str),
     DuplicatePresetIndex(usize),
+    DuplicatePresetId(String),
     InvalidValue { field: &'static str, message: String },
 }
 
@@ -35,6 +38,7 @@ impl fmt::Display for ThemePackError {
             Self::Parse { line, message } => write!(f, "parse error at line {line}: {message}"),
             Self::MissingField(field) => write!(f, "missing required field: {field}"),
             Self::DuplicatePresetIndex(idx) => write!(f, "duplicate preset index: {idx}"),
+            Self::DuplicatePresetId(id) => write!(f, "duplicate preset id: {id}"),
             Self::InvalidValue { field, message } => {
                 write!(f, "invalid value for {field}: {message}")
             }
@@ -48,7 +52,7 @@ impl ThemePackManifest {
     pub fn parse(text: &str) -> Result<Self, ThemePackError> {
         let mut name: Option<String> = None;
         let mut tags: Option<Vec<String>> = None;
-        let mut preset_indices: Option<Vec<usize>> = None;
+        let mut presets: Option<Vec<PresetRef>> = None;
         let mut min_beats: Option<u32> = None;
         let mut max_beats: Option<u32> = None;
         let mut crossfade_ms: Option<u32> = None;
@@ -83,9 +87,9 @@ impl ThemePackManifest {
                     assign_once(&mut tags, parsed, line_no, "duplicate 'tags' field")?;
                 }
                 "presets" => {
-                    let parsed = parse_csv_usize(value, line_no, "presets")?;
+                    let parsed = parse_csv_refs(value, line_no, "presets")?;
                     assign_once(
-                        &mut preset_indices,
+                        &mut presets,
                         parsed,
                         line_no,
                         "duplicate 'presets' field",
@@ -148,7 +152,7 @@ impl ThemePackManifest {
         let manifest = Self {
             name: name.ok_or(ThemePackError::MissingField("name"))?,
             tags: tags.unwrap_or_default(),
-            preset_indices: preset_indices.ok_or(ThemePackError::MissingField("presets"))?,
+            presets: presets.ok_or(ThemePackError::MissingField("presets"))?,
             transition: TransitionPrefs {
                 min_beats: min_beats.ok_or(ThemePackError::MissingField("transition.min_beats"))?,
                 max_beats: max_beats.ok_or(ThemePackError::MissingField("transition.max_beats"))?,
@@ -173,7 +177,7 @@ impl ThemePackManifest {
     pub fn to_text(&self) -> String {
         let tags = self.tags.join(",");
         let presets = self
-            .preset_indices
+            .presets
             .iter()
             .map(|p| p.to_string())
             .collect::<Vec<_>>()
@@ -191,6 +195,12 @@ impl ThemePackManifest {
         .join("\n")
     }
 
+    /// Positions of the pack's presets in a registry with the given ids, plus
+    /// the references that no longer match any preset.
+    pub fn resolve_presets(&self, preset_ids: &[String]) -> (Vec<usize>, Vec<PresetRef>) {
+        resolve_refs(&self.presets, preset_ids)
+    }
+
     pub fn validate(&self) -> Result<(), ThemePackError> {
         if self.name.trim().is_empty() {
             return Err(ThemePackError::InvalidValue {
@@ -198,17 +208,20 @@ impl ThemePackManifest {
                 message: "name must not be empty".to_string(),
             });
         }
-        if self.preset_indices.is_empty() {
+        if self.presets.is_empty() {
             return Err(ThemePackError::InvalidValue {
                 field: "presets",
-                message: "at least one preset index is required".to_string(),
+                message: "at least one preset is required".to_string(),
             });
         }
 
         let mut seen = HashSet::new();
-        for preset in &self.preset_indices {
-            if !seen.insert(*preset) {
-                return Err(ThemePackError::DuplicatePresetIndex(*preset));
+        for preset in &self.presets {
+            if !seen.insert(preset) {
+                return Err(match preset {
+                    PresetRef::Index(idx) => ThemePackError::DuplicatePresetIndex(*idx),
+                    PresetRef::Id(id) => ThemePackError::DuplicatePresetId(id.clone()),
+                });
             }
         }
 
@@ -291,14 +304,14 @@ fn parse_csv_strings(s: &str) -> Vec<String> {
         .collect()
 }
 
-fn parse_csv_usize(s: &str, line: usize, field: &'static str) -> Result<Vec<usize>, ThemePackError> {
+fn parse_csv_refs(s: &str, line: usize, field: &'static str) -> Result<Vec<PresetRef>, ThemePackError> {
     let mut out = Vec::new();
     for part in s.split(',') {
         let token = part.trim();
         if token.is_empty() {
             continue;
         }
-        let value = token.parse::<usize>().map_err(|_| ThemePackError::Parse {
+        let value = PresetRef::parse(token).ok_or_else(|| ThemePackError::Parse {
             line,
             message: format!("invalid list entry for {field}"),
         })?;
@@ -176,7 +176,8 @@ pub fn typography_reactive_audio(
             audio.onset = (audio.onset * (1.0 + 0.32 * stripe) + 0.05 * pulse).clamp(0.0, 1.0);
             audio.beat_strength =
                 (audio.beat_strength * (1.0 + 0.24 * stripe) + 0.06 * pulse).clamp(0.0, 1.0);
-            for b in &mut audio.bands[2..6] {
+            let range = audio.band8_range(2..6);
+            for b in &mut audio.bands[range] {
                 *b = (*b * (1.0 + 0.20 * stripe)).clamp(0.0, 1.0);
             }
             (audio, (1.0 + 0.08 * stripe + 0.05 * pulse).clamp(0.90, 1.35))
@@ -187,7 +188,8 @@ pub fn typography_reactive_audio(
             audio.onset = (audio.onset * (1.0 + 0.28 * cadence) + 0.08 * accent).clamp(0.0, 1.0);
             audio.beat_strength =
                 (audio.beat_strength * (1.0 + 0.22 * cadence) + 0.05 * pulse).clamp(0.0, 1.0);
-            for b in &mut audio.bands[1..5] {
+            let range = audio.band8_range(1..5);
+            for b in &mut audio.bands[range] {
                 *b = (*b * (1.0 + 0.18 * cadence + 0.10 * accent)).clamp(0.0, 1.0);
             }
             (audio, (1.0 + 0.10 * cadence + 0.06 * accent).clamp(0.90, 1.45))
@@ -199,7 +201,8 @@ pub fn typography_reactive_audio(
             audio.onset = (audio.onset * (1.0 + 0.20 * edge) + 0.05 * glide).clamp(0.0, 1.0);
             audio.beat_strength =
                 (audio.beat_strength * (1.0 + 0.16 * edge) + 0.04 * pulse).clamp(0.0, 1.0);
-            for b in &mut audio.bands[3..8] {
+            let range = audio.band8_range(3..8);
+            for b in &mut audio.bands[range] {
                 *b = (*b * (1.0 + 0.24 * glide + 0.18 * edge)).clamp(0.0, 1.0);
             }
             (audio, (1.0 + 0.14 * edge + 0.08 * glide).clamp(0.90, 1.55))
@@ -211,10 +214,12 @@ pub fn typography_reactive_audio(
             audio.onset = (audio.onset * (1.0 + 0.42 * glitch) + 0.14 * gate).clamp(0.0, 1.0);
             audio.beat_strength =
                 (audio.beat_strength * (1.0 + 0.28 * glitch) + 0.10 * gate).clamp(0.0, 1.0);
-            for b in &mut audio.bands[5..8] {
+            let range = audio.band8_range(5..8);
+            for b in &mut audio.bands[range] {
                 *b = (*b * (1.0 + 0.45 * glitch + 0.20 * gate)).clamp(0.0, 1.0);
             }
-            for b in &mut audio.bands[0..2] {
+            let range = audio.band8_range(0..2);
+            for b in &mut audio.bands[range] {
                 *b = (*b * (1.0 - 0.12 * gate)).clamp(0.0, 1.0);
             }
             (audio, (1.0 + 0.18 * glitch + 0.10 * gate).clamp(0.90, 1.65))
@@ -1,11 +1,14 @@
 use crate::audio::AudioFeatures;
 use crate::config::{Quality, SwitchMode};
+use crate::preset_graph::CompiledPresetGraph;
 use crate::visual::{
-    CameraPathMode, FractalZoomMode, PlaybackContext, RenderCtx, TransitionMode, VisualEngine,
+    CameraPathMode, FractalZoomMode, PlaybackContext, Preset, PresetMeta, PresetParam, RenderCtx,
+    TransitionMode, VisualEngine,
 };
 use anyhow::{anyhow, Context};
 use metal::*;
 use objc::rc::autoreleasepool;
+use std::collections::HashSet;
 use std::time::Instant;
 
 const METAL_PRESET_COUNT: usize = 56;
@@ -71,6 +74,10 @@ struct Uniforms {
 
 pub struct MetalEngine {
     preset_names: Vec<&'static str>,
+    preset_metas: Vec<PresetMeta>,
+    /// Presets without a shader (see `Preset::has_metal_shader`), indexed like
+    /// `preset_names`; these render on the CPU into `cpu_front`/`cpu_back`.
+    cpu_presets: Vec<Option<Box<dyn Preset>>>,
     ctx: PlaybackContext,
 
     device: Device,
@@ -98,11 +105,13 @@ pub struct MetalEngine {
     cpu_pixels: Vec<u8>,
     out_pixels: Vec<u8>,
     mandel_orbit_cpu: Vec<[f32; 2]>,
+    cpu_front: Vec<u8>,
+    cpu_back: Vec<u8>,
 }
 
 impl MetalEngine {
     pub fn new(
-        preset_names: Vec<&'static str>,
+        presets: Vec<Box<dyn Preset>>,
         active: usize,
         shuffle: bool,
         switch_mode: SwitchMode,
@@ -145,6 +154,12 @@ impl MetalEngine {
 
         let (tex_a, tex_b, readback_a, readback_b, readback_bpr, cpu_pixels) = make_resources(&device, 1, 1)?;
 
+        let preset_names = presets.iter().map(|p| p.name()).collect::<Vec<_>>();
+        let preset_metas = presets.iter().map(|p| p.meta()).collect::<Vec<_>>();
+        let cpu_presets = presets
+            .into_iter()
+            .map(|p| (!p.has_metal_shader()).then_some(p))
+            .collect();
         let preset_count = preset_names.len();
         Ok(Self {
             ctx: PlaybackContext::new(
@@ -156,6 +171,8 @@ impl MetalEngine {
                 seconds_per_switch,
             ),
             preset_names,
+            preset_metas,
+            cpu_presets,
             device,
             queue,
             pipeline,
@@ -178,9 +195,40 @@ impl MetalEngine {
             cpu_pixels,
             out_pixels: Vec::new(),
             mandel_orbit_cpu: vec![[0.0; 2]; MANDEL_REF_SLOTS],
+            cpu_front: Vec::new(),
+            cpu_back: Vec::new(),
         })
     }
 
+    fn renders_on_cpu(&self, idx: usize) -> bool {
+        matches!(self.cpu_presets.get(idx), Some(Some(_)))
+    }
+
+    /// Renders a shaderless preset at full output size, like `PresetEngine`.
+    fn render_cpu(&mut self, idx: usize, mut ctx: RenderCtx, quality: Quality, scale: usize) -> &[u8] {
+        // Drain in-flight GPU work so switching back starts from a fresh readback.
+        if let Some(cmd) = self.prev_cmd.take() {
+            cmd.wait_until_completed();
+        }
+        self.has_prev = false;
+
+        let n = ctx.w.saturating_mul(ctx.h).saturating_mul(4);
+        if self.cpu_front.len() != n {
+            self.cpu_front.clear();
+            self.cpu_front.resize(n, 0);
+            self.cpu_back.clear();
+            self.cpu_back.resize(n, 0);
+        }
+        ctx.quality = quality;
+        ctx.scale = scale.max(1);
+        ctx.fractal_zoom_mul = self.ctx.fractal_zoom_mul();
+        if let Some(Some(preset)) = self.cpu_presets.get_mut(idx) {
+            preset.render(&ctx, &self.cpu_front, &mut self.cpu_back);
+            std::mem::swap(&mut self.cpu_front, &mut self.cpu_back);
+        }
+        &self.cpu_front
+    }
+
     fn map_name_to_shader_preset(name: &str, fallback: usize) -> u32 {
         let n = name.to_ascii_lowercase();
         if n.contains("mandelbrot: bass zoom") {
@@ -387,6 +435,11 @@ impl MetalEngine {
 impl VisualEngine for MetalEngine {
     fn resize(&mut self, w: usize, h: usize) {
         let _ = self.ensure_size(w, h);
+        self.cpu_front.fill(0);
+        self.cpu_back.fill(0);
+        for preset in self.cpu_presets.iter_mut().flatten() {
+            preset.on_resize(w, h);
+        }
     }
 
     fn preset_name(&self) -> &'static str {
@@ -410,6 +463,7 @@ impl VisualEngine for MetalEngine {
     fn next_transition_kind(&mut self) { self.ctx.next_transition_kind() }
     fn prev_transition_kind(&mut self) { self.ctx.prev_transition_kind() }
     fn scene_section_name(&self) -> &'static str { self.ctx.scene_section_name() }
+    fn structure_phase_name(&self) -> Option<&'static str> { self.ctx.structure_phase_name() }
     fn cycle_camera_path_mode(&mut self) { self.ctx.cycle_camera_path_mode() }
     fn step_camera_path_mode(&mut self, forward: bool) { self.ctx.step_camera_path_mode(forward) }
     fn camera_path_mode(&self) -> CameraPathMode { self.ctx.camera_path_mode() }
@@ -431,9 +485,45 @@ impl VisualEngine for MetalEngine {
     fn prev_preset(&mut self) { self.ctx.prev_preset() }
     fn next_preset(&mut self) { self.ctx.next_preset() }
 
+    fn set_preset_graph(&mut self, graph: Option<CompiledPresetGraph>) {
+        self.ctx.set_preset_graph(graph)
+    }
+
+    fn graph_node_name(&self) -> Option<&str> {
+        self.ctx.graph_node_name()
+    }
+
+    /// Only presets rendered on the CPU have live parameters here; the shaders
+    /// take theirs from the uniforms.
+    fn preset_params(&self) -> Vec<PresetParam> {
+        match self.cpu_presets.get(self.ctx.active) {
+            Some(Some(preset)) => preset.params(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn set_preset_param(&mut self, name: &str, value: f32) -> bool {
+        let mut applied = false;
+        for idx in [Some(self.ctx.active), self.ctx.next].into_iter().flatten() {
+            if let Some(Some(preset)) = self.cpu_presets.get_mut(idx) {
+                applied |= preset.set_param(name, value);
+            }
+        }
+        applied
+    }
+
+    fn tunable_preset_params(&self) -> HashSet<&'static str> {
+        self.cpu_presets
+            .iter()
+            .flatten()
+            .flat_map(|p| p.params())
+            .map(|p| p.name)
+            .collect()
+    }
+
     fn update_auto_switch(&mut self, now: Instant, audio: &AudioFeatures) {
-        let names = &self.preset_names;
-        self.ctx.update_auto_switch(now, audio, |i| names[i])
+        let metas = &self.preset_metas;
+        self.ctx.update_auto_switch(now, audio, |i| metas[i])
     }
 
     fn render(&mut self, ctx: RenderCtx, quality: Quality, scale: usize) -> &[u8] {
@@ -448,24 +538,36 @@ impl VisualEngine for MetalEngine {
             return &self.cpu_pixels;
         }
 
-        let alpha = self.ctx.step_transition(ctx.now);
+        let mut alpha = self.ctx.step_transition(ctx.now);
+        let mut active_idx = self.ctx.active;
+        let mut next_idx = self.ctx.next.unwrap_or(active_idx);
+        if self.renders_on_cpu(active_idx) || self.renders_on_cpu(next_idx) {
+            // The shader can't blend with a CPU frame, so cut over halfway.
+            let shown = if alpha < 0.5 { active_idx } else { next_idx };
+            if self.renders_on_cpu(shown) {
+                return self.render_cpu(shown, ctx, quality, scale);
+            }
+            alpha = 0.0;
+            active_idx = shown;
+            next_idx = shown;
+        }
 
-        let active = self.shader_preset_index(self.ctx.active);
-        let next = self.shader_preset_index(self.ctx.next.unwrap_or(self.ctx.active));
+        let active = self.shader_preset_index(active_idx);
+        let next = self.shader_preset_index(next_idx);
 
         let seed = if alpha == 0.0 {
-            fastrand::u32(..)
+            self.ctx.rng.u32(..)
         } else {
             self.ctx.transition_seed
         };
         let quality_u32 = Self::quality_u32(quality);
-        let bass = ctx.audio.bands[1].clamp(0.0, 1.0);
-        let lowmid = ctx.audio.bands[2].clamp(0.0, 1.0);
-        let mid = (0.62 * ctx.audio.bands[3]
+        let bass = ctx.audio.band8(1).clamp(0.0, 1.0);
+        let lowmid = ctx.audio.band8(2).clamp(0.0, 1.0);
+        let mid = (0.62 * ctx.audio.band8(3)
             + 0.23 * lowmid
-            + 0.15 * ctx.audio.bands[4].clamp(0.0, 1.0))
+            + 0.15 * ctx.audio.band8(4).clamp(0.0, 1.0))
             .clamp(0.0, 1.0);
-        let treb = ctx.audio.bands[5].clamp(0.0, 1.0);
+        let treb = ctx.audio.band8(5).clamp(0.0, 1.0);
         let beat = ctx.beat_pulse.clamp(0.0, 1.0);
         let zoom_mul = self.ctx.fractal_zoom_mul();
 
@@ -516,7 +618,7 @@ impl VisualEngine for MetalEngine {
             onset: ctx.audio.onset,
             centroid: ctx.audio.centroid,
             flatness: ctx.audio.flatness,
-            bands: ctx.audio.bands,
+            bands: ctx.audio.bands8(),
             seed,
             safe: if ctx.safe { 1 } else { 0 },
             quality: quality_u32,
@@ -1,12 +1,19 @@
 mod presets;
+mod structure;
 #[cfg(target_os = "macos")]
 mod metal;
 
 use crate::audio::AudioFeatures;
 use crate::config::{Quality, SwitchMode};
+use crate::preset_graph::{CompiledPresetGraph, PresetGraphWalker};
+use std::collections::HashSet;
 use std::time::{Duration, Instant};
+use structure::{StructureEvent, StructurePhase, StructureTracker};
 
-pub use presets::{make_presets, Preset, RenderCtx};
+pub use presets::{
+    apply_stereo_split, check_definition, make_presets, make_presets_with, Level, Preset,
+    PresetCategory, PresetMeta, PresetParam, RenderCtx, BUILTIN_PRESET_IDS,
+};
 #[cfg(target_os = "macos")]
 pub use metal::MetalEngine;
 
@@ -431,7 +438,7 @@ pub(crate) fn suggest_transition_for_section(
 
     // Auto policy is section-driven so pacing and transition operators
     // stay aligned with stable scene energy.
-    let treb = (audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0);
+    let treb = (audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0);
     let hit = audio.onset.max(audio.beat_strength).max(treb);
     let transient = audio.onset.max(audio.beat_strength);
     let strong_transient =
@@ -472,7 +479,7 @@ pub(crate) fn suggest_transition_for_section(
                 return (dur, kind);
             }
 
-            let bass = audio.bands[1];
+            let bass = audio.band8(1);
             let kind = if treb > 0.58 && hit > 0.35 {
                 pick_kind(
                     seed ^ 0x73A4_4C9B,
@@ -660,17 +667,15 @@ pub(crate) fn suggest_manual_transition(
     pick_kind(seed ^ 0x7CA4_719D, pool, last)
 }
 
-pub(crate) fn is_fractal_preset_name(name: &str) -> bool {
-    let n = name.to_ascii_lowercase();
-    n.contains("mandelbrot")
-        || n.contains("julia")
-        || n.contains("burning ship")
-        || n.contains("orbit trap")
-        || n.contains("fractal")
-        || n.contains("flame")
-        || n.contains("mandelbulb")
-        || n.contains("sphere")
-        || n.contains("sdf")
+/// Whether a preset fits the detected section when the adaptive policy picks
+/// the next scene: calm passages avoid high-energy and flashy presets, driving
+/// and impact sections avoid low-energy ones.
+pub(crate) fn suits_section(meta: &PresetMeta, section: SceneSection) -> bool {
+    match section {
+        SceneSection::Calm => meta.energy != Level::High && meta.flash_risk != Level::High,
+        SceneSection::Groove => true,
+        SceneSection::Drive | SceneSection::Impact => meta.energy != Level::Low,
+    }
 }
 
 #[allow(dead_code)]
@@ -679,11 +684,12 @@ pub(crate) fn is_calm_section(audio: &AudioFeatures) -> bool {
 }
 
 pub(crate) fn classify_scene_section(audio: &AudioFeatures) -> SceneSection {
-    let bass = audio.bands[1].clamp(0.0, 1.0);
-    let mid = audio.bands[3].clamp(0.0, 1.0);
-    let treb = (audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0);
+    let bass = audio.band8(1).clamp(0.0, 1.0);
+    let mid = audio.band8(3).clamp(0.0, 1.0);
+    let treb = (audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0);
     let transient = audio.onset.max(audio.beat_strength).clamp(0.0, 1.0);
-    let pulse = (audio.rms * 0.42 + transient * 0.30 + bass * 0.18 + mid * 0.10).clamp(0.0, 1.0);
+    let level = audio.perceived_level();
+    let pulse = (level * 0.42 + transient * 0.30 + bass * 0.18 + mid * 0.10).clamp(0.0, 1.0);
 
     if pulse < 0.24 && transient < 0.21 && !audio.beat && treb < 0.34 {
         SceneSection::Calm
@@ -777,6 +783,10 @@ pub trait VisualEngine {
     fn scene_section_name(&self) -> &'static str {
         SceneSection::Groove.label()
     }
+    /// Long-form energy phase ("Breakdown" or "Build-up"); `None` while steady.
+    fn structure_phase_name(&self) -> Option<&'static str> {
+        None
+    }
     fn cycle_camera_path_mode(&mut self) {}
     fn step_camera_path_mode(&mut self, _forward: bool) {}
     fn camera_path_mode(&self) -> CameraPathMode {
@@ -804,6 +814,21 @@ pub trait VisualEngine {
     fn auto_switch(&self) -> bool;
     fn prev_preset(&mut self);
     fn next_preset(&mut self);
+    fn set_preset_graph(&mut self, graph: Option<CompiledPresetGraph>);
+    fn graph_node_name(&self) -> Option<&str>;
+    /// Live parameters of the active preset; empty on engines that can't change them.
+    fn preset_params(&self) -> Vec<PresetParam> {
+        Vec::new()
+    }
+    /// Sets a parameter on the active preset (and the incoming one during a
+    /// transition). Returns `false` if neither has it.
+    fn set_preset_param(&mut self, _name: &str, _value: f32) -> bool {
+        false
+    }
+    /// Every parameter name `set_preset_param` can change on some preset.
+    fn tunable_preset_params(&self) -> HashSet<&'static str> {
+        HashSet::new()
+    }
     fn update_auto_switch(&mut self, now: Instant, audio: &AudioFeatures);
     fn render(&mut self, ctx: RenderCtx, quality: Quality, scale: usize) -> &[u8];
 }
@@ -819,6 +844,7 @@ pub(crate) struct PlaybackContext {
     pub seconds_per_switch: f32,
     pub last_switch: Instant,
     pub beat_counter: u32,
+    pub last_beat_phase: f32,
     pub transition_started: Option<Instant>,
     pub transition_dur: Duration,
     pub transition_kind: TransitionKind,
@@ -834,9 +860,14 @@ pub(crate) struct PlaybackContext {
     pub scene_section_pending: SceneSection,
     pub scene_section_votes: u8,
     pub scene_section_changed_at: Instant,
+    structure: StructureTracker,
     pub camera_path_mode: CameraPathMode,
     pub camera_path_speed: f32,
+    pub graph: Option<PresetGraphWalker>,
     preset_count: usize,
+    /// Drives transition seeds and picks, shuffle and fractal bias. Forked from
+    /// the thread's global RNG, so `fastrand::seed` (`--seed`) fixes it.
+    pub rng: fastrand::Rng,
 }
 
 impl PlaybackContext {
@@ -849,6 +880,7 @@ impl PlaybackContext {
         seconds_per_switch: f32,
     ) -> Self {
         let now = Instant::now();
+        let mut rng = fastrand::Rng::new();
         let last_auto_mode = if switch_mode == SwitchMode::Manual {
             SwitchMode::Adaptive
         } else {
@@ -865,10 +897,11 @@ impl PlaybackContext {
             seconds_per_switch: seconds_per_switch.max(1.0),
             last_switch: now,
             beat_counter: 0,
+            last_beat_phase: 0.0,
             transition_started: None,
             transition_dur: Duration::from_millis(900),
             transition_kind: TransitionKind::Fade,
-            transition_seed: fastrand::u32(..),
+            transition_seed: rng.u32(..),
             transition_mode: TransitionMode::Auto,
             last_transition_kind: TransitionKind::Fade,
             transition_override: None,
@@ -880,9 +913,12 @@ impl PlaybackContext {
             scene_section_pending: SceneSection::Groove,
             scene_section_votes: 0,
             scene_section_changed_at: now,
+            structure: StructureTracker::new(now),
             camera_path_mode: CameraPathMode::Auto,
             camera_path_speed: 1.0,
+            graph: None,
             preset_count,
+            rng,
         }
     }
 
@@ -960,6 +996,13 @@ impl PlaybackContext {
         self.scene_section.label()
     }
 
+    pub fn structure_phase_name(&self) -> Option<&'static str> {
+        match self.structure.phase() {
+            StructurePhase::Steady => None,
+            phase => Some(phase.label()),
+        }
+    }
+
     pub fn cycle_camera_path_mode(&mut self) {
         self.camera_path_mode = self.camera_path_mode.next();
     }
@@ -1045,6 +1088,14 @@ impl PlaybackContext {
         self.switch_mode != SwitchMode::Manual
     }
 
+    pub fn set_preset_graph(&mut self, graph: Option<CompiledPresetGraph>) {
+        self.graph = graph.map(PresetGraphWalker::new);
+    }
+
+    pub fn graph_node_name(&self) -> Option<&str> {
+        self.graph.as_ref().map(|w| w.current().id.as_str())
+    }
+
     fn playlist_pos_for_active(&self) -> usize {
         self.playlist.iter().position(|&i| i == self.active).unwrap_or(0)
     }
@@ -1056,7 +1107,7 @@ impl PlaybackContext {
         if self.playlist.len() == 1 {
             return self.playlist[0];
         }
-        let mut idx = self.playlist[fastrand::usize(..self.playlist.len())];
+        let mut idx = self.playlist[self.rng.usize(..self.playlist.len())];
         if idx == self.active {
             let pos = self.playlist_pos_for_active();
             idx = self.playlist[(pos + 1) % self.playlist.len()];
@@ -1094,7 +1145,7 @@ impl PlaybackContext {
         if next == self.active || self.preset_count == 0 {
             return;
         }
-        self.transition_seed = fastrand::u32(..);
+        self.transition_seed = self.rng.u32(..);
         self.transition_kind = if let Some(k) = self.transition_override {
             k
         } else {
@@ -1119,48 +1170,55 @@ impl PlaybackContext {
         self.transition_dur = dur.clamp(Duration::from_millis(80), Duration::from_millis(2600));
         self.transition_kind = kind;
         self.last_transition_kind = kind;
-        self.transition_seed = fastrand::u32(..);
+        self.transition_seed = self.rng.u32(..);
         self.next = Some(next);
         self.transition_started = Some(Instant::now());
         self.last_switch = Instant::now();
         self.beat_counter = 0;
     }
 
-    pub fn pick_fractal_index(&mut self, name_of: impl Fn(usize) -> &'static str) -> Option<usize> {
+    /// A fractal preset from the playlist that also suits the current section.
+    pub fn pick_fractal_index(&mut self, meta_of: impl Fn(usize) -> PresetMeta) -> Option<usize> {
+        let section = self.scene_section;
+        self.pick_matching(|i| {
+            let meta = meta_of(i);
+            meta.category == PresetCategory::Fractal && suits_section(&meta, section)
+        })
+    }
+
+    /// Another playlist entry that satisfies `fits`: a random one in shuffle
+    /// mode, otherwise the first one after the active preset.
+    fn pick_matching(&mut self, fits: impl Fn(usize) -> bool) -> Option<usize> {
         if self.playlist.len() <= 1 {
             return None;
         }
-        let fractals = self
-            .playlist
-            .iter()
-            .copied()
-            .filter_map(|i| {
-                if i != self.active && is_fractal_preset_name(name_of(i)) {
-                    Some(i)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
-        if fractals.is_empty() {
-            return None;
-        }
         if self.shuffle {
-            return Some(fractals[fastrand::usize(..fractals.len())]);
+            let matches = self
+                .playlist
+                .iter()
+                .copied()
+                .filter(|&i| i != self.active && fits(i))
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                return None;
+            }
+            return Some(matches[self.rng.usize(..matches.len())]);
         }
         let pos = self.playlist_pos_for_active();
-        for d in 1..self.playlist.len() {
-            let idx = self.playlist[(pos + d) % self.playlist.len()];
-            if idx != self.active && is_fractal_preset_name(name_of(idx)) {
-                return Some(idx);
-            }
+        (1..self.playlist.len())
+            .map(|d| self.playlist[(pos + d) % self.playlist.len()])
+            .find(|&idx| idx != self.active && fits(idx))
+    }
+
+    fn next_preset_auto(&mut self, audio: &AudioFeatures, meta_of: impl Fn(usize) -> PresetMeta) {
+        if let Some(next) = self.auto_next_index(meta_of) {
+            self.start_auto_transition(next, audio);
         }
-        None
     }
 
-    fn next_preset_auto(&mut self, audio: &AudioFeatures, name_of: impl Fn(usize) -> &'static str) {
+    fn auto_next_index(&mut self, meta_of: impl Fn(usize) -> PresetMeta) -> Option<usize> {
         if self.playlist.is_empty() {
-            return;
+            return None;
         }
         let mut next = if self.shuffle {
             self.pick_shuffle()
@@ -1168,18 +1226,51 @@ impl PlaybackContext {
             let pos = self.playlist_pos_for_active();
             self.playlist[(pos + 1) % self.playlist.len()]
         };
+        if self.switch_mode != SwitchMode::Adaptive {
+            return Some(next);
+        }
+        let section = self.scene_section;
         if self.fractal_bias
-            && self.switch_mode == SwitchMode::Adaptive
-            && self.scene_section == SceneSection::Calm
-            && fastrand::f32() < 0.78
+            && section == SceneSection::Calm
+            && self.rng.f32() < 0.78
+            && let Some(fr) = self.pick_fractal_index(&meta_of)
         {
-            if let Some(fr) = self.pick_fractal_index(&name_of) {
-                next = fr;
-            }
+            return Some(fr);
         }
+        // Keep the rotation order unless the next preset clashes with the section.
+        let fits = |i: usize| suits_section(&meta_of(i), section);
+        if !fits(next)
+            && let Some(fit) = self.pick_matching(fits)
+        {
+            next = fit;
+        }
+        Some(next)
+    }
+
+    /// Hard switch on a detected drop. A transition still running from before the
+    /// build-up is finished first so the cut lands on the drop itself.
+    fn drop_transition(&mut self, audio: &AudioFeatures, meta_of: impl Fn(usize) -> PresetMeta) {
+        if let Some(next) = self.next.take() {
+            self.active = next;
+            self.transition_started = None;
+        }
+        let Some(next) = self.auto_next_index(meta_of) else {
+            return;
+        };
+        let kind = self.transition_override.unwrap_or_else(|| {
+            pick_kind(
+                self.rng.u32(..),
+                &[TransitionKind::Cut, TransitionKind::Flash],
+                self.last_transition_kind,
+            )
+        });
+        self.start_transition_with_dur(next, transition_duration_for_kind(kind, audio), kind);
+    }
+
+    fn start_auto_transition(&mut self, next: usize, audio: &AudioFeatures) {
         let (mut dur, mut kind) = suggest_transition_for_section(
             audio,
-            fastrand::u32(..),
+            self.rng.u32(..),
             self.transition_mode,
             self.last_transition_kind,
             self.scene_section,
@@ -1216,30 +1307,62 @@ impl PlaybackContext {
         }
     }
 
+    /// `Some(true)` on the frame the tracked beat phase wraps, `None` without a tempo lock.
+    fn tempo_beat_tick(&mut self, audio: &AudioFeatures) -> Option<bool> {
+        let prev = std::mem::replace(&mut self.last_beat_phase, audio.beat_phase);
+        if audio.bpm <= 0.0 {
+            return None;
+        }
+        Some(audio.beat_phase + 0.5 < prev)
+    }
+
     pub fn update_auto_switch(
         &mut self,
         now: Instant,
         audio: &AudioFeatures,
-        name_of: impl Fn(usize) -> &'static str,
+        meta_of: impl Fn(usize) -> PresetMeta,
     ) {
         self.update_scene_section_state(now, audio);
+        let tempo_beat = self.tempo_beat_tick(audio);
+        let structure_event = self.structure.update(now, audio);
 
         if self.switch_mode == SwitchMode::Manual {
             return;
         }
+        if self.graph.is_none() && structure_event == Some(StructureEvent::Drop) {
+            self.drop_transition(audio, &meta_of);
+            return;
+        }
         if self.transition_started.is_some() {
             return;
         }
 
+        if self.graph.is_some() {
+            self.update_graph_switch(now, audio);
+            return;
+        }
+        // Keep the scene through a build-up so the drop gets the change.
+        if self.structure.holding(now) {
+            return;
+        }
+
         match self.switch_mode {
             SwitchMode::Manual => {}
             SwitchMode::Beat => {
-                if audio.beat {
+                // With a tempo lock, count beats off the tracked phase so syncopated
+                // onsets don't advance the counter, and land whole-bar switches on
+                // the downbeat. Without a lock, fall back to raw onset peaks.
+                let (tick, downbeat) = match tempo_beat {
+                    Some(tick) => (tick, audio.bar_phase < 0.125),
+                    None => (audio.beat, true),
+                };
+                if tick {
                     self.beat_counter = self.beat_counter.wrapping_add(1);
                     let beats_per =
                         section_beats_per_switch(self.beats_per_switch, self.scene_section);
-                    if self.beat_counter % beats_per == 0 {
-                        self.next_preset_auto(audio, &name_of);
+                    let bar_aligned = !beats_per.is_multiple_of(4) || downbeat;
+                    if self.beat_counter >= beats_per && bar_aligned {
+                        self.next_preset_auto(audio, &meta_of);
                     }
                 }
             }
@@ -1248,19 +1371,19 @@ impl PlaybackContext {
                 let since = now.duration_since(self.last_switch).as_secs_f32();
                 let (energy_gate, min_since) = section_energy_gate(self.scene_section);
                 if e > energy_gate && since > min_since {
-                    self.next_preset_auto(audio, &name_of);
+                    self.next_preset_auto(audio, &meta_of);
                 }
             }
             SwitchMode::Time => {
                 let target = (self.seconds_per_switch * section_time_scale(self.scene_section))
                     .clamp(2.0, 60.0);
                 if now.duration_since(self.last_switch).as_secs_f32() > target {
-                    self.next_preset_auto(audio, &name_of);
+                    self.next_preset_auto(audio, &meta_of);
                 }
             }
             SwitchMode::Adaptive => {
                 let since = now.duration_since(self.last_switch).as_secs_f32();
-                let treb = (audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0);
+                let treb = (audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0);
                 let hit = audio.onset.max(audio.beat_strength).max(treb);
                 let e = audio.rms;
 
@@ -1289,14 +1412,34 @@ impl PlaybackContext {
                 let slam = (audio.beat && audio.beat_strength > slam_gate)
                     || audio.onset > (slam_gate - 0.04);
                 if slam && since > min_since {
-                    self.next_preset_auto(audio, &name_of);
+                    self.next_preset_auto(audio, &meta_of);
                 } else if since > target {
-                    self.next_preset_auto(audio, &name_of);
+                    self.next_preset_auto(audio, &meta_of);
                 }
             }
         }
     }
 
+    /// Walks the loaded preset graph instead of the switch-mode policy: the
+    /// section-scaled `seconds_per_switch` is the minimum dwell per node.
+    fn update_graph_switch(&mut self, now: Instant, audio: &AudioFeatures) {
+        let min_dwell = Duration::from_secs_f32(
+            (self.seconds_per_switch * section_time_scale(self.scene_section)).clamp(2.0, 60.0),
+        );
+        let active = self.active;
+        let preset_count = self.preset_count;
+        let Some(walker) = self.graph.as_mut() else {
+            return;
+        };
+        walker.sync_to_preset(active, now);
+        if let Some(next) = walker
+            .step(now, audio, min_dwell)
+            .filter(|&idx| idx < preset_count)
+        {
+            self.start_auto_transition(next, audio);
+        }
+    }
+
     /// Advance transition state; returns current blend alpha (0.0 if no transition active).
     pub fn step_transition(&mut self, now: Instant) -> f32 {
         if let (Some(start), Some(next)) = (self.transition_started, self.next) {
@@ -1459,6 +1602,7 @@ impl VisualEngine for PresetEngine {
     fn next_transition_kind(&mut self) { self.ctx.next_transition_kind() }
     fn prev_transition_kind(&mut self) { self.ctx.prev_transition_kind() }
     fn scene_section_name(&self) -> &'static str { self.ctx.scene_section_name() }
+    fn structure_phase_name(&self) -> Option<&'static str> { self.ctx.structure_phase_name() }
     fn cycle_camera_path_mode(&mut self) { self.ctx.cycle_camera_path_mode() }
     fn step_camera_path_mode(&mut self, forward: bool) { self.ctx.step_camera_path_mode(forward) }
     fn camera_path_mode(&self) -> CameraPathMode { self.ctx.camera_path_mode() }
@@ -1480,9 +1624,42 @@ impl VisualEngine for PresetEngine {
     fn prev_preset(&mut self) { self.ctx.prev_preset() }
     fn next_preset(&mut self) { self.ctx.next_preset() }
 
+    fn set_preset_graph(&mut self, graph: Option<CompiledPresetGraph>) {
+        self.ctx.set_preset_graph(graph)
+    }
+
+    fn graph_node_name(&self) -> Option<&str> {
+        self.ctx.graph_node_name()
+    }
+
+    fn preset_params(&self) -> Vec<PresetParam> {
+        self.presets
+            .get(self.ctx.active)
+            .map(|p| p.params())
+            .unwrap_or_default()
+    }
+
+    fn set_preset_param(&mut self, name: &str, value: f32) -> bool {
+        let mut applied = false;
+        for idx in [Some(self.ctx.active), self.ctx.next].into_iter().flatten() {
+            if let Some(preset) = self.presets.get_mut(idx) {
+                applied |= preset.set_param(name, value);
+            }
+        }
+        applied
+    }
+
+    fn tunable_preset_params(&self) -> HashSet<&'static str> {
+        self.presets
+            .iter()
+            .flat_map(|p| p.params())
+            .map(|p| p.name)
+            .collect()
+    }
+
     fn update_auto_switch(&mut self, now: Instant, audio: &AudioFeatures) {
         let presets = &self.presets;
-        self.ctx.update_auto_switch(now, audio, |i| presets[i].name())
+        self.ctx.update_auto_switch(now, audio, |i| presets[i].meta())
     }
 
     fn render(&mut self, ctx: RenderCtx, quality: Quality, scale: usize) -> &[u8] {
@@ -1576,8 +1753,8 @@ fn blend_radial_rgba(
     out: &mut [u8],
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
-    let bass = audio.bands[1].clamp(0.0, 1.0);
-    let treb = ((audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0)).clamp(0.0, 1.0);
+    let bass = audio.band8(1).clamp(0.0, 1.0);
+    let treb = ((audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0)).clamp(0.0, 1.0);
     let feather = 0.035 + 0.11 * bass;
 
     let wf = w.max(1) as f32;
@@ -1612,8 +1789,8 @@ fn blend_swirl_rgba(
     out: &mut [u8],
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
-    let bass = audio.bands[1].clamp(0.0, 1.0);
-    let mid = audio.bands[3].clamp(0.0, 1.0);
+    let bass = audio.band8(1).clamp(0.0, 1.0);
+    let mid = audio.band8(3).clamp(0.0, 1.0);
     let spin = (1.2 + 2.6 * bass + 1.4 * mid) * (1.0 + 0.3 * (t * 0.7).sin());
 
     let wf = w.max(1) as f32;
@@ -1653,7 +1830,7 @@ fn blend_dissolve_rgba(
     out: &mut [u8],
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
-    let treb = ((audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0)).clamp(0.0, 1.0);
+    let treb = ((audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0)).clamp(0.0, 1.0);
     let drive = (audio.onset + audio.beat_strength + treb * 0.7).clamp(0.0, 1.0);
     let blocks = (8.0 + 48.0 * drive).clamp(8.0, 64.0) as usize;
     let feather = (0.08 - 0.05 * drive).clamp(0.015, 0.09);
@@ -1690,7 +1867,7 @@ fn blend_cut_rgba(
     out: &mut [u8],
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
-    let drive = (audio.onset + audio.beat_strength + audio.bands[1] * 0.5).clamp(0.0, 1.0);
+    let drive = (audio.onset + audio.beat_strength + audio.band8(1) * 0.5).clamp(0.0, 1.0);
     let gate = (alpha * (1.35 + 0.65 * drive)).clamp(0.0, 1.0);
     let tear_amp = (1.0 - alpha) * (0.025 + 0.08 * drive);
     let wf = w.max(1) as f32;
@@ -1729,9 +1906,9 @@ fn blend_morph_rgba(
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
     let eased = smoothstep(0.0, 1.0, alpha);
-    let bass = audio.bands[1].clamp(0.0, 1.0);
-    let mid = audio.bands[3].clamp(0.0, 1.0);
-    let treb = ((audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0)).clamp(0.0, 1.0);
+    let bass = audio.band8(1).clamp(0.0, 1.0);
+    let mid = audio.band8(3).clamp(0.0, 1.0);
+    let treb = ((audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0)).clamp(0.0, 1.0);
     let amp = (0.06 + 0.22 * (bass * 0.6 + mid * 0.25 + treb * 0.15)) * (1.0 - 0.45 * eased);
     let wf = w.max(1) as f32;
     let hf = h.max(1) as f32;
@@ -1770,7 +1947,7 @@ fn blend_wipe_rgba(
     out: &mut [u8],
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
-    let drive = (audio.onset + audio.beat_strength + audio.bands[1] * 0.55).clamp(0.0, 1.0);
+    let drive = (audio.onset + audio.beat_strength + audio.band8(1) * 0.55).clamp(0.0, 1.0);
     let seed_phase = ((seed >> 11) & 1023) as f32 * (1.0 / 1024.0) * std::f32::consts::TAU;
     let ang = t * (0.35 + 0.8 * drive) + seed_phase;
     let dir = (ang.cos(), ang.sin());
@@ -1807,7 +1984,7 @@ fn blend_luma_rgba(
     out: &mut [u8],
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
-    let drive = (audio.onset + audio.beat_strength + audio.bands[5] * 0.45).clamp(0.0, 1.0);
+    let drive = (audio.onset + audio.beat_strength + audio.band8(5) * 0.45).clamp(0.0, 1.0);
     let feather = (0.09 - 0.05 * drive).clamp(0.02, 0.1);
     let noise_amp = 0.08 + 0.12 * (1.0 - alpha);
 
@@ -1840,7 +2017,7 @@ fn blend_flash_rgba(
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
     let gate = smoothstep(0.20, 0.80, alpha);
-    let drive = (audio.onset + audio.beat_strength + audio.bands[1] * 0.45).clamp(0.0, 1.0);
+    let drive = (audio.onset + audio.beat_strength + audio.band8(1) * 0.45).clamp(0.0, 1.0);
     let flash = (1.0 - (alpha * 2.0 - 1.0).abs()).powf(1.65) * (0.35 + 0.55 * drive);
 
     for y in 0..h {
@@ -1872,7 +2049,7 @@ fn blend_prism_rgba(
     out: &mut [u8],
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
-    let drive = (audio.bands[5] + audio.bands[6] + audio.onset * 0.6).clamp(0.0, 1.0);
+    let drive = (audio.band8(5) + audio.band8(6) + audio.onset * 0.6).clamp(0.0, 1.0);
     let eased = smoothstep(0.0, 1.0, alpha);
     let split = (0.02 + 0.11 * drive) * (1.0 - 0.35 * eased);
     let wf = w.max(1) as f32;
@@ -1914,9 +2091,9 @@ fn blend_remix_rgba(
 ) {
     let alpha = alpha.clamp(0.0, 1.0);
     let eased = smoothstep(0.0, 1.0, alpha);
-    let bass = audio.bands[1].clamp(0.0, 1.0);
-    let mid = audio.bands[3].clamp(0.0, 1.0);
-    let treb = ((audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0)).clamp(0.0, 1.0);
+    let bass = audio.band8(1).clamp(0.0, 1.0);
+    let mid = audio.band8(3).clamp(0.0, 1.0);
+    let treb = ((audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0)).clamp(0.0, 1.0);
     let drive = (bass * 0.45 + mid * 0.35 + treb * 0.2 + audio.onset * 0.35).clamp(0.0, 1.0);
     let amp = (0.05 + 0.22 * drive) * (1.0 - 0.3 * eased);
     let feedback = ((1.0 - alpha) * (0.08 + 0.32 * drive)).clamp(0.0, 0.78);
@@ -1976,7 +2153,7 @@ fn blend_echo_rgba(
     }
 
     let alpha = alpha.clamp(0.0, 1.0);
-    let drive = (audio.bands[1] + audio.beat_strength + audio.onset * 0.8).clamp(0.0, 1.0);
+    let drive = (audio.band8(1) + audio.beat_strength + audio.onset * 0.8).clamp(0.0, 1.0);
     let mix_amt = ((1.0 - alpha) * (0.14 + 0.58 * drive)).clamp(0.0, 0.88);
     let wf = w.max(1) as f32;
     let hf = h.max(1) as f32;
@@ -2016,7 +2193,7 @@ fn datamosh_overlay(
     }
 
     let alpha = alpha.clamp(0.0, 1.0);
-    let treb = (audio.bands[5] + audio.bands[6] + audio.bands[7]) * (1.0 / 3.0);
+    let treb = (audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0);
     let drive = (audio.onset + audio.beat_strength + treb * 0.7).clamp(0.0, 1.0);
 
     // More treble/onset -> smaller blocks and stronger displacement.
@@ -1,9 +1,11 @@
 use crate::audio::AudioFeatures;
 use crate::config::Quality;
+use crate::preset_file::{PostFxSpec, PresetDefinition};
+use crate::preset_id::preset_slug;
 use std::f32::consts::PI;
 use std::time::Instant;
 
-pub struct RenderCtx {
+pub struct RenderCtx<'a> {
     pub now: Instant,
     pub t: f32,
     pub dt: f32,
@@ -13,153 +15,408 @@ pub struct RenderCtx {
     pub beat_pulse: f32,
     pub fractal_zoom_mul: f32,
     pub safe: bool,
+    /// Stereo split mode: field presets also drift and widen with the mix.
+    pub stereo: bool,
     pub quality: Quality,
     pub scale: usize,
+    /// Log-spaced spectrum bins (0..1); empty when no snapshot is available.
+    pub spectrum: &'a [f32],
+    /// Latest analysis window (-1..1); empty when no snapshot is available.
+    pub waveform: &'a [f32],
+    /// Side signal for `waveform`; empty or all zeros for mono input.
+    pub side: &'a [f32],
+}
+
+/// What kind of imagery a preset draws.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PresetCategory {
+    Fractal,
+    Attractor,
+    Organic,
+    Geometric,
+    Particle,
+    Retro,
+    Scope,
+}
+
+impl PresetCategory {
+    pub const ALL: [Self; 7] = [
+        Self::Fractal,
+        Self::Attractor,
+        Self::Organic,
+        Self::Geometric,
+        Self::Particle,
+        Self::Retro,
+        Self::Scope,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Fractal => "fractal",
+            Self::Attractor => "attractor",
+            Self::Organic => "organic",
+            Self::Geometric => "geometric",
+            Self::Particle => "particle",
+            Self::Retro => "retro",
+            Self::Scope => "scope",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.label() == label)
+    }
+}
+
+/// Three-step rating used for a preset's energy, motion and flash risk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Low,
+    Medium,
+    High,
+}
+
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        [Self::Low, Self::Medium, Self::High]
+            .into_iter()
+            .find(|l| l.label() == label)
+    }
+}
+
+/// Structured description of a preset, read by auto-switching and the
+/// selector menus instead of anything in the display name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PresetMeta {
+    pub category: PresetCategory,
+    /// How well the preset fits loud, dense sections.
+    pub energy: Level,
+    /// How fast the image moves, spins or zooms.
+    pub motion: Level,
+    /// How likely the preset is to strobe or flash the whole frame.
+    pub flash_risk: Level,
+    pub tags: &'static [&'static str],
+}
+
+impl PresetMeta {
+    pub const fn new(
+        category: PresetCategory,
+        energy: Level,
+        motion: Level,
+        flash_risk: Level,
+        tags: &'static [&'static str],
+    ) -> Self {
+        Self {
+            category,
+            energy,
+            motion,
+            flash_risk,
+            tags,
+        }
+    }
+}
+
+/// A parameter that can be changed while a preset runs, with its current
+/// value and the range `Preset::set_param` clamps to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PresetParam {
+    pub name: &'static str,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
 }
 
 pub trait Preset {
     fn name(&self) -> &'static str;
+    fn meta(&self) -> PresetMeta;
+    /// Stable id used by theme packs, preset graphs and saved playlists. Built-ins
+    /// freeze theirs so renaming a preset doesn't break references to it.
+    fn id(&self) -> String {
+        preset_slug(self.name())
+    }
+    /// Live-tunable parameters, in a stable order.
+    fn params(&self) -> Vec<PresetParam> {
+        Vec::new()
+    }
+    fn param(&self, name: &str) -> Option<f32> {
+        self.params().into_iter().find(|p| p.name == name).map(|p| p.value)
+    }
+    /// Sets a parameter, clamped to its range (whole-number parameters are
+    /// rounded). Returns `false` if the preset has no such parameter.
+    fn set_param(&mut self, _name: &str, _value: f32) -> bool {
+        false
+    }
+    /// Whether `MetalEngine` has a shader for this preset; it renders the
+    /// others on the CPU.
+    fn has_metal_shader(&self) -> bool {
+        false
+    }
     fn render(&mut self, ctx: &RenderCtx, prev: &[u8], out: &mut [u8]);
     fn on_resize(&mut self, _w: usize, _h: usize) {}
 }
 
+/// Ids of `make_presets()`, in order, for callers that only need to know which
+/// ids are taken (building the registry draws from the global RNG).
+pub const BUILTIN_PRESET_IDS: &[&str] = &[
+    "mandelbrot-bass-zoom",
+    "julia-treble-shimmer",
+    "fractal-flame-beat-burst",
+    "feedback-tunnel-tempo-spin",
+    "plasma-kaleidoscope",
+    "neon-grid-warp",
+    "voronoi-shatter-beat-cuts",
+    "metaballs-sub-bass-pump",
+    "particle-fountain-hi-hat-sparks",
+    "starfield-kick-accel",
+    "flow-field-vocal-sway",
+    "chromatic-waves",
+    "spectrum-vortex",
+    "reaction-diffusion-lite",
+    "cellular-automata-beat-seeding",
+    "glitch-mosaic-transient-trigger",
+    "concentric-rings-snare-flash",
+    "heatmap-smoke",
+    "phase-shift-stripes",
+    "prism-noise-treble-rain",
+    "burning-ship-bass-sink",
+    "orbit-trap-neon-bloom",
+    "clifford-field-acid-lace",
+    "de-jong-field-neon-knots",
+    "domain-warp-candy-melt",
+    "polar-moire-treble-sheen",
+    "truchet-tiles-groove",
+    "sdf-orbs-beat-pop",
+    "chladni-plates-bass-lines",
+    "crt-scanlines-vhs-glow",
+    "kaleido-mandala",
+    "moire-interference",
+    "starfield-hyperdrive",
+    "metaballs-neon-lava",
+    "voronoi-crystal-lattice",
+    "plasma-aurora-storm",
+    "vortex-warpdrive",
+    "stripes-hypersync",
+    "glitch-blocks-datamosh-drift",
+    "noise-ribbons-treble-drift",
+    "mandelbrot-infinite-dive",
+    "mandelbrot-seahorse-zoom",
+    "mandelbrot-spiral-probe",
+    "julia-infinite-bloom",
+    "julia-cathedral-zoom",
+    "burning-ship-abyss-dive",
+    "reaction-diffusion-psychedelic-bloom",
+    "fluid-vorticity-bass-storm",
+    "fractal-flame-ifs-cathedral",
+    "mandelbulb-slice-neon-relic",
+    "sphere-trace-gyroid-temple",
+    "curl-noise-plasma-veins",
+    "perlin-warp-liquid-aurora",
+    "ifs-attractor-ribbon-knot",
+    "fractal-morph-wormhole-garden",
+    "sdf-fractal-cosmic-monolith",
+    "hopalong-attractor-neon-dust",
+    "ikeda-loop-bass-cyclone",
+    "hex-tunnel-lattice-drive",
+    "gyroid-slice-reactor-core",
+    "phyllotaxis-bloom-harmonic-seeds",
+    "nova-fractal-root-resonance",
+    "spectrum-bars-classic-analyzer",
+    "spectrum-mirror-center-split",
+    "spectrum-halo-radial-bars",
+    "oscilloscope-waveform-trace",
+    "waveform-ring-polar-scope",
+    "lissajous-scope-phase-portrait",
+];
+
 pub fn make_presets() -> Vec<Box<dyn Preset>> {
     use Algo::*;
+    use Level::*;
     use Palette::*;
+    use PresetCategory::*;
 
     let mut v: Vec<Box<dyn Preset>> = Vec::new();
 
     v.push(Box::new(FieldPreset::new(
+        "mandelbrot-bass-zoom",
         "Mandelbrot: Bass Zoom",
         Mandelbrot {
             center: (-0.743_643_9, 0.131_825_91),
         },
         Prism,
         Feedback::tunnel(0.88, 0.018, 1.5),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["zoom", "bass"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "julia-treble-shimmer",
         "Julia: Treble Shimmer",
         Julia { c_base: (-0.8, 0.156) },
         Neon,
         Feedback::tunnel(0.9, 0.014, 1.2),
+        PresetMeta::new(Fractal, Low, Low, Low, &["julia", "treble"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "fractal-flame-beat-burst",
         "Fractal Flame: Beat Burst",
         Julia { c_base: (0.285, 0.01) },
         Fire,
         Feedback::tunnel(0.92, 0.02, 1.8),
+        PresetMeta::new(Fractal, High, Medium, Medium, &["flame", "beat"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "feedback-tunnel-tempo-spin",
         "Feedback Tunnel: Tempo Spin",
         Plasma { freq: 2.4 },
         Aurora,
         Feedback::tunnel(0.94, 0.03, 2.2),
+        PresetMeta::new(Geometric, Medium, High, Low, &["tunnel", "tempo"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "plasma-kaleidoscope",
         "Plasma Kaleidoscope",
         Kaleido { freq: 2.7, symmetry: 7 },
         Acid,
         Feedback::tunnel(0.9, 0.02, 1.4),
+        PresetMeta::new(Geometric, Medium, Medium, Low, &["kaleidoscope", "plasma"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "neon-grid-warp",
         "Neon Grid Warp",
         Stripes { freq: 14.0 },
         Neon,
         Feedback::tunnel(0.93, 0.022, 1.7),
+        PresetMeta::new(Geometric, High, Medium, Medium, &["grid", "neon"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "voronoi-shatter-beat-cuts",
         "Voronoi Shatter: Beat Cuts",
         Voronoi { points: 6 },
         Prism,
         Feedback::tunnel(0.88, 0.03, 1.6),
+        PresetMeta::new(Geometric, High, Medium, High, &["cells", "beat"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "metaballs-sub-bass-pump",
         "Metaballs: Sub Bass Pump",
         Metaballs { blobs: 5 },
         Aurora,
         Feedback::tunnel(0.9, 0.02, 1.2),
+        PresetMeta::new(Organic, Medium, Medium, Low, &["blobs", "bass"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "particle-fountain-hi-hat-sparks",
         "Particle Fountain: Hi-hat Sparks",
         Sparks { density: 1.0 },
         Neon,
         Feedback::tunnel(0.86, 0.018, 1.0),
+        PresetMeta::new(Particle, High, High, Medium, &["sparks", "hats"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "starfield-kick-accel",
         "Starfield: Kick Accel",
         Starfield { depth: 1.0 },
         Prism,
         Feedback::none(),
+        PresetMeta::new(Particle, Medium, High, Low, &["stars", "kick"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "flow-field-vocal-sway",
         "Flow Field: Vocal Sway",
         Flow { freq: 1.6 },
         Aurora,
         Feedback::tunnel(0.95, 0.016, 0.9),
+        PresetMeta::new(Organic, Low, Low, Low, &["flow", "vocal"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "chromatic-waves",
         "Chromatic Waves",
         Rings { freq: 6.5 },
         Prism,
         Feedback::tunnel(0.9, 0.02, 1.1),
+        PresetMeta::new(Geometric, Medium, Medium, Low, &["rings", "waves"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "spectrum-vortex",
         "Spectrum Vortex",
         Vortex { spin: 1.2 },
         Acid,
         Feedback::tunnel(0.93, 0.02, 1.4),
+        PresetMeta::new(Geometric, High, High, Low, &["vortex", "spin"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "reaction-diffusion-lite",
         "Reaction-Diffusion Lite",
         Smoke { blur: 0.55 },
         Aurora,
         Feedback::tunnel(0.97, 0.01, 0.6),
+        PresetMeta::new(Organic, Low, Low, Low, &["smoke", "ambient"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "cellular-automata-beat-seeding",
         "Cellular Automata: Beat Seeding",
         Cells { scale: 8.0 },
         Acid,
         Feedback::tunnel(0.9, 0.02, 0.8),
+        PresetMeta::new(Retro, Medium, Medium, Medium, &["cells", "beat"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "glitch-mosaic-transient-trigger",
         "Glitch Mosaic: Transient Trigger",
         Glitch { block: 10.0 },
         Neon,
         Feedback::tunnel(0.86, 0.02, 1.0),
+        PresetMeta::new(Retro, High, High, High, &["glitch", "transient"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "concentric-rings-snare-flash",
         "Concentric Rings: Snare Flash",
         Rings { freq: 10.0 },
         Fire,
         Feedback::tunnel(0.9, 0.02, 1.0),
+        PresetMeta::new(Geometric, High, Medium, High, &["rings", "snare"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "heatmap-smoke",
         "Heatmap Smoke",
         Smoke { blur: 0.75 },
         Fire,
         Feedback::tunnel(0.98, 0.01, 0.5),
+        PresetMeta::new(Organic, Low, Low, Low, &["smoke", "ambient"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "phase-shift-stripes",
         "Phase-Shift Stripes",
         Stripes { freq: 22.0 },
         Prism,
         Feedback::tunnel(0.9, 0.02, 1.4),
+        PresetMeta::new(Geometric, Medium, Medium, Medium, &["stripes"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "prism-noise-treble-rain",
         "Prism Noise: Treble Rain",
         Noise { freq: 3.0 },
         Prism,
         Feedback::tunnel(0.9, 0.02, 1.1),
+        PresetMeta::new(Organic, Medium, Medium, Low, &["noise", "treble"]),
     )));
 
     // v1.1: +20 presets (more fractals, warps, attractor fields, and geometric tiles).
     v.push(Box::new(FieldPreset::new(
+        "burning-ship-bass-sink",
         "Burning Ship: Bass Sink",
         BurningShip { center: (-0.45, -0.02) },
         Fire,
         Feedback::tunnel(0.90, 0.022, 1.55),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["zoom", "bass"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "orbit-trap-neon-bloom",
         "Orbit Trap: Neon Bloom",
         OrbitTrap {
             center: (-0.38, 0.58),
@@ -167,118 +424,156 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Neon,
         Feedback::tunnel(0.93, 0.020, 1.25),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["orbit-trap", "neon"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "clifford-field-acid-lace",
         "Clifford Field: Acid Lace",
         Clifford,
         Cosmic,
         Feedback::tunnel(0.92, 0.020, 1.15),
+        PresetMeta::new(Attractor, Low, Low, Low, &["attractor"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "de-jong-field-neon-knots",
         "de Jong Field: Neon Knots",
         DeJong,
         Neon,
         Feedback::tunnel(0.90, 0.024, 1.10),
+        PresetMeta::new(Attractor, Low, Low, Low, &["attractor"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "domain-warp-candy-melt",
         "Domain Warp: Candy Melt",
         Warp { freq: 2.9 },
         Cosmic,
         Feedback::tunnel(0.95, 0.016, 0.95),
+        PresetMeta::new(Organic, Low, Medium, Low, &["warp"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "polar-moire-treble-sheen",
         "Polar Moire: Treble Sheen",
         PolarMoire { freq: 1.0 },
         Prism,
         Feedback::tunnel(0.92, 0.020, 1.10),
+        PresetMeta::new(Geometric, Low, Low, Medium, &["moire", "treble"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "truchet-tiles-groove",
         "Truchet Tiles: Groove",
         Truchet { tiles: 8.0 },
         Aurora,
         Feedback::tunnel(0.90, 0.018, 1.05),
+        PresetMeta::new(Geometric, Medium, Low, Low, &["tiles", "groove"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "sdf-orbs-beat-pop",
         "SDF Orbs: Beat Pop",
         Orbs { freq: 2.4 },
         Neon,
         Feedback::tunnel(0.88, 0.020, 1.05),
+        PresetMeta::new(Geometric, High, Medium, Medium, &["orbs", "beat"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "chladni-plates-bass-lines",
         "Chladni Plates: Bass Lines",
         Chladni { a: 2.0, b: 2.0 },
         Prism,
         Feedback::none(),
+        PresetMeta::new(Geometric, Medium, Low, Low, &["chladni", "bass"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "crt-scanlines-vhs-glow",
         "CRT Scanlines: VHS Glow",
         Crt { freq: 320.0 },
         Cosmic,
         Feedback::tunnel(0.96, 0.012, 0.85),
+        PresetMeta::new(Retro, Low, Low, Medium, &["crt", "vhs"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "kaleido-mandala",
         "Kaleido Mandala",
         Kaleido { freq: 4.2, symmetry: 11 },
         Cosmic,
         Feedback::tunnel(0.92, 0.020, 1.35),
+        PresetMeta::new(Geometric, Low, Low, Low, &["kaleidoscope", "mandala"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "moire-interference",
         "Moire Interference",
         Moire { freq: 18.0 },
         Prism,
         Feedback::tunnel(0.90, 0.020, 1.20),
+        PresetMeta::new(Geometric, Medium, Medium, Medium, &["moire"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "starfield-hyperdrive",
         "Starfield: Hyperdrive",
         Starfield { depth: 1.6 },
         Prism,
         Feedback::tunnel(0.92, 0.016, 1.05),
+        PresetMeta::new(Particle, High, High, Low, &["stars", "speed"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "metaballs-neon-lava",
         "Metaballs: Neon Lava",
         Metaballs { blobs: 8 },
         Fire,
         Feedback::tunnel(0.93, 0.020, 1.15),
+        PresetMeta::new(Organic, Low, Low, Low, &["blobs", "lava"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "voronoi-crystal-lattice",
         "Voronoi: Crystal Lattice",
         Voronoi { points: 10 },
         Aurora,
         Feedback::tunnel(0.90, 0.026, 1.35),
+        PresetMeta::new(Geometric, Medium, Low, Low, &["cells", "crystal"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "plasma-aurora-storm",
         "Plasma: Aurora Storm",
         Plasma { freq: 3.6 },
         Aurora,
         Feedback::tunnel(0.94, 0.020, 1.25),
+        PresetMeta::new(Organic, Medium, Medium, Low, &["plasma", "aurora"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "vortex-warpdrive",
         "Vortex: Warpdrive",
         Vortex { spin: 2.0 },
         Acid,
         Feedback::tunnel(0.93, 0.020, 1.30),
+        PresetMeta::new(Geometric, High, High, Low, &["vortex", "speed"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "stripes-hypersync",
         "Stripes: Hypersync",
         Stripes { freq: 32.0 },
         Cosmic,
         Feedback::tunnel(0.90, 0.020, 1.20),
+        PresetMeta::new(Geometric, High, High, High, &["stripes", "strobe"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "glitch-blocks-datamosh-drift",
         "Glitch Blocks: DataMosh Drift",
         Glitch { block: 6.0 },
         Neon,
         Feedback::tunnel(0.86, 0.020, 1.05),
+        PresetMeta::new(Retro, Medium, Medium, High, &["glitch", "datamosh"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "noise-ribbons-treble-drift",
         "Noise Ribbons: Treble Drift",
         Noise { freq: 5.2 },
         Cosmic,
         Feedback::tunnel(0.92, 0.018, 1.18),
+        PresetMeta::new(Organic, Low, Medium, Low, &["noise", "treble"]),
     )));
 
     // v1.2: deep fractal zoom pack (continuous dive + orbit drift).
     v.push(Box::new(FieldPreset::new(
+        "mandelbrot-infinite-dive",
         "Mandelbrot: Infinite Dive",
         MandelDeep {
             center: (-0.7436439, 0.13182591),
@@ -287,8 +582,10 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Prism,
         Feedback::tunnel(0.90, 0.018, 1.30),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["zoom", "deep"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "mandelbrot-seahorse-zoom",
         "Mandelbrot: Seahorse Zoom",
         MandelDeep {
             center: (-0.7453, 0.1127),
@@ -297,8 +594,10 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Cosmic,
         Feedback::tunnel(0.91, 0.018, 1.28),
+        PresetMeta::new(Fractal, Low, Medium, Low, &["zoom", "deep"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "mandelbrot-spiral-probe",
         "Mandelbrot: Spiral Probe",
         MandelDeep {
             center: (-0.761_574, -0.084_759_6),
@@ -307,8 +606,10 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Neon,
         Feedback::tunnel(0.90, 0.020, 1.32),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["zoom", "deep"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "julia-infinite-bloom",
         "Julia: Infinite Bloom",
         JuliaDeep {
             c_base: (-0.745, 0.186),
@@ -316,8 +617,10 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Acid,
         Feedback::tunnel(0.91, 0.020, 1.26),
+        PresetMeta::new(Fractal, Low, Medium, Low, &["julia", "deep"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "julia-cathedral-zoom",
         "Julia: Cathedral Zoom",
         JuliaDeep {
             c_base: (-0.391, -0.587),
@@ -325,8 +628,10 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Aurora,
         Feedback::tunnel(0.92, 0.018, 1.24),
+        PresetMeta::new(Fractal, Low, Medium, Low, &["julia", "deep"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "burning-ship-abyss-dive",
         "Burning Ship: Abyss Dive",
         BurningShipDeep {
             center: (-1.7443, -0.0173),
@@ -334,28 +639,36 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Fire,
         Feedback::tunnel(0.90, 0.022, 1.30),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["zoom", "deep"]),
     )));
 
     // v1.3: research pack (reaction-diffusion / fluid / flame / sphere-trace inspired).
     v.push(Box::new(FieldPreset::new(
+        "reaction-diffusion-psychedelic-bloom",
         "Reaction-Diffusion: Psychedelic Bloom",
         Smoke { blur: 0.62 },
         Acid,
         Feedback::tunnel(0.97, 0.012, 0.72),
+        PresetMeta::new(Organic, Medium, Low, Low, &["smoke", "psychedelic"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "fluid-vorticity-bass-storm",
         "Fluid Vorticity: Bass Storm",
         Flow { freq: 2.8 },
         Aurora,
         Feedback::tunnel(0.95, 0.015, 0.85),
+        PresetMeta::new(Organic, High, High, Low, &["flow", "bass"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "fractal-flame-ifs-cathedral",
         "Fractal Flame: IFS Cathedral",
         Warp { freq: 3.2 },
         Fire,
         Feedback::tunnel(0.92, 0.020, 1.18),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["flame", "ifs"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "mandelbulb-slice-neon-relic",
         "Mandelbulb Slice: Neon Relic",
         OrbitTrap {
             center: (-0.42, 0.57),
@@ -363,44 +676,58 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Neon,
         Feedback::tunnel(0.91, 0.020, 1.22),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["mandelbulb", "neon"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "sphere-trace-gyroid-temple",
         "Sphere Trace: Gyroid Temple",
         Orbs { freq: 3.1 },
         Cosmic,
         Feedback::tunnel(0.92, 0.018, 1.08),
+        PresetMeta::new(Fractal, Low, Low, Low, &["sdf", "temple"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "curl-noise-plasma-veins",
         "Curl Noise: Plasma Veins",
         Noise { freq: 6.0 },
         Prism,
         Feedback::tunnel(0.93, 0.018, 1.10),
+        PresetMeta::new(Organic, Medium, Medium, Low, &["noise", "plasma"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "perlin-warp-liquid-aurora",
         "Perlin Warp: Liquid Aurora",
         Plasma { freq: 4.6 },
         Aurora,
         Feedback::tunnel(0.94, 0.018, 1.16),
+        PresetMeta::new(Organic, Low, Low, Low, &["plasma", "aurora"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "ifs-attractor-ribbon-knot",
         "IFS Attractor: Ribbon Knot",
         Clifford,
         Acid,
         Feedback::tunnel(0.91, 0.020, 1.06),
+        PresetMeta::new(Attractor, Medium, Medium, Low, &["attractor", "ifs"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "fractal-morph-wormhole-garden",
         "Fractal Morph: Wormhole Garden",
         Kaleido { freq: 5.0, symmetry: 13 },
         Cosmic,
         Feedback::tunnel(0.92, 0.020, 1.30),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["kaleidoscope", "wormhole"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "sdf-fractal-cosmic-monolith",
         "SDF Fractal: Cosmic Monolith",
         Orbs { freq: 4.0 },
         Neon,
         Feedback::tunnel(0.90, 0.022, 1.15),
+        PresetMeta::new(Fractal, Medium, Low, Low, &["sdf", "cosmic"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "hopalong-attractor-neon-dust",
         "Hopalong Attractor: Neon Dust",
         Hopalong {
             a: 1.42,
@@ -409,41 +736,119 @@ pub fn make_presets() -> Vec<Box<dyn Preset>> {
         },
         Neon,
         Feedback::tunnel(0.92, 0.020, 1.16),
+        PresetMeta::new(Attractor, Medium, Medium, Low, &["attractor", "dust"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "ikeda-loop-bass-cyclone",
         "Ikeda Loop: Bass Cyclone",
         Ikeda { u: 0.89 },
         Cosmic,
         Feedback::tunnel(0.91, 0.021, 1.14),
+        PresetMeta::new(Attractor, High, High, Low, &["attractor", "bass"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "hex-tunnel-lattice-drive",
         "Hex Tunnel: Lattice Drive",
         HexTunnel { freq: 6.2 },
         Prism,
         Feedback::tunnel(0.90, 0.020, 1.22),
+        PresetMeta::new(Geometric, High, High, Low, &["tunnel", "hex"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "gyroid-slice-reactor-core",
         "Gyroid Slice: Reactor Core",
         Gyroid { freq: 4.0 },
         Aurora,
         Feedback::tunnel(0.92, 0.018, 1.08),
+        PresetMeta::new(Geometric, Medium, Medium, Low, &["gyroid"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "phyllotaxis-bloom-harmonic-seeds",
         "Phyllotaxis Bloom: Harmonic Seeds",
         Phyllotaxis { petals: 96 },
         Fire,
         Feedback::tunnel(0.92, 0.020, 1.10),
+        PresetMeta::new(Geometric, Low, Low, Low, &["spiral", "harmony"]),
     )));
     v.push(Box::new(FieldPreset::new(
+        "nova-fractal-root-resonance",
         "Nova Fractal: Root Resonance",
         Nova { c: (-0.42, 0.23) },
         Acid,
         Feedback::tunnel(0.91, 0.020, 1.18),
+        PresetMeta::new(Fractal, Medium, Medium, Low, &["nova", "harmony"]),
+    )));
+
+    v.push(Box::new(ScopePreset::new(
+        "spectrum-bars-classic-analyzer",
+        "Spectrum Bars: Classic Analyzer",
+        ScopeKind::Bars,
+        Prism,
+        Feedback::none(),
+        PresetMeta::new(Scope, Medium, Medium, Low, &["spectrum", "bars"]),
+    )));
+    v.push(Box::new(ScopePreset::new(
+        "spectrum-mirror-center-split",
+        "Spectrum Mirror: Center Split",
+        ScopeKind::MirrorBars,
+        Neon,
+        Feedback::tunnel(0.62, 0.004, 1.04),
+        PresetMeta::new(Scope, Medium, Medium, Low, &["spectrum", "bars"]),
+    )));
+    v.push(Box::new(ScopePreset::new(
+        "spectrum-halo-radial-bars",
+        "Spectrum Halo: Radial Bars",
+        ScopeKind::RadialBars,
+        Cosmic,
+        Feedback::tunnel(0.70, 0.006, 1.06),
+        PresetMeta::new(Scope, Medium, Medium, Low, &["spectrum", "radial"]),
+    )));
+    v.push(Box::new(ScopePreset::new(
+        "oscilloscope-waveform-trace",
+        "Oscilloscope: Waveform Trace",
+        ScopeKind::Trace,
+        Aurora,
+        Feedback::tunnel(0.55, 0.002, 1.0),
+        PresetMeta::new(Scope, Low, Medium, Low, &["waveform"]),
+    )));
+    v.push(Box::new(ScopePreset::new(
+        "waveform-ring-polar-scope",
+        "Waveform Ring: Polar Scope",
+        ScopeKind::Ring,
+        Acid,
+        Feedback::tunnel(0.78, 0.008, 1.08),
+        PresetMeta::new(Scope, Low, Medium, Low, &["waveform", "radial"]),
+    )));
+    v.push(Box::new(ScopePreset::new(
+        "lissajous-scope-phase-portrait",
+        "Lissajous Scope: Phase Portrait",
+        ScopeKind::Lissajous,
+        Fire,
+        Feedback::tunnel(0.74, 0.004, 1.03),
+        PresetMeta::new(Scope, Low, Medium, Low, &["waveform", "stereo"]),
     )));
 
     v
 }
 
+/// Built-in presets followed by one `FieldPreset` per user definition. Definitions
+/// that don't resolve (see `check_definition`) are left out.
+pub fn make_presets_with(defs: &[PresetDefinition]) -> Vec<Box<dyn Preset>> {
+    let mut v = make_presets();
+    for def in defs {
+        if let Ok(preset) = FieldPreset::from_definition(def) {
+            v.push(Box::new(preset));
+        }
+    }
+    v
+}
+
+/// Resolves a definition's algorithm, parameters and palette against the
+/// built-in field renderer, describing the first thing that doesn't exist.
+pub fn check_definition(def: &PresetDefinition) -> Result<(), String> {
+    ResolvedDefinition::resolve(def).map(|_| ())
+}
+
 #[derive(Clone, Copy)]
 enum Palette {
     Prism,
@@ -494,6 +899,261 @@ enum Algo {
     Nova { c: (f32, f32) },
 }
 
+impl Palette {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "prism" => Self::Prism,
+            "acid" => Self::Acid,
+            "neon" => Self::Neon,
+            "fire" => Self::Fire,
+            "aurora" => Self::Aurora,
+            "cosmic" => Self::Cosmic,
+            _ => return None,
+        })
+    }
+}
+
+impl Algo {
+    /// Category a user preset gets when its file doesn't set `category=`.
+    fn category(&self) -> PresetCategory {
+        use Algo::*;
+        match self {
+            Mandelbrot { .. }
+            | MandelDeep { .. }
+            | BurningShip { .. }
+            | BurningShipDeep { .. }
+            | OrbitTrap { .. }
+            | Julia { .. }
+            | JuliaDeep { .. }
+            | Nova { .. } => PresetCategory::Fractal,
+            Clifford | DeJong | Hopalong { .. } | Ikeda { .. } => PresetCategory::Attractor,
+            Plasma { .. }
+            | Warp { .. }
+            | Metaballs { .. }
+            | Flow { .. }
+            | Smoke { .. }
+            | Noise { .. } => PresetCategory::Organic,
+            Sparks { .. } | Starfield { .. } => PresetCategory::Particle,
+            Cells { .. } | Glitch { .. } | Crt { .. } => PresetCategory::Retro,
+            _ => PresetCategory::Geometric,
+        }
+    }
+
+    /// The algorithm a `.preset` file names with `algo=`, with the same
+    /// parameters as its first built-in use.
+    fn from_file_name(name: &str) -> Option<Self> {
+        use Algo::*;
+        Some(match name {
+            "mandelbrot" => Mandelbrot {
+                center: (-0.743_643_9, 0.131_825_91),
+            },
+            "mandel_deep" => MandelDeep {
+                center: (-0.743_643_9, 0.131_825_91),
+                orbit: (0.34, 0.28),
+                speed: 0.92,
+            },
+            "burning_ship" => BurningShip { center: (-0.45, -0.02) },
+            "burning_ship_deep" => BurningShipDeep {
+                center: (-1.7443, -0.0173),
+                speed: 0.78,
+            },
+            "orbit_trap" => OrbitTrap {
+                center: (-0.38, 0.58),
+                trap: (0.15, 0.05),
+            },
+            "julia" => Julia { c_base: (-0.8, 0.156) },
+            "julia_deep" => JuliaDeep {
+                c_base: (-0.745, 0.186),
+                speed: 0.88,
+            },
+            "clifford" => Clifford,
+            "de_jong" => DeJong,
+            "plasma" => Plasma { freq: 2.4 },
+            "warp" => Warp { freq: 2.9 },
+            "polar_moire" => PolarMoire { freq: 1.0 },
+            "kaleido" => Kaleido { freq: 2.7, symmetry: 7 },
+            "stripes" => Stripes { freq: 14.0 },
+            "voronoi" => Voronoi { points: 6 },
+            "metaballs" => Metaballs { blobs: 5 },
+            "sparks" => Sparks { density: 1.0 },
+            "starfield" => Starfield { depth: 1.0 },
+            "flow" => Flow { freq: 1.6 },
+            "rings" => Rings { freq: 6.5 },
+            "vortex" => Vortex { spin: 1.2 },
+            "smoke" => Smoke { blur: 0.55 },
+            "cells" => Cells { scale: 8.0 },
+            "glitch" => Glitch { block: 10.0 },
+            "noise" => Noise { freq: 3.0 },
+            "truchet" => Truchet { tiles: 8.0 },
+            "orbs" => Orbs { freq: 2.4 },
+            "chladni" => Chladni { a: 2.0, b: 2.0 },
+            "crt" => Crt { freq: 320.0 },
+            "moire" => Moire { freq: 18.0 },
+            "hopalong" => Hopalong {
+                a: 1.42,
+                b: -2.19,
+                c: 0.73,
+            },
+            "ikeda" => Ikeda { u: 0.89 },
+            "hex_tunnel" => HexTunnel { freq: 6.2 },
+            "gyroid" => Gyroid { freq: 4.0 },
+            "phyllotaxis" => Phyllotaxis { petals: 96 },
+            "nova" => Nova { c: (-0.42, 0.23) },
+            _ => return None,
+        })
+    }
+
+    /// Overrides one `algo.<param>` value. Pairs take two numbers, counts are
+    /// rounded and must be at least 1.
+    fn set_file_param(&mut self, param: &str, values: &[f32]) -> Result<(), String> {
+        use Algo::*;
+        let scalar = || match values {
+            [v] => Ok(*v),
+            _ => Err(format!("algo.{param} takes one number")),
+        };
+        let pair = || match values {
+            [x, y] => Ok((*x, *y)),
+            _ => Err(format!("algo.{param} takes two numbers (x,y)")),
+        };
+        let count = || match values {
+            [v] if *v >= 1.0 => Ok(v.round() as u32),
+            _ => Err(format!("algo.{param} takes one whole number >= 1")),
+        };
+        match (self, param) {
+            (Mandelbrot { center }, "center")
+            | (MandelDeep { center, .. }, "center")
+            | (BurningShip { center }, "center")
+            | (BurningShipDeep { center, .. }, "center")
+            | (OrbitTrap { center, .. }, "center") => *center = pair()?,
+            (MandelDeep { orbit, .. }, "orbit") => *orbit = pair()?,
+            (OrbitTrap { trap, .. }, "trap") => *trap = pair()?,
+            (Julia { c_base }, "c_base") | (JuliaDeep { c_base, .. }, "c_base") => *c_base = pair()?,
+            (Nova { c }, "c") => *c = pair()?,
+            (MandelDeep { speed, .. }, "speed")
+            | (BurningShipDeep { speed, .. }, "speed")
+            | (JuliaDeep { speed, .. }, "speed") => *speed = scalar()?,
+            (Plasma { freq }, "freq")
+            | (Warp { freq }, "freq")
+            | (PolarMoire { freq }, "freq")
+            | (Kaleido { freq, .. }, "freq")
+            | (Stripes { freq }, "freq")
+            | (Flow { freq }, "freq")
+            | (Rings { freq }, "freq")
+            | (Noise { freq }, "freq")
+            | (Orbs { freq }, "freq")
+            | (Crt { freq }, "freq")
+            | (Moire { freq }, "freq")
+            | (HexTunnel { freq }, "freq")
+            | (Gyroid { freq }, "freq") => *freq = scalar()?,
+            (Kaleido { symmetry, .. }, "symmetry") => *symmetry = count()?,
+            (Voronoi { points }, "points") => *points = count()?,
+            (Metaballs { blobs }, "blobs") => *blobs = count()?,
+            (Phyllotaxis { petals }, "petals") => *petals = count()?,
+            (Sparks { density }, "density") => *density = scalar()?,
+            (Starfield { depth }, "depth") => *depth = scalar()?,
+            (Vortex { spin }, "spin") => *spin = scalar()?,
+            (Smoke { blur }, "blur") => *blur = scalar()?,
+            (Cells { scale }, "scale") => *scale = scalar()?,
+            (Glitch { block }, "block") => *block = scalar()?,
+            (Truchet { tiles }, "tiles") => *tiles = scalar()?,
+            (Chladni { a, .. }, "a") | (Hopalong { a, .. }, "a") => *a = scalar()?,
+            (Chladni { b, .. }, "b") | (Hopalong { b, .. }, "b") => *b = scalar()?,
+            (Hopalong { c, .. }, "c") => *c = scalar()?,
+            (Ikeda { u }, "u") => *u = scalar()?,
+            _ => return Err(format!("algorithm has no parameter '{param}'")),
+        }
+        Ok(())
+    }
+
+    /// The scalar and whole-number parameters with their live ranges. Pairs
+    /// such as `center` only come from `.preset` files.
+    fn param_slots(&mut self) -> Vec<(&'static str, ParamSlot<'_>, f32, f32)> {
+        use Algo::*;
+        use ParamSlot::{Count, Float};
+        match self {
+            MandelDeep { speed, .. } | BurningShipDeep { speed, .. } | JuliaDeep { speed, .. } => {
+                vec![("speed", Float(speed), 0.1, 3.0)]
+            }
+            Kaleido { freq, symmetry } => {
+                vec![("freq", Float(freq), 0.2, 12.0), ("symmetry", Count(symmetry), 2.0, 16.0)]
+            }
+            Plasma { freq }
+            | Warp { freq }
+            | PolarMoire { freq }
+            | Flow { freq }
+            | Noise { freq }
+            | Orbs { freq }
+            | Gyroid { freq } => vec![("freq", Float(freq), 0.2, 12.0)],
+            Stripes { freq } | Rings { freq } | Moire { freq } | HexTunnel { freq } => {
+                vec![("freq", Float(freq), 1.0, 48.0)]
+            }
+            Crt { freq } => vec![("freq", Float(freq), 40.0, 800.0)],
+            Voronoi { points } => vec![("points", Count(points), 2.0, 12.0)],
+            Metaballs { blobs } => vec![("blobs", Count(blobs), 2.0, 10.0)],
+            Phyllotaxis { petals } => vec![("petals", Count(petals), 24.0, 180.0)],
+            Sparks { density } => vec![("density", Float(density), 0.1, 3.0)],
+            Starfield { depth } => vec![("depth", Float(depth), 0.2, 4.0)],
+            Vortex { spin } => vec![("spin", Float(spin), -4.0, 4.0)],
+            Smoke { blur } => vec![("blur", Float(blur), 0.0, 1.0)],
+            Cells { scale } => vec![("scale", Float(scale), 1.0, 32.0)],
+            Glitch { block } => vec![("block", Float(block), 2.0, 32.0)],
+            Truchet { tiles } => vec![("tiles", Float(tiles), 1.0, 32.0)],
+            Chladni { a, b } => vec![("a", Float(a), 1.0, 8.0), ("b", Float(b), 1.0, 8.0)],
+            Hopalong { a, b, c } => vec![
+                ("a", Float(a), -3.0, 3.0),
+                ("b", Float(b), -3.0, 3.0),
+                ("c", Float(c), -3.0, 3.0),
+            ],
+            Ikeda { u } => vec![("u", Float(u), 0.6, 0.95)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn params(&self) -> Vec<PresetParam> {
+        let mut algo = *self;
+        algo.param_slots()
+            .into_iter()
+            .map(|(name, slot, min, max)| PresetParam {
+                name,
+                value: slot.get(),
+                min,
+                max,
+            })
+            .collect()
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match self.param_slots().into_iter().find(|(n, ..)| *n == name) {
+            Some((_, slot, min, max)) => {
+                slot.set(value.clamp(min, max));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+enum ParamSlot<'a> {
+    Float(&'a mut f32),
+    Count(&'a mut u32),
+}
+
+impl ParamSlot<'_> {
+    fn get(&self) -> f32 {
+        match self {
+            Self::Float(v) => **v,
+            Self::Count(v) => **v as f32,
+        }
+    }
+
+    fn set(self, value: f32) {
+        match self {
+            Self::Float(v) => *v = value,
+            Self::Count(v) => *v = value.round() as u32,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 struct Feedback {
     fade: f32,
@@ -523,28 +1183,195 @@ impl Feedback {
             strength: 1.0,
         }
     }
+
+    /// Appends the tunnel settings as `feedback.*` parameters; presets drawn
+    /// from scratch have none.
+    fn push_params(&self, out: &mut Vec<PresetParam>) {
+        if self.strength <= 0.0 {
+            return;
+        }
+        for (name, value, min, max) in [
+            ("feedback.fade", self.fade, 0.0, 1.0),
+            ("feedback.warp", self.warp_amp, 0.0, 0.2),
+            ("feedback.warp_freq", self.warp_freq, 0.0, 8.0),
+            ("feedback.zoom", self.zoom, 0.2, 4.0),
+        ] {
+            out.push(PresetParam { name, value, min, max });
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        if self.strength <= 0.0 {
+            return false;
+        }
+        match name {
+            "feedback.fade" => self.fade = value.clamp(0.0, 1.0),
+            "feedback.warp" => self.warp_amp = value.clamp(0.0, 0.2),
+            "feedback.warp_freq" => self.warp_freq = value.clamp(0.0, 8.0),
+            "feedback.zoom" => self.zoom = value.clamp(0.2, 4.0),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Samples the previous frame through the rotating, warped tunnel transform.
+    fn base(
+        &self,
+        ctx: &RenderCtx,
+        prev: &[u8],
+        (sx, sy): (f32, f32),
+        route: &RouteMap,
+        zoom_mod: f32,
+        seed: u32,
+    ) -> [u8; 3] {
+        if self.strength <= 0.0 || prev.is_empty() {
+            return [0, 0, 0];
+        }
+        let (w, h, t) = (ctx.w.max(1), ctx.h.max(1), ctx.t);
+        let (mid, treb, beat_pulse) = (route.mid, route.treb, route.beat);
+        let ang = t * (0.5 + mid * 1.2);
+        let ca = ang.cos();
+        let sa = ang.sin();
+        let rx = sx * ca - sy * sa;
+        let ry = sx * sa + sy * ca;
+
+        let wamp = self.warp_amp * (0.4 + treb * 1.8 + beat_pulse * 1.2);
+        let dx = (rx * self.warp_freq + t * 1.7).sin() + hash_noise(rx * 3.0, ry * 3.0, seed).sin() * 0.6;
+        let dy = (ry * self.warp_freq - t * 1.3).cos()
+            + hash_noise(rx * 2.0, ry * 2.0, seed ^ 0x9E37_79B9).cos() * 0.6;
+
+        let z = (self.zoom * zoom_mod).max(0.2);
+        let u = rx / z + dx * wamp;
+        let v = ry / z + dy * wamp;
+
+        let mut base = sample_rgb(prev, w, h, u, v);
+        base[0] = (base[0] as f32 * self.fade) as u8;
+        base[1] = (base[1] as f32 * self.fade) as u8;
+        base[2] = (base[2] as f32 * self.fade) as u8;
+        base
+    }
+}
+
+/// A `.preset` definition checked against the field renderer, before anything
+/// is allocated for it.
+struct ResolvedDefinition {
+    algo: Algo,
+    palette: Palette,
+    fb: Feedback,
+    category: PresetCategory,
+    energy: Level,
+    motion: Level,
+    flash_risk: Level,
+}
+
+impl ResolvedDefinition {
+    fn resolve(def: &PresetDefinition) -> Result<Self, String> {
+        let mut algo =
+            Algo::from_file_name(&def.algo).ok_or_else(|| format!("unknown algo '{}'", def.algo))?;
+        for (param, values) in &def.params {
+            algo.set_file_param(param, values)?;
+        }
+        let palette =
+            Palette::from_name(&def.palette).ok_or_else(|| format!("unknown palette '{}'", def.palette))?;
+        let spec = def.feedback;
+        let fb = if spec.enabled {
+            Feedback {
+                warp_freq: spec.warp_freq,
+                ..Feedback::tunnel(spec.fade, spec.warp, spec.zoom)
+            }
+        } else {
+            Feedback::none()
+        };
+        let spec = &def.meta;
+        let level = |field: &str, value: &Option<String>| match value {
+            Some(v) => Level::from_label(v).ok_or_else(|| format!("unknown {field} level '{v}'")),
+            None => Ok(Level::Medium),
+        };
+        let category = match &spec.category {
+            Some(c) => PresetCategory::from_label(c).ok_or_else(|| format!("unknown category '{c}'"))?,
+            None => algo.category(),
+        };
+        let energy = level("energy", &spec.energy)?;
+        let motion = level("motion", &spec.motion)?;
+        let flash_risk = match &spec.flash_risk {
+            Some(_) => level("flash_risk", &spec.flash_risk)?,
+            None => Level::Low,
+        };
+        Ok(Self {
+            algo,
+            palette,
+            fb,
+            category,
+            energy,
+            motion,
+            flash_risk,
+        })
+    }
 }
 
 pub struct FieldPreset {
+    /// Frozen id of a built-in; `.preset` files use the slug of their name.
+    id: Option<&'static str>,
     name: &'static str,
+    meta: PresetMeta,
     algo: Algo,
     palette: Palette,
     fb: Feedback,
+    post_fx: PostFxSpec,
     seed: u32,
     post_fx_scratch: Vec<u8>,
+    /// Built from a `.preset` file; the Metal shaders only know the built-ins.
+    from_file: bool,
 }
 
 impl FieldPreset {
-    fn new(name: &'static str, algo: Algo, palette: Palette, fb: Feedback) -> Self {
+    fn new(
+        id: &'static str,
+        name: &'static str,
+        algo: Algo,
+        palette: Palette,
+        fb: Feedback,
+        meta: PresetMeta,
+    ) -> Self {
         Self {
+            id: Some(id),
             name,
+            meta,
             algo,
             palette,
             fb,
+            post_fx: PostFxSpec::default(),
             seed: fastrand::u32(..),
             post_fx_scratch: Vec::new(),
+            from_file: false,
         }
     }
+
+    fn from_definition(def: &PresetDefinition) -> Result<Self, String> {
+        let resolved = ResolvedDefinition::resolve(def)?;
+        // Preset names and tags are `&'static` everywhere; user presets are
+        // built once per engine, so leaking them is cheap.
+        let name: &'static str = Box::leak(def.name.clone().into_boxed_str());
+        let tags = def
+            .meta
+            .tags
+            .iter()
+            .map(|t| &*Box::leak(t.clone().into_boxed_str()))
+            .collect::<Vec<&'static str>>();
+        let meta = PresetMeta::new(
+            resolved.category,
+            resolved.energy,
+            resolved.motion,
+            resolved.flash_risk,
+            Box::leak(tags.into_boxed_slice()),
+        );
+        let mut preset = Self::new("", name, resolved.algo, resolved.palette, resolved.fb, meta);
+        // No frozen id: `id()` falls back to the slug of the file's name.
+        preset.id = None;
+        preset.post_fx = def.post_fx;
+        preset.from_file = true;
+        Ok(preset)
+    }
 }
 
 impl Preset for FieldPreset {
@@ -552,6 +1379,28 @@ impl Preset for FieldPreset {
         self.name
     }
 
+    fn id(&self) -> String {
+        self.id.map_or_else(|| preset_slug(self.name), str::to_string)
+    }
+
+    fn meta(&self) -> PresetMeta {
+        self.meta
+    }
+
+    fn params(&self) -> Vec<PresetParam> {
+        let mut out = self.algo.params();
+        self.fb.push_params(&mut out);
+        out
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        self.algo.set_param(name, value) || self.fb.set_param(name, value)
+    }
+
+    fn has_metal_shader(&self) -> bool {
+        !self.from_file
+    }
+
     fn render(&mut self, ctx: &RenderCtx, prev: &[u8], out: &mut [u8]) {
         let w = ctx.w.max(1);
         let h = ctx.h.max(1);
@@ -568,16 +1417,20 @@ impl Preset for FieldPreset {
         let onset = route.onset;
         let energy = route.energy;
         let beat_pulse = route.beat;
+        // Fractal zooms surge on kicks; sparks and glitch blocks flicker on hats.
+        let zoom_hit = onset.max(route.kick);
+        let hat = route.hat;
 
         let zoom_mod = (1.0 - bass * 0.12 * route.zoom - beat_pulse * 0.08).clamp(0.25, 1.4);
         let t = ctx.t;
+        let harmony = harmony_hue(&ctx.audio);
 
         // Fill in blocks to allow adaptive downscale without a second buffer.
         for by in (0..h).step_by(scale) {
             for bx in (0..w).step_by(scale) {
                 let x = bx as f32 / w as f32;
                 let y = by as f32 / h as f32;
-                let nx = x * 2.0 - 1.0;
+                let nx = (x * 2.0 - 1.0 - route.pan * 0.18) / (1.0 + route.width * 0.3);
                 let ny = y * 2.0 - 1.0;
 
                 let (sx, sy) = match self.algo {
@@ -586,29 +1439,7 @@ impl Preset for FieldPreset {
                 };
 
                 // Feedback base layer (warp previous frame into a tunnel).
-                let mut base = [0u8, 0u8, 0u8];
-                if self.fb.strength > 0.0 && !prev.is_empty() {
-                    let ang = t * (0.5 + mid * 1.2);
-                    let ca = ang.cos();
-                    let sa = ang.sin();
-                    let rx = sx * ca - sy * sa;
-                    let ry = sx * sa + sy * ca;
-
-                    let wamp = self.fb.warp_amp * (0.4 + treb * 1.8 + beat_pulse * 1.2);
-                    let dx = (rx * self.fb.warp_freq + t * 1.7).sin()
-                        + hash_noise(rx * 3.0, ry * 3.0, self.seed).sin() * 0.6;
-                    let dy = (ry * self.fb.warp_freq - t * 1.3).cos()
-                        + hash_noise(rx * 2.0, ry * 2.0, self.seed ^ 0x9E37_79B9).cos() * 0.6;
-
-                    let z = (self.fb.zoom * zoom_mod).max(0.2);
-                    let u = rx / z + dx * wamp;
-                    let v = ry / z + dy * wamp;
-
-                    base = sample_rgb(prev, w, h, u, v);
-                    base[0] = (base[0] as f32 * self.fb.fade) as u8;
-                    base[1] = (base[1] as f32 * self.fb.fade) as u8;
-                    base[2] = (base[2] as f32 * self.fb.fade) as u8;
-                }
+                let base = self.fb.base(ctx, prev, (sx, sy), &route, zoom_mod, self.seed);
 
                 // Main field value (0..1)
                 let mut val = match self.algo {
@@ -616,7 +1447,7 @@ impl Preset for FieldPreset {
                         let (fx, fy) =
                             fractal_motion_xy(sx, sy, t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
                         let fz =
-                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
+                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, zoom_hit);
                         let zoom = 1.7 * zoom_mod * fz;
                         let drift = (0.10 + 0.25 * bass + 0.10 * beat_pulse)
                             / zoom.max(1.0)
@@ -656,7 +1487,7 @@ impl Preset for FieldPreset {
                         let (fx, fy) =
                             fractal_motion_xy(sx, sy, t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
                         let fz =
-                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
+                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, zoom_hit);
                         let zoom = 1.55 * zoom_mod * fz;
                         fractal_burning_ship(
                             fx,
@@ -692,7 +1523,7 @@ impl Preset for FieldPreset {
                         let (fx, fy) =
                             fractal_motion_xy(sx, sy, t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
                         let fz =
-                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
+                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, zoom_hit);
                         let zoom = 1.55 * zoom_mod * fz;
                         fractal_orbit_trap(
                             fx,
@@ -710,7 +1541,7 @@ impl Preset for FieldPreset {
                         let (fx, fy) =
                             fractal_motion_xy(sx, sy, t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
                         let fz =
-                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, onset);
+                            fractal_zoom_motion(t, ctx.fractal_zoom_mul, bass, mid, treb, beat_pulse, zoom_hit);
                         let zoom = 1.35 * zoom_mod * fz;
                         let cx = c_base.0 + 0.16 * (t * (0.17 + treb)).cos() + mid * 0.05;
                         let cy = c_base.1 + 0.14 * (t * (0.19 + bass)).sin() - treb * 0.04;
@@ -745,14 +1576,14 @@ impl Preset for FieldPreset {
                     Algo::Stripes { freq } => stripes(sx, sy, t, freq, beat_pulse),
                     Algo::Voronoi { points } => voronoiish(sx, sy, t, points, self.seed),
                     Algo::Metaballs { blobs } => metaballs(sx, sy, t, blobs, self.seed),
-                    Algo::Sparks { density } => sparks(sx, sy, t, density, treb, beat_pulse, self.seed),
+                    Algo::Sparks { density } => sparks(sx, sy, t, density, treb.max(hat), beat_pulse, self.seed),
                     Algo::Starfield { depth } => starfield(sx, sy, t, bass, depth, self.seed),
                     Algo::Flow { freq } => flow(sx, sy, t, freq, mid, self.seed),
                     Algo::Rings { freq } => rings(sx, sy, t, freq, bass, beat_pulse),
                     Algo::Vortex { spin } => vortex(sx, sy, t, spin, energy, bass, treb),
                     Algo::Smoke { blur } => smoke(prev, w, h, bx, by, blur),
                     Algo::Cells { scale } => cells(sx, sy, t, scale, beat_pulse, self.seed),
-                    Algo::Glitch { block } => glitch(bx, by, w, h, t, block, ctx.audio.onset, self.seed),
+                    Algo::Glitch { block } => glitch(bx, by, w, h, t, block, ctx.audio.onset.max(hat), self.seed),
                     Algo::Noise { freq } => noise(sx, sy, t, freq, self.seed),
                     Algo::Truchet { tiles } => truchet(sx, sy, t, tiles, bass, treb, self.seed),
                     Algo::Orbs { freq } => orbs(sx, sy, t, freq, bass, beat_pulse),
@@ -774,7 +1605,7 @@ impl Preset for FieldPreset {
                 // Extra "psychedelic pop": beat injects energy into the field.
                 val = (val + beat_pulse * 0.35 + treb * 0.18).fract();
 
-                let ink = palette(self.palette, val, t, bass, mid, treb, beat_pulse);
+                let ink = palette(self.palette, val, t, [bass, mid, treb], beat_pulse, harmony);
 
                 let ink_alpha = (0.55 + energy * 0.35 + beat_pulse * 0.35).clamp(0.2, 0.95);
                 let r = (base[0] as f32 * (1.0 - ink_alpha) + ink[0] as f32 * ink_alpha) as u8;
@@ -798,10 +1629,323 @@ impl Preset for FieldPreset {
             }
         }
 
+        let route = route.scale_post_fx(&self.post_fx);
         apply_post_fx(out, w, h, t, route, ctx.quality, &mut self.post_fx_scratch);
     }
 }
 
+/// Which audio display a `ScopePreset` draws.
+#[derive(Clone, Copy)]
+enum ScopeKind {
+    Bars,
+    MirrorBars,
+    RadialBars,
+    Trace,
+    Ring,
+    Lissajous,
+}
+
+/// Direct spectrum/waveform displays. They share palettes, feedback trails and post-FX
+/// with `FieldPreset`, so they sit in playlists and transitions like any other preset.
+pub struct ScopePreset {
+    id: &'static str,
+    name: &'static str,
+    meta: PresetMeta,
+    kind: ScopeKind,
+    palette: Palette,
+    fb: Feedback,
+    seed: u32,
+    peaks: Vec<f32>,
+    plot: Vec<f32>,
+    post_fx_scratch: Vec<u8>,
+}
+
+impl ScopePreset {
+    fn new(
+        id: &'static str,
+        name: &'static str,
+        kind: ScopeKind,
+        palette: Palette,
+        fb: Feedback,
+        meta: PresetMeta,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            meta,
+            kind,
+            palette,
+            fb,
+            seed: fastrand::u32(..),
+            peaks: Vec::new(),
+            plot: Vec::new(),
+            post_fx_scratch: Vec::new(),
+        }
+    }
+
+    /// Peak-hold caps: jump to new maxima, then fall at a fixed rate.
+    fn update_peaks(&mut self, spectrum: &[f32], dt: f32) {
+        self.peaks.resize(spectrum.len(), 0.0);
+        let fall = dt.clamp(0.0, 0.1) * 0.35;
+        for (p, &v) in self.peaks.iter_mut().zip(spectrum) {
+            *p = if v >= *p { v } else { (*p - fall).max(v) };
+        }
+    }
+
+    /// Rasterises a goniometer: side against mid for stereo input, or for mono the
+    /// waveform against a quarter-period delayed copy of itself, rotated 45 degrees.
+    fn update_plot(&mut self, waveform: &[f32], side: &[f32], gain: f32, w: usize, h: usize) {
+        self.plot.clear();
+        self.plot.resize(w * h, 0.0);
+        if waveform.len() < 8 || gain <= 0.0 {
+            return;
+        }
+        let stereo = side.len() == waveform.len() && side.iter().any(|s| s.abs() > 1e-4);
+        let lag = if stereo { 0 } else { quarter_period(waveform) };
+        let (cx, cy) = (w as f32 * 0.5, h as f32 * 0.5);
+        let radius = w.min(h) as f32 * 0.5 * 0.92;
+        let k = std::f32::consts::FRAC_1_SQRT_2;
+
+        let mut last: Option<(f32, f32)> = None;
+        for i in 0..waveform.len() - lag {
+            let (x, y) = if stereo {
+                (side[i], waveform[i])
+            } else {
+                let (a, b) = (waveform[i], waveform[i + lag]);
+                ((a - b) * k, (a + b) * k)
+            };
+            let px = cx + x * gain * radius;
+            let py = cy - y * gain * radius;
+            let (lx, ly) = last.unwrap_or((px, py));
+            let steps = (px - lx).abs().max((py - ly).abs()).ceil().max(1.0) as usize;
+            for s in 1..=steps {
+                let f = s as f32 / steps as f32;
+                let x = (lx + (px - lx) * f).round();
+                let y = (ly + (py - ly) * f).round();
+                if x >= 0.0 && y >= 0.0 && (x as usize) < w && (y as usize) < h {
+                    let cell = &mut self.plot[y as usize * w + x as usize];
+                    *cell = (*cell + 0.35).min(1.0);
+                }
+            }
+            last = Some((px, py));
+        }
+    }
+
+    /// Foreground coverage (0..1) and palette position for one pixel block.
+    fn coverage(&self, ctx: &RenderCtx, bx: usize, by: usize, gain: f32) -> (f32, f32) {
+        let (w, h, scale) = (ctx.w.max(1), ctx.h.max(1), ctx.scale.max(1));
+        let x01 = (bx as f32 + 0.5 * scale as f32) / w as f32;
+        let y01 = (by as f32 + 0.5 * scale as f32) / h as f32;
+        let spectrum = ctx.spectrum;
+        let waveform = ctx.waveform;
+        let bins = spectrum.len();
+
+        // Polar coordinates in units of the inscribed circle's radius.
+        let radius = w.min(h) as f32 * 0.5;
+        let dx = (bx as f32 - w as f32 * 0.5) / radius;
+        let dy = (by as f32 - h as f32 * 0.5) / radius;
+        let r = (dx * dx + dy * dy).sqrt();
+        let theta = dy.atan2(dx) / (2.0 * PI) + 0.5;
+
+        match self.kind {
+            ScopeKind::Bars | ScopeKind::MirrorBars => {
+                if bins == 0 {
+                    return (0.0, 0.0);
+                }
+                let (across, level) = match self.kind {
+                    ScopeKind::MirrorBars => ((x01 * 2.0 - 1.0).abs(), (y01 * 2.0 - 1.0).abs()),
+                    _ => (x01, 1.0 - y01),
+                };
+                let pos = across * bins as f32;
+                let col = (pos as usize).min(bins - 1);
+                let bar_px = w as f32 / bins as f32;
+                if bar_px >= 3.0 && pos.fract() > 0.78 {
+                    return (0.0, 0.0);
+                }
+                let cap = 1.5 * scale as f32 / h as f32;
+                if level <= spectrum[col] * 0.92 {
+                    (1.0, level)
+                } else if (level - self.peaks[col] * 0.92).abs() < cap {
+                    (0.9, 1.0)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            ScopeKind::RadialBars => {
+                if bins == 0 {
+                    return (0.0, 0.0);
+                }
+                let (inner, span) = (0.32, 0.62);
+                let col = (((theta * 2.0 - 1.0).abs() * bins as f32) as usize).min(bins - 1);
+                let cap = 1.5 * scale as f32 / radius;
+                if r >= inner && r <= inner + spectrum[col] * span {
+                    (1.0, (r - inner) / span)
+                } else if (r - inner - self.peaks[col] * span).abs() < cap {
+                    (0.9, 1.0)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            ScopeKind::Trace => {
+                let n = waveform.len();
+                if n < 2 {
+                    return (0.0, 0.0);
+                }
+                let i0 = ((bx as f32 / w as f32) * (n - 1) as f32) as usize;
+                let i1 = (((bx + scale) as f32 / w as f32) * (n - 1) as f32).ceil() as usize;
+                let (lo, hi) = waveform[i0.min(n - 1)..=i1.min(n - 1)]
+                    .iter()
+                    .fold((f32::MAX, f32::MIN), |(lo, hi), &s| {
+                        let y = -s * gain * 0.9;
+                        (lo.min(y), hi.max(y))
+                    });
+                let ny = y01 * 2.0 - 1.0;
+                let th = 1.5 * scale as f32 * 2.0 / h as f32;
+                if ny >= lo - th && ny <= hi + th {
+                    (1.0, 0.5 + 0.5 * ny.abs())
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            ScopeKind::Ring => {
+                let n = waveform.len();
+                if n < 2 {
+                    return (0.0, 0.0);
+                }
+                let s = waveform[((theta * (n - 1) as f32) as usize).min(n - 1)] * gain;
+                let ring = 0.58 + 0.3 * s;
+                let d = (r - ring).abs();
+                let th = 1.5 * scale as f32 / radius;
+                if d < th {
+                    (1.0, 0.5 + 0.5 * s.abs())
+                } else if d < th * 2.5 {
+                    (0.35, 0.3)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            ScopeKind::Lissajous => {
+                let v = self.plot.get(by * w + bx).copied().unwrap_or(0.0);
+                (v, 0.3 + 0.7 * v)
+            }
+        }
+    }
+}
+
+impl Preset for ScopePreset {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn meta(&self) -> PresetMeta {
+        self.meta
+    }
+
+    fn params(&self) -> Vec<PresetParam> {
+        let mut out = Vec::new();
+        self.fb.push_params(&mut out);
+        out
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        self.fb.set_param(name, value)
+    }
+
+    fn render(&mut self, ctx: &RenderCtx, prev: &[u8], out: &mut [u8]) {
+        let w = ctx.w.max(1);
+        let h = ctx.h.max(1);
+        let scale = ctx.scale.max(1);
+        let frame_len = w.saturating_mul(h).saturating_mul(4);
+        if out.len() < frame_len {
+            return;
+        }
+
+        let route = RouteMap::from_ctx(ctx);
+        let (bass, mid, treb, energy) = (route.bass, route.mid, route.treb, route.energy);
+        let beat_pulse = route.beat;
+        let zoom_mod = (1.0 - bass * 0.12 * route.zoom - beat_pulse * 0.08).clamp(0.25, 1.4);
+        let t = ctx.t;
+
+        // Normalise the scope traces so quiet material stays readable.
+        let peak = ctx.waveform.iter().chain(ctx.side).fold(0.0f32, |m, s| m.max(s.abs()));
+        let gain = if peak > 1e-4 {
+            (0.9 / peak).min(8.0) * (0.65 + 0.35 * energy)
+        } else {
+            0.0
+        };
+
+        self.update_peaks(ctx.spectrum, ctx.dt);
+        if matches!(self.kind, ScopeKind::Lissajous) {
+            let (waveform, side) = (ctx.waveform, ctx.side);
+            self.update_plot(waveform, side, gain, w, h);
+        }
+        let harmony = harmony_hue(&ctx.audio);
+
+        for by in (0..h).step_by(scale) {
+            for bx in (0..w).step_by(scale) {
+                let sx = bx as f32 / w as f32 * 2.0 - 1.0;
+                let sy = by as f32 / h as f32 * 2.0 - 1.0;
+                let base = self.fb.base(ctx, prev, (sx, sy), &route, zoom_mod, self.seed);
+
+                // Faint palette wash keeps the background from going fully black.
+                let wash = palette(
+                    self.palette,
+                    0.08 + 0.12 * (1.0 - sy) * 0.5,
+                    t,
+                    [bass, mid, treb],
+                    beat_pulse,
+                    harmony,
+                );
+                let (cov, val) = self.coverage(ctx, bx, by, gain);
+                let ink = palette(self.palette, val, t, [bass, mid, treb], beat_pulse, harmony);
+                let ink_alpha = (cov * (0.8 + 0.2 * energy + 0.15 * beat_pulse)).clamp(0.0, 1.0);
+
+                let mut rgb = [0u8; 3];
+                for c in 0..3 {
+                    let bg = base[c] as f32 * 0.92 + wash[c] as f32 * 0.08;
+                    rgb[c] = (bg * (1.0 - ink_alpha) + ink[c] as f32 * ink_alpha) as u8;
+                }
+
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let x2 = bx + dx;
+                        let y2 = by + dy;
+                        if x2 >= w || y2 >= h {
+                            continue;
+                        }
+                        let i = (y2 * w + x2) * 4;
+                        out[i] = rgb[0];
+                        out[i + 1] = rgb[1];
+                        out[i + 2] = rgb[2];
+                        out[i + 3] = 255;
+                    }
+                }
+            }
+        }
+
+        apply_post_fx(out, w, h, t, route, ctx.quality, &mut self.post_fx_scratch);
+    }
+}
+
+/// Quarter of the dominant period, estimated from the zero-crossing rate.
+fn quarter_period(waveform: &[f32]) -> usize {
+    let n = waveform.len();
+    let crossings = waveform
+        .windows(2)
+        .filter(|p| (p[0] >= 0.0) != (p[1] >= 0.0))
+        .count();
+    let lag = if crossings >= 2 {
+        n as f32 / crossings as f32 * 0.5
+    } else {
+        n as f32 / 8.0
+    };
+    (lag.round() as usize).clamp(2, (n / 4).max(2))
+}
+
 #[derive(Clone, Copy)]
 struct RouteMap {
     bass: f32,
@@ -810,6 +1954,10 @@ struct RouteMap {
     onset: f32,
     energy: f32,
     beat: f32,
+    kick: f32,
+    hat: f32,
+    pan: f32,
+    width: f32,
     transient: f32,
     drive: f32,
     zoom: f32,
@@ -824,21 +1972,33 @@ struct RouteMap {
 
 impl RouteMap {
     fn from_ctx(ctx: &RenderCtx) -> Self {
-        let bands = &ctx.audio.bands;
+        let bands = ctx.audio.bands8();
         let bass = (0.56 * bands[0] + 0.44 * bands[1]).clamp(0.0, 1.0);
         let mid = (0.22 * bands[2] + 0.46 * bands[3] + 0.32 * bands[4]).clamp(0.0, 1.0);
         let treb = (0.34 * bands[5] + 0.66 * bands[6]).clamp(0.0, 1.0);
         let onset = ctx.audio.onset.clamp(0.0, 1.0);
         let energy = ctx.audio.rms.clamp(0.0, 1.0);
+        let mut kick = ctx.audio.kick.clamp(0.0, 1.0);
+        let mut hat = ctx.audio.hat.clamp(0.0, 1.0);
+        // Drift toward the louder channel and stretch with the stereo image, but
+        // only in stereo split mode so unbalanced mixes leave other looks alone.
+        let (pan, width) = if ctx.stereo {
+            (ctx.audio.balance.clamp(-1.0, 1.0), ctx.audio.stereo_width.clamp(0.0, 1.0))
+        } else {
+            (0.0, 0.0)
+        };
 
         let mut beat = ctx.beat_pulse.clamp(0.0, 1.0);
         if ctx.safe {
+            // Drum triggers drive the same zoom surges and flashes as the beat.
             beat = (beat * 0.6).min(0.6);
+            kick = (kick * 0.6).min(0.6);
+            hat = (hat * 0.6).min(0.6);
         }
 
         let transient = smoothed_transient_drive(beat, onset);
         let drive = smoothed_motion_drive(bass, mid, treb, beat, onset);
-        let zoom = (0.55 + 0.68 * drive + 0.33 * transient).clamp(0.35, 1.85);
+        let zoom = (0.55 + 0.68 * drive + 0.33 * transient + 0.22 * kick).clamp(0.35, 1.85);
         let orbit = (0.28 + 0.54 * mid + 0.34 * treb + 0.22 * transient).clamp(0.12, 1.45);
         let detail = (0.26 + 0.50 * treb + 0.28 * transient + 0.10 * mid).clamp(0.0, 1.0);
         let fx_mix = (0.10 + 0.52 * energy + 0.38 * transient + 0.12 * drive).clamp(0.0, 1.0);
@@ -854,6 +2014,10 @@ impl RouteMap {
             onset,
             energy,
             beat,
+            kick,
+            hat,
+            pan,
+            width,
             transient,
             drive,
             zoom,
@@ -866,6 +2030,15 @@ impl RouteMap {
             bloom,
         }
     }
+
+    fn scale_post_fx(mut self, fx: &PostFxSpec) -> Self {
+        self.fx_mix = (self.fx_mix * fx.mix).clamp(0.0, 1.0);
+        self.chroma *= fx.chroma;
+        self.scanline *= fx.scanline;
+        self.vignette = (self.vignette * fx.vignette).clamp(0.0, 0.95);
+        self.bloom = (self.bloom * fx.bloom).clamp(0.0, 0.95);
+        self
+    }
 }
 
 fn fractal_mandelbrot(
@@ -1948,6 +3121,39 @@ fn nova_fractal(
     ((1.0 - n).powf(0.42) * 0.56 + root_glow * 0.30 + stripe * 0.14).clamp(0.0, 1.0)
 }
 
+/// Stereo split mode: brightens the half of the frame on the louder channel and
+/// dims the other, with a soft crossfade across the centre. `gains` is per-column
+/// scratch kept by the caller between frames.
+pub fn apply_stereo_split(
+    pixels: &mut [u8],
+    w: usize,
+    h: usize,
+    audio: &AudioFeatures,
+    gains: &mut Vec<f32>,
+) {
+    let (l, r) = (audio.rms_left.max(0.0), audio.rms_right.max(0.0));
+    let peak = l.max(r);
+    if w == 0 || peak <= 1e-4 {
+        return;
+    }
+    let gain_l = 0.55 + 0.6 * l / peak;
+    let gain_r = 0.55 + 0.6 * r / peak;
+
+    gains.clear();
+    gains.extend((0..w).map(|x| {
+        let u = ((x as f32 + 0.5) / w as f32 - 0.45) / 0.1;
+        let f = u.clamp(0.0, 1.0);
+        gain_l + (gain_r - gain_l) * f * f * (3.0 - 2.0 * f)
+    }));
+    for row in pixels.chunks_exact_mut(w * 4).take(h) {
+        for (px, g) in row.chunks_exact_mut(4).zip(gains.iter()) {
+            for c in &mut px[..3] {
+                *c = (*c as f32 * g).min(255.0) as u8;
+            }
+        }
+    }
+}
+
 fn apply_post_fx(out: &mut [u8], w: usize, h: usize, t: f32, route: RouteMap, quality: Quality, scratch: &mut Vec<u8>) {
     let frame_len = w.saturating_mul(h).saturating_mul(4);
     if out.len() < frame_len || w == 0 || h == 0 {
@@ -2054,13 +3260,25 @@ fn apply_post_fx(out: &mut [u8], w: usize, h: usize, t: f32, route: RouteMap, qu
     }
 }
 
-fn palette(p: Palette, v: f32, t: f32, bass: f32, mid: f32, treb: f32, beat: f32) -> [u8; 3] {
+/// Hue offset (0..1) for the estimated key: the relative major's position on the
+/// circle of fifths, so closely related keys get neighbouring colours. Fades in
+/// with key confidence and is 0 until a key has been found.
+fn harmony_hue(audio: &AudioFeatures) -> f32 {
+    let tonic = if audio.key_minor { (audio.key + 3) % 12 } else { audio.key % 12 };
+    let fifths = (tonic as u32 * 7) % 12;
+    let w = ((audio.key_confidence - 0.3) / 0.4).clamp(0.0, 1.0);
+    fifths as f32 / 12.0 * w * w * (3.0 - 2.0 * w)
+}
+
+/// `harmony` is the key hue from `harmony_hue`; only the harmonic palettes
+/// (Prism, Aurora) follow it.
+fn palette(p: Palette, v: f32, t: f32, [bass, mid, treb]: [f32; 3], beat: f32, harmony: f32) -> [u8; 3] {
     let v = v.clamp(0.0, 1.0);
     let pop = (0.4 * bass + 0.3 * mid + 0.6 * treb + 0.8 * beat).clamp(0.0, 1.0);
 
     match p {
         Palette::Prism => {
-            let h = fract01(v * 0.92 + t * 0.04 + pop * 0.12);
+            let h = fract01(v * 0.92 + t * 0.04 + pop * 0.12 + harmony);
             hsv_to_rgb(h, 0.92, (0.55 + v * 0.45 + pop * 0.2).min(1.0))
         }
         Palette::Acid => {
@@ -2077,7 +3295,8 @@ fn palette(p: Palette, v: f32, t: f32, bass: f32, mid: f32, treb: f32, beat: f32
             hsv_to_rgb(h, 0.96, (0.35 + v * 0.75 + pop * 0.35).min(1.0))
         }
         Palette::Aurora => {
-            let h = fract01(0.35 + v * 0.22 + t * 0.03 + mid * 0.1);
+            // Aurora keeps to its greens/blues; the key only shifts it partway round.
+            let h = fract01(0.35 + v * 0.22 + t * 0.03 + mid * 0.1 + harmony * 0.35);
             hsv_to_rgb(h, 0.9, (0.45 + v * 0.6 + pop * 0.15).min(1.0))
         }
         Palette::Cosmic => {
@@ -0,0 +1,215 @@
+use crate::audio::AudioFeatures;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Length of one history bucket; the build-up window spans `HISTORY_BUCKETS` of them.
+const BUCKET: Duration = Duration::from_millis(500);
+const HISTORY_BUCKETS: usize = 16;
+
+/// Time constants (seconds) of the fast low-end follower and the long-run
+/// low-end reference a breakdown is measured against.
+const LOW_FAST_S: f32 = 0.35;
+const LOW_REF_S: f32 = 20.0;
+/// Low end must stay below this fraction of the reference for `BREAKDOWN_HOLD`
+/// before the track counts as broken down.
+const BREAKDOWN_RATIO: f32 = 0.4;
+const BREAKDOWN_HOLD: Duration = Duration::from_secs(2);
+/// A drop is the low end coming back to this fraction of the reference.
+const DROP_RATIO: f32 = 0.75;
+/// Rise in the build score between the older and newer half of the window
+/// that counts as a build-up, and the high-band level it must reach.
+const BUILD_RISE: f32 = 0.08;
+const BUILD_MIN_HIGH: f32 = 0.2;
+/// A build-up that stops rising for this long is abandoned.
+const BUILD_RELEASE: Duration = Duration::from_secs(3);
+/// Longest a build-up may hold the current preset before auto-switching resumes.
+const BUILD_MAX_HOLD: Duration = Duration::from_secs(40);
+const DROP_COOLDOWN: Duration = Duration::from_secs(4);
+/// Quietest low end that still counts as a reference; keeps silence from
+/// looking like a breakdown.
+const LOW_REF_FLOOR: f32 = 0.08;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StructurePhase {
+    Steady,
+    /// Low end has dropped out well below its running level.
+    Breakdown,
+    /// Energy and high-band density have been rising for several bars.
+    BuildUp,
+}
+
+impl StructurePhase {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Steady => "Steady",
+            Self::Breakdown => "Breakdown",
+            Self::BuildUp => "Build-up",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StructureEvent {
+    BuildUp,
+    Drop,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    score: f32,
+    high: f32,
+    frames: u32,
+}
+
+/// Long-horizon energy tracker: where `classify_scene_section` looks at one
+/// frame, this watches the last several bars for build-ups and for the low end
+/// returning after a breakdown.
+pub(crate) struct StructureTracker {
+    phase: StructurePhase,
+    phase_since: Instant,
+    last_update: Option<Instant>,
+    low_fast: f32,
+    low_ref: f32,
+    low_quiet_since: Option<Instant>,
+    /// Low end has dipped out at some point since the break or build began.
+    low_was_out: bool,
+    last_rising: Instant,
+    last_drop: Option<Instant>,
+    bucket: Bucket,
+    bucket_start: Instant,
+    history: VecDeque<Bucket>,
+}
+
+impl StructureTracker {
+    pub(crate) fn new(now: Instant) -> Self {
+        Self {
+            phase: StructurePhase::Steady,
+            phase_since: now,
+            last_update: None,
+            low_fast: 0.0,
+            low_ref: 0.0,
+            low_quiet_since: None,
+            low_was_out: false,
+            last_rising: now,
+            last_drop: None,
+            bucket: Bucket::default(),
+            bucket_start: now,
+            history: VecDeque::with_capacity(HISTORY_BUCKETS + 1),
+        }
+    }
+
+    pub(crate) fn phase(&self) -> StructurePhase {
+        self.phase
+    }
+
+    /// True while a build-up should keep the current preset on screen.
+    pub(crate) fn holding(&self, now: Instant) -> bool {
+        self.phase == StructurePhase::BuildUp && now.duration_since(self.phase_since) < BUILD_MAX_HOLD
+    }
+
+    pub(crate) fn update(&mut self, now: Instant, audio: &AudioFeatures) -> Option<StructureEvent> {
+        let dt = self
+            .last_update
+            .map(|t| now.saturating_duration_since(t).as_secs_f32())
+            .unwrap_or(0.0)
+            .min(0.5);
+        self.last_update = Some(now);
+
+        let low = ((audio.band8(0) + audio.band8(1)) * 0.5).clamp(0.0, 1.0);
+        let high = ((audio.band8(5) + audio.band8(6) + audio.band8(7)) * (1.0 / 3.0)).clamp(0.0, 1.0);
+        let onset = audio.onset.clamp(0.0, 1.0);
+        self.low_fast += (low - self.low_fast) * (1.0 - (-dt / LOW_FAST_S).exp());
+        // The reference only learns outside breakdowns, so a long break can't
+        // lower the bar the drop has to clear.
+        if self.phase == StructurePhase::Steady {
+            self.low_ref += (low - self.low_ref) * (1.0 - (-dt / LOW_REF_S).exp());
+        }
+
+        self.bucket.score += audio.rms.clamp(0.0, 1.0) * 0.4 + high * 0.4 + onset * 0.2;
+        self.bucket.high += high;
+        self.bucket.frames += 1;
+        if now.saturating_duration_since(self.bucket_start) >= BUCKET {
+            let n = self.bucket.frames.max(1) as f32;
+            self.history.push_back(Bucket {
+                score: self.bucket.score / n,
+                high: self.bucket.high / n,
+                frames: 1,
+            });
+            if self.history.len() > HISTORY_BUCKETS {
+                self.history.pop_front();
+            }
+            self.bucket = Bucket::default();
+            self.bucket_start = now;
+        }
+
+        let low_ref = self.low_ref.max(LOW_REF_FLOOR);
+        if self.low_fast < low_ref * BREAKDOWN_RATIO {
+            self.low_quiet_since.get_or_insert(now);
+        } else {
+            self.low_quiet_since = None;
+        }
+        if self.phase == StructurePhase::Steady {
+            self.low_was_out = self.low_quiet_since.is_some();
+        } else {
+            self.low_was_out |= self.low_quiet_since.is_some();
+        }
+        let broken_down = self
+            .low_quiet_since
+            .is_some_and(|t| now.duration_since(t) >= BREAKDOWN_HOLD);
+        let rising = self.rising();
+        if rising {
+            self.last_rising = now;
+        }
+
+        let cooling = self
+            .last_drop
+            .is_some_and(|t| now.duration_since(t) < DROP_COOLDOWN);
+        match self.phase {
+            StructurePhase::Breakdown | StructurePhase::BuildUp
+                if self.low_was_out && self.low_fast >= low_ref * DROP_RATIO =>
+            {
+                self.set_phase(StructurePhase::Steady, now);
+                self.last_drop = Some(now);
+                self.history.clear();
+                return Some(StructureEvent::Drop);
+            }
+            StructurePhase::BuildUp if now.duration_since(self.last_rising) > BUILD_RELEASE => {
+                let next = if broken_down {
+                    StructurePhase::Breakdown
+                } else {
+                    StructurePhase::Steady
+                };
+                self.set_phase(next, now);
+            }
+            StructurePhase::Steady | StructurePhase::Breakdown
+                if rising && !cooling =>
+            {
+                self.set_phase(StructurePhase::BuildUp, now);
+                return Some(StructureEvent::BuildUp);
+            }
+            StructurePhase::Steady if broken_down => self.set_phase(StructurePhase::Breakdown, now),
+            _ => {}
+        }
+        None
+    }
+
+    fn set_phase(&mut self, phase: StructurePhase, now: Instant) {
+        self.phase = phase;
+        self.phase_since = now;
+    }
+
+    /// Build score and high-band density both rising across the full window.
+    fn rising(&self) -> bool {
+        if self.history.len() < HISTORY_BUCKETS {
+            return false;
+        }
+        let half = HISTORY_BUCKETS / 2;
+        let mean = |range: std::ops::Range<usize>, f: fn(&Bucket) -> f32| {
+            range.clone().map(|i| f(&self.history[i])).sum::<f32>() / range.len() as f32
+        };
+        let score_rise = mean(half..HISTORY_BUCKETS, |b| b.score) - mean(0..half, |b| b.score);
+        let high_old = mean(0..half, |b| b.high);
+        let high_new = mean(half..HISTORY_BUCKETS, |b| b.high);
+        score_rise > BUILD_RISE && high_new > high_old && high_new > BUILD_MIN_HIGH
+    }
+}
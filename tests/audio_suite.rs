@@ -0,0 +1,896 @@
+use clap::Parser;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tui_visualizer::audio::{
+    read_wav_mono_f32, read_wav_stereo_f32, Analyzer, AnalyzerConfig, AudioSnapshot,
+    AudioSupervisor, AudioSystem, SupervisorEvent, ANALYZER_WINDOW, LUFS_FLOOR,
+};
+use tui_visualizer::config::{AudioSource, Config, SynthPattern};
+use tui_visualizer::recording::{FeatureRecorder, FeatureRecording};
+use tui_visualizer::synth::PatternSynth;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("brotviz-{}-{}", std::process::id(), name))
+}
+
+fn write_wav_i16(path: &PathBuf, sr: u32, channels: u16, interleaved: &[i16]) {
+    let data_len = (interleaved.len() * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sr.to_le_bytes());
+    bytes.extend_from_slice(&(sr * channels as u32 * 2).to_le_bytes());
+    bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for s in interleaved {
+        bytes.extend_from_slice(&s.to_le_bytes());
+    }
+    std::fs::write(path, bytes).expect("write wav fixture");
+}
+
+fn tone_i16(sr: u32, seconds: f32, hz: f32, amp: f32) -> Vec<i16> {
+    let n = (sr as f32 * seconds) as usize;
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / sr as f32;
+            ((2.0 * std::f32::consts::PI * hz * t).sin() * amp * 32767.0) as i16
+        })
+        .collect()
+}
+
+#[test]
+fn wav_reader_downmixes_stereo_pcm16() {
+    let path = temp_path("stereo.wav");
+    write_wav_i16(&path, 22_050, 2, &[16384, 0, -16384, -16384, 0, 32767]);
+
+    let (sr, mono) = read_wav_mono_f32(&path).expect("wav should decode");
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(sr, 22_050);
+    assert_eq!(mono.len(), 3);
+    assert!((mono[0] - 0.25).abs() < 1e-3);
+    assert!((mono[1] + 0.5).abs() < 1e-3);
+    assert!((mono[2] - 0.5).abs() < 1e-3);
+}
+
+#[test]
+fn wav_reader_keeps_stereo_channels() {
+    let path = temp_path("stereo-lr.wav");
+    write_wav_i16(&path, 22_050, 2, &[16384, 0, -16384, -16384]);
+
+    let (sr, frames) = read_wav_stereo_f32(&path).expect("wav should decode");
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(sr, 22_050);
+    assert_eq!(frames, vec![[0.5, 0.0], [-0.5, -0.5]]);
+}
+
+#[test]
+fn file_source_requires_input_path() {
+    let cfg = Config::try_parse_from(["tui-visualizer", "--source", "file"])
+        .expect("parse should succeed");
+    assert_eq!(cfg.source, AudioSource::File);
+    let err = AudioSystem::new(&cfg).err().expect("missing --input must fail");
+    assert!(err.to_string().contains("--input"));
+}
+
+#[test]
+fn file_source_drives_analyzer_in_real_time() {
+    let path = temp_path("tone.wav");
+    write_wav_i16(&path, 48_000, 1, &tone_i16(48_000, 0.5, 220.0, 0.6));
+
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "file",
+        "--input",
+        path.to_str().expect("utf-8 temp path"),
+        "--input-loop",
+    ])
+    .expect("parse should succeed");
+    let audio = AudioSystem::new(&cfg).expect("file source should start");
+    let features = audio.features();
+
+    let deadline = Instant::now() + Duration::from_secs(3);
+    let mut rms = 0.0f32;
+    while Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+        rms = features.load().rms;
+        if rms > 0.05 {
+            break;
+        }
+    }
+    drop(audio);
+    let _ = std::fs::remove_file(&path);
+
+    assert!(rms > 0.05, "file playback never reached the analyzer (rms={rms})");
+}
+
+#[test]
+fn file_source_publishes_spectrum_and_waveform_snapshot() {
+    let path = temp_path("snapshot.wav");
+    write_wav_i16(&path, 48_000, 1, &tone_i16(48_000, 0.5, 220.0, 0.6));
+
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "file",
+        "--input",
+        path.to_str().expect("utf-8 temp path"),
+        "--input-loop",
+        "--spectrum-bins",
+        "32",
+    ])
+    .expect("parse should succeed");
+    let audio = AudioSystem::new(&cfg).expect("file source should start");
+    let shared = audio.snapshot();
+    let mut snapshot = AudioSnapshot::default();
+
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+        shared.load_into(&mut snapshot);
+        if snapshot.waveform.iter().any(|s| s.abs() > 0.1) {
+            break;
+        }
+    }
+    drop(audio);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(snapshot.spectrum.len(), 32);
+    assert_eq!(snapshot.waveform.len(), ANALYZER_WINDOW);
+    assert!(snapshot.waveform.iter().any(|s| s.abs() > 0.1), "waveform never published");
+    let peak = snapshot
+        .spectrum
+        .iter()
+        .enumerate()
+        .fold((0, 0.0f32), |a, (i, &v)| if v > a.1 { (i, v) } else { a });
+    // 220 Hz sits about a third of the way up a 30 Hz..16 kHz log axis.
+    assert!(peak.1 > 0.1 && (8..=12).contains(&peak.0), "unexpected spectrum peak {peak:?}");
+}
+
+#[test]
+fn file_source_reports_balance_and_width_for_left_panned_tone() {
+    let path = temp_path("left.wav");
+    let interleaved = tone_i16(48_000, 0.5, 330.0, 0.6)
+        .into_iter()
+        .flat_map(|s| [s, 0])
+        .collect::<Vec<_>>();
+    write_wav_i16(&path, 48_000, 2, &interleaved);
+
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "file",
+        "--input",
+        path.to_str().expect("utf-8 temp path"),
+        "--input-loop",
+    ])
+    .expect("parse should succeed");
+    let audio = AudioSystem::new(&cfg).expect("file source should start");
+    let features = audio.features();
+
+    let deadline = Instant::now() + Duration::from_secs(3);
+    let mut f = features.load();
+    while Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+        f = features.load();
+        if f.balance < -0.9 && f.stereo_width > 0.4 {
+            break;
+        }
+    }
+    drop(audio);
+    let _ = std::fs::remove_file(&path);
+
+    assert!(f.rms_left > 0.1 && f.rms_right < 1e-3, "channel levels {f:?}");
+    assert!(f.balance < -0.9, "balance should lean left: {}", f.balance);
+    assert!(f.stereo_width > 0.4, "hard pan should read as wide: {}", f.stereo_width);
+}
+
+#[test]
+fn file_source_honours_fft_size_and_band_layout() {
+    let path = temp_path("layout.wav");
+    write_wav_i16(&path, 48_000, 1, &tone_i16(48_000, 0.5, 90.0, 0.6));
+
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "file",
+        "--input",
+        path.to_str().expect("utf-8 temp path"),
+        "--input-loop",
+        "--fft-size",
+        "4096",
+        "--hop",
+        "512",
+        "--window",
+        "blackman",
+        "--agc=false",
+        "--band-edges",
+        "20,40,60,150,400,1000,20000",
+    ])
+    .expect("parse should succeed");
+    let audio = AudioSystem::new(&cfg).expect("file source should start");
+    let features = audio.features();
+    let shared = audio.snapshot();
+
+    let deadline = Instant::now() + Duration::from_secs(3);
+    let mut f = features.load();
+    while Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+        f = features.load();
+        if f.rms > 0.05 && f.bands[2] > 0.1 {
+            break;
+        }
+    }
+    let mut snapshot = AudioSnapshot::default();
+    shared.load_into(&mut snapshot);
+    drop(audio);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(f.band_count, 6);
+    assert_eq!(snapshot.waveform.len(), 4096);
+    let loudest = (0..f.band_count)
+        .max_by(|&a, &b| f.bands[a].total_cmp(&f.bands[b]))
+        .expect("bands");
+    // 90 Hz lands in the 60..150 Hz band.
+    assert_eq!(loudest, 2, "bands {:?}", &f.bands[..f.band_count]);
+    assert!(f.bands[f.band_count..].iter().all(|&b| b == 0.0));
+}
+
+#[test]
+fn capture_flags_parse_channel_selections() {
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--capture-channels",
+        "3-4,7",
+        "--capture-rate",
+        "96000",
+        "--capture-buffer",
+        "256",
+    ])
+    .expect("parse should succeed");
+    let channels = cfg.capture.capture_channels.expect("channels set");
+    assert_eq!(channels.indices(), [2, 3, 6]);
+    assert_eq!(channels.min_channels(), 7);
+    assert_eq!(cfg.capture.capture_rate, Some(96_000));
+    assert_eq!(cfg.capture.capture_buffer, Some(256));
+
+    for bad in ["0", "4-3", "a", "1,,2"] {
+        assert!(
+            Config::try_parse_from(["tui-visualizer", "--capture-channels", bad]).is_err(),
+            "{bad:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn audio_system_rejects_invalid_fft_size() {
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "stdin",
+        "--fft-size",
+        "1000",
+    ])
+    .expect("parse should succeed");
+    let err = AudioSystem::new(&cfg).err().expect("non power of two must fail");
+    assert!(err.to_string().contains("--fft-size"));
+}
+
+#[test]
+fn analyzer_output_does_not_depend_on_chunking() {
+    let sr = 48_000u32;
+    let frames = (0..sr as usize / 2)
+        .map(|i| {
+            let t = i as f32 / sr as f32;
+            let kick = if (t * 4.0).fract() < 0.05 {
+                (t * 60.0 * std::f32::consts::TAU).sin()
+            } else {
+                0.0
+            };
+            let tone = (t * 880.0 * std::f32::consts::TAU).sin() * 0.2;
+            [kick * 0.8 + tone, kick * 0.6 - tone]
+        })
+        .collect::<Vec<_>>();
+
+    let mut whole = Analyzer::new(AnalyzerConfig::default(), sr).expect("analyzer");
+    let mut expected = Vec::new();
+    whole.process(&frames, |a| expected.push((a.features(), a.spectrum().to_vec())));
+    assert_eq!(expected.len(), (frames.len() - 1024) / 256 + 1);
+
+    let mut chunked = Analyzer::new(AnalyzerConfig::default(), sr).expect("analyzer");
+    let mut got = Vec::new();
+    for chunk in frames.chunks(97) {
+        for &frame in chunk {
+            if let Some(f) = chunked.push_frame(frame) {
+                got.push((f, chunked.spectrum().to_vec()));
+            }
+        }
+    }
+    assert!(got == expected, "chunked analysis diverged from whole-buffer analysis");
+    assert_eq!(chunked.waveform(), whole.waveform());
+    assert_eq!(chunked.side(), whole.side());
+    assert!(expected.iter().any(|(f, _)| f.beat));
+}
+
+#[test]
+fn analyzer_rejects_invalid_config_and_sample_rate() {
+    let bad_hop = AnalyzerConfig {
+        hop: 0,
+        ..AnalyzerConfig::default()
+    };
+    assert!(Analyzer::new(bad_hop, 48_000).is_err());
+    assert!(Analyzer::new(AnalyzerConfig::default(), 0).is_err());
+    let bad_hold = AnalyzerConfig {
+        silence_hold_s: -1.0,
+        ..AnalyzerConfig::default()
+    };
+    assert!(Analyzer::new(bad_hold, 48_000).is_err());
+}
+
+#[test]
+fn analyzer_handles_sample_rates_below_the_tonal_range() {
+    for sr in [1u32, 50, 200, 250] {
+        let mut analyzer = Analyzer::new(AnalyzerConfig::default(), sr).expect("rate accepted");
+        let frames = (0..4096)
+            .map(|i| {
+                let v = (i as f32 * 0.3).sin() * 0.5;
+                [v, v]
+            })
+            .collect::<Vec<_>>();
+        let mut last = None;
+        for frame in frames {
+            last = analyzer.push_frame(frame).or(last);
+        }
+        let f = last.expect("features produced");
+        assert!(f.rms.is_finite() && f.pitch_hz.is_finite(), "{sr} Hz: {f:?}");
+    }
+}
+
+#[test]
+fn analyzer_flags_silence_after_the_hold_and_clears_on_sound() {
+    let sr = 48_000u32;
+    let tone = |seconds: f32| {
+        (0..(sr as f32 * seconds) as usize)
+            .map(|i| {
+                let v = (i as f32 / sr as f32 * 330.0 * std::f32::consts::TAU).sin() * 0.3;
+                [v, v]
+            })
+            .collect::<Vec<_>>()
+    };
+    let quiet = |seconds: f32| vec![[1e-5f32; 2]; (sr as f32 * seconds) as usize];
+    let cfg = AnalyzerConfig {
+        silence_threshold_db: -50.0,
+        silence_hold_s: 1.5,
+        ..AnalyzerConfig::default()
+    };
+    let mut analyzer = Analyzer::new(cfg, sr).expect("analyzer");
+    analyzer.process(&tone(0.5), |_| {});
+    assert!(!analyzer.features().silent);
+    analyzer.process(&quiet(1.0), |_| {});
+    assert!(!analyzer.features().silent, "silent before the hold time");
+    analyzer.process(&quiet(1.0), |_| {});
+    assert!(analyzer.features().silent, "not silent after the hold time");
+
+    let mut first = None;
+    analyzer.process(&tone(0.1), |a| {
+        first.get_or_insert(a.features().silent);
+    });
+    assert_eq!(first, Some(false), "sound did not end silence on the first hop");
+}
+
+#[test]
+fn analyzer_measures_k_weighted_loudness_and_crest_factor() {
+    let sine = |sr: u32, seconds: f32, hz: f32, amp: f32| {
+        (0..(sr as f32 * seconds) as usize)
+            .map(|i| {
+                let v = (i as f32 / sr as f32 * hz * std::f32::consts::TAU).sin() * amp;
+                [v, v]
+            })
+            .collect::<Vec<_>>()
+    };
+    // BS.1770 calibration: a 1 kHz sine at -20 dBFS on both channels reads
+    // -20 LUFS, at any sample rate.
+    for sr in [44_100u32, 48_000] {
+        let mut analyzer = Analyzer::new(AnalyzerConfig::default(), sr).expect("analyzer");
+        analyzer.process(&sine(sr, 3.5, 1000.0, 0.1), |_| {});
+        let f = analyzer.features();
+        assert!((f.lufs_momentary + 20.0).abs() < 0.3, "{sr} Hz momentary {}", f.lufs_momentary);
+        assert!((f.lufs_short_term + 20.0).abs() < 0.3, "{sr} Hz short-term {}", f.lufs_short_term);
+        assert!((f.crest_db - 3.01).abs() < 0.2, "sine crest {}", f.crest_db);
+    }
+
+    // K-weighting discounts the low end: a 40 Hz sine at the same level reads quieter.
+    let mut analyzer = Analyzer::new(AnalyzerConfig::default(), 48_000).expect("analyzer");
+    analyzer.process(&sine(48_000, 1.0, 40.0, 0.1), |_| {});
+    assert!(analyzer.features().lufs_momentary < -21.0);
+
+    // Momentary follows a 20 dB drop within half a second; short-term lags behind.
+    let mut analyzer = Analyzer::new(AnalyzerConfig::default(), 48_000).expect("analyzer");
+    analyzer.process(&sine(48_000, 3.0, 1000.0, 0.1), |_| {});
+    analyzer.process(&sine(48_000, 0.5, 1000.0, 0.01), |_| {});
+    let f = analyzer.features();
+    assert!((f.lufs_momentary + 40.0).abs() < 0.5, "momentary {}", f.lufs_momentary);
+    assert!(f.lufs_short_term > -28.0, "short-term {}", f.lufs_short_term);
+    assert!(f.loudness() < f.loudness_short());
+
+    // Sparse clicks over a quiet bed have a high crest factor.
+    let clicks = (0..96_000usize)
+        .map(|i| {
+            let v = if i % 12_000 == 0 { 0.9 } else { ((i as f32) * 0.37).sin() * 0.01 };
+            [v, v]
+        })
+        .collect::<Vec<_>>();
+    let mut analyzer = Analyzer::new(AnalyzerConfig::default(), 48_000).expect("analyzer");
+    analyzer.process(&clicks, |_| {});
+    assert!(analyzer.features().crest_db > 25.0, "click crest {}", analyzer.features().crest_db);
+    assert_eq!(analyzer.features().dynamics(), 1.0);
+
+    let silent = Analyzer::new(AnalyzerConfig::default(), 48_000)
+        .map(|mut a| {
+            a.process(&vec![[0.0f32; 2]; 48_000], |_| {});
+            a.features()
+        })
+        .expect("analyzer");
+    assert_eq!((silent.lufs_momentary, silent.crest_db), (LUFS_FLOOR, 0.0));
+}
+
+fn chord_frames(sr: u32, seconds: f32, notes_hz: &[f32]) -> Vec<[f32; 2]> {
+    (0..(sr as f32 * seconds) as usize)
+        .map(|i| {
+            let t = i as f32 / sr as f32;
+            let v = notes_hz
+                .iter()
+                .map(|hz| (t * hz * std::f32::consts::TAU).sin())
+                .sum::<f32>()
+                * 0.2;
+            [v, v]
+        })
+        .collect()
+}
+
+#[test]
+fn analyzer_reports_dominant_pitch_and_chroma() {
+    let sr = 48_000u32;
+    // G3 with two overtones: the fundamental must win over its octave.
+    let frames = (0..sr as usize)
+        .map(|i| {
+            let p = i as f32 / sr as f32 * 196.0 * std::f32::consts::TAU;
+            let v = 0.3 * p.sin() + 0.25 * (2.0 * p).sin() + 0.15 * (3.0 * p).sin();
+            [v, v]
+        })
+        .collect::<Vec<_>>();
+    let mut analyzer = Analyzer::new(AnalyzerConfig::default(), sr).expect("analyzer");
+    analyzer.process(&frames, |_| {});
+    let f = analyzer.features();
+    assert!((f.pitch_hz - 196.0).abs() < 196.0 * 0.02, "pitch {}", f.pitch_hz);
+    let strongest = (0..12).max_by(|&a, &b| f.chroma[a].total_cmp(&f.chroma[b])).unwrap();
+    assert_eq!(strongest, 7, "chroma {:?}", f.chroma);
+
+    let mut quiet = Analyzer::new(AnalyzerConfig::default(), sr).expect("analyzer");
+    quiet.process(&vec![[0.0; 2]; sr as usize / 2], |_| {});
+    assert_eq!(quiet.features().pitch_hz, 0.0);
+    assert_eq!(quiet.features().chroma, [0.0; 12]);
+}
+
+#[test]
+fn analyzer_estimates_key_and_flags_key_changes() {
+    let sr = 22_050u32;
+    let c_major = [
+        [261.63, 329.63, 392.00],
+        [349.23, 440.00, 523.25],
+        [392.00, 493.88, 587.33],
+        [261.63, 329.63, 392.00],
+    ];
+    // Same progression a tritone up (F# major), the furthest key away.
+    let tritone = 2f32.powf(6.0 / 12.0);
+    let mut frames = Vec::new();
+    for chord in c_major.iter().cycle().take(12) {
+        frames.extend(chord_frames(sr, 1.0, chord));
+    }
+    let mut analyzer = Analyzer::new(AnalyzerConfig::default(), sr).expect("analyzer");
+    let mut changes = 0;
+    analyzer.process(&frames, |a| changes += usize::from(a.features().key_change == 1.0));
+    let f = analyzer.features();
+    assert_eq!((f.key, f.key_minor), (0, false), "expected C major");
+    assert!(f.key_confidence > 0.5, "confidence {}", f.key_confidence);
+    assert_eq!(changes, 0, "the first lock is not a key change");
+
+    frames.clear();
+    for chord in c_major.iter().cycle().take(24) {
+        let shifted = chord.map(|hz| hz * tritone);
+        frames.extend(chord_frames(sr, 1.0, &shifted));
+    }
+    analyzer.process(&frames, |a| changes += usize::from(a.features().key_change == 1.0));
+    let f = analyzer.features();
+    assert_eq!((f.key, f.key_minor), (6, false), "expected F# major");
+    assert!(changes >= 1, "key change never flagged");
+}
+
+#[test]
+fn file_source_records_every_feature_frame() {
+    let wav = temp_path("record.wav");
+    let log = temp_path("record.bvfr");
+    write_wav_i16(&wav, 48_000, 1, &tone_i16(48_000, 0.4, 220.0, 0.5));
+
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "file",
+        "--input",
+        wav.to_str().expect("utf-8 temp path"),
+        "--record-features",
+        log.to_str().expect("utf-8 temp path"),
+    ])
+    .expect("parse should succeed");
+    let audio = AudioSystem::new(&cfg).expect("file source should start");
+    std::thread::sleep(Duration::from_millis(900));
+    drop(audio);
+
+    let rec = FeatureRecording::load(&log).expect("recording should load");
+    let _ = std::fs::remove_file(&wav);
+    let _ = std::fs::remove_file(&log);
+
+    assert_eq!(rec.sample_rate_hz, 48_000);
+    // 0.4 s of audio at the default 1024/256 window: one frame per hop after the first window.
+    let hops = (48_000 * 4 / 10 - 1024) / 256 + 1;
+    assert_eq!(rec.frames.len(), hops);
+    assert!(rec.frames.windows(2).all(|w| w[0].0 <= w[1].0));
+    assert!(rec.frames.iter().all(|(_, f)| f.band_count == 8));
+    assert!(rec.frames.iter().any(|(_, f)| f.rms > 0.05));
+}
+
+#[test]
+fn replay_source_plays_frames_back_on_their_timeline() {
+    let log = temp_path("replay.bvfr");
+    let mut recorder = FeatureRecorder::create(&log, 44_100).expect("create recording");
+    let frames = (0..4)
+        .map(|i| tui_visualizer::audio::AudioFeatures {
+            rms: 0.1 * (i + 1) as f32,
+            beat: i == 2,
+            bpm: 120.0,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    for f in &frames {
+        recorder.record(f).expect("record frame");
+        std::thread::sleep(Duration::from_millis(120));
+    }
+    recorder.flush().expect("flush");
+    drop(recorder);
+
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "replay",
+        "--input",
+        log.to_str().expect("utf-8 temp path"),
+    ])
+    .expect("parse should succeed");
+    let audio = AudioSystem::new(&cfg).expect("replay source should start");
+    assert_eq!(audio.sample_rate_hz, 44_100);
+    let features = audio.features();
+
+    let start = Instant::now();
+    let mut seen = Vec::<(f32, tui_visualizer::audio::AudioFeatures)>::new();
+    while start.elapsed() < Duration::from_millis(700) {
+        let f = features.load();
+        if f.rms > 0.0 && seen.last().map(|(_, last)| *last != f).unwrap_or(true) {
+            seen.push((start.elapsed().as_secs_f32(), f));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    drop(audio);
+    let _ = std::fs::remove_file(&log);
+
+    let values = seen.iter().map(|(_, f)| *f).collect::<Vec<_>>();
+    assert_eq!(values, frames);
+    // The last frame was recorded ~360 ms in and must not arrive early.
+    let last_at = seen.last().expect("frames replayed").0;
+    assert!((0.3..0.55).contains(&last_at), "last frame at {last_at:.3}s");
+}
+
+#[test]
+fn feature_recordings_skip_unknown_fields_and_reject_truncation() {
+    let log = temp_path("compat.bvfr");
+    let mut recorder = FeatureRecorder::create(&log, 48_000).expect("create recording");
+    let f = tui_visualizer::audio::AudioFeatures {
+        rms: 0.5,
+        gain: 4.0,
+        ..Default::default()
+    };
+    recorder.record(&f).expect("record frame");
+    recorder.flush().expect("flush");
+    drop(recorder);
+    let bytes = std::fs::read(&log).expect("read recording");
+    let _ = std::fs::remove_file(&log);
+
+    let rec = FeatureRecording::parse(&bytes).expect("parse");
+    assert_eq!(rec.frames.len(), 1);
+    assert_eq!(rec.frames[0].1, f);
+
+    // A newer writer may append fields to each record.
+    let mut newer = bytes.clone();
+    let len = u16::from_le_bytes([newer[12], newer[13]]) + 4;
+    newer[12..14].copy_from_slice(&len.to_le_bytes());
+    newer.extend_from_slice(&7.0f32.to_le_bytes());
+    assert_eq!(FeatureRecording::parse(&newer).expect("parse newer").frames, rec.frames);
+
+    assert!(FeatureRecording::parse(&bytes[..bytes.len() - 3]).is_err());
+    assert!(FeatureRecording::parse(b"RIFF\0\0\0\0WAVE").is_err());
+}
+
+#[test]
+fn replay_source_requires_input_path() {
+    let cfg = Config::try_parse_from(["tui-visualizer", "--source", "replay"])
+        .expect("parse should succeed");
+    let err = AudioSystem::new(&cfg).err().expect("missing --input must fail");
+    assert!(err.to_string().contains("--input"));
+}
+
+/// Beats and beat-phase wraps over `seconds` of a pattern stepped at 200 Hz.
+fn synth_counts(pattern: SynthPattern, bpm: f32, seconds: f32) -> (usize, usize, Vec<f32>) {
+    let mut synth = PatternSynth::new(pattern, bpm);
+    let (mut beats, mut wraps, mut last_phase) = (0, 0, 0.0);
+    let mut kicks = Vec::new();
+    for i in 0..(seconds * 200.0) as usize {
+        let t = i as f32 / 200.0;
+        let f = synth.step(t);
+        if f.beat {
+            beats += 1;
+            if f.kick > 0.5 {
+                kicks.push(t);
+            }
+        }
+        if f.beat_phase + 0.5 < last_phase {
+            wraps += 1;
+        }
+        last_phase = f.beat_phase;
+    }
+    (beats, wraps, kicks)
+}
+
+#[test]
+fn synth_patterns_follow_the_tempo() {
+    // 120 BPM for 4 s: eight quarter notes, the first at t = 0 before any step.
+    let (beats, wraps, kicks) = synth_counts(SynthPattern::FourOnTheFloor, 120.0, 4.0);
+    assert_eq!(beats, 7);
+    assert_eq!(wraps, 7);
+    for pair in kicks.windows(2) {
+        assert!((pair[1] - pair[0] - 0.5).abs() < 0.011, "kicks {kicks:?}");
+    }
+
+    let (breaks, _, _) = synth_counts(SynthPattern::Breakbeat, 120.0, 4.0);
+    assert!(breaks > beats, "breakbeat should hit more often than four-on-the-floor");
+
+    let (ambient, ambient_wraps, _) = synth_counts(SynthPattern::Ambient, 120.0, 4.0);
+    assert_eq!((ambient, ambient_wraps), (0, 0));
+    assert_eq!(PatternSynth::new(SynthPattern::Ambient, 120.0).step(1.0).bpm, 0.0);
+
+    // Sections: calm for the first eight bars, full energy in the last eight.
+    let mut synth = PatternSynth::new(SynthPattern::Sections, 120.0);
+    let calm = synth.step(4.0);
+    let drop = synth.step(3.0 * 16.0 + 0.01);
+    assert!(!calm.beat && calm.bpm == 0.0);
+    assert!(drop.kick > 0.5 && drop.rms > calm.rms);
+}
+
+#[test]
+fn synth_source_publishes_features_and_snapshot() {
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--source",
+        "synth",
+        "--synth-pattern",
+        "four-on-the-floor",
+        "--synth-bpm",
+        "100",
+    ])
+    .expect("parse should succeed");
+    let audio = AudioSystem::new(&cfg).expect("synth source should start");
+    std::thread::sleep(Duration::from_millis(300));
+    let f = audio.features().load();
+    let mut snapshot = AudioSnapshot::default();
+    audio.snapshot().load_into(&mut snapshot);
+    drop(audio);
+
+    assert_eq!(f.bpm, 100.0);
+    assert!(f.rms > 0.0);
+    assert_eq!(snapshot.waveform.len(), ANALYZER_WINDOW);
+    assert!(snapshot.spectrum.iter().any(|&v| v > 0.05), "synthetic spectrum never published");
+
+    let cfg = Config::try_parse_from(["tui-visualizer", "--source", "synth", "--synth-bpm", "500"])
+        .expect("parse should succeed");
+    assert!(AudioSystem::new(&cfg).is_err());
+}
+
+static OPENED_DEVICES: Mutex<Vec<(Option<String>, bool)>> = Mutex::new(Vec::new());
+static FAILING_OPENS: AtomicUsize = AtomicUsize::new(0);
+
+/// Stands in for a flaky capture device: records which device (and whether a
+/// channel subset) was asked for and fails while `FAILING_OPENS` is non-zero.
+fn flaky_open(cfg: &Config) -> anyhow::Result<AudioSystem> {
+    OPENED_DEVICES
+        .lock()
+        .unwrap()
+        .push((cfg.device.clone(), cfg.capture.capture_channels.is_some()));
+    if FAILING_OPENS.load(Ordering::Relaxed) > 0 {
+        FAILING_OPENS.fetch_sub(1, Ordering::Relaxed);
+        anyhow::bail!("device unavailable");
+    }
+    let mut cfg = cfg.clone();
+    cfg.source = AudioSource::Synth;
+    AudioSystem::new(&cfg)
+}
+
+/// Polls at `now` until the reopen running on the audio thread has finished.
+fn settle(sup: &mut AudioSupervisor, now: Instant) -> Option<SupervisorEvent> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while sup.is_reopening() && Instant::now() < deadline {
+        if let Some(event) = sup.poll(now) {
+            return Some(event);
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    None
+}
+
+#[test]
+fn supervisor_reopens_the_same_then_default_device_after_a_stream_error() {
+    let path = temp_path("supervised.bvfr");
+    let cfg = Config::try_parse_from([
+        "tui-visualizer",
+        "--device",
+        "usb",
+        "--capture-channels",
+        "1",
+        "--record-features",
+        path.to_str().unwrap(),
+    ])
+    .expect("parse should succeed");
+    let mut sup = AudioSupervisor::with_opener(&cfg, flaky_open).expect("initial open");
+    let opened_at = Instant::now();
+    let first = sup.features();
+    let t0 = Instant::now();
+    assert_eq!(sup.poll(t0), None, "healthy stream should not report anything");
+
+    sup.stream_fault().expect("stream running").raise("device unplugged");
+    assert_eq!(sup.poll(t0), Some(SupervisorEvent::Lost("device unplugged".into())));
+    assert!(sup.is_down());
+    assert_eq!(sup.outages(), 1);
+
+    // Nothing is retried before the reopen delay; then the named device and the
+    // default both fail once each before the named device comes back.
+    FAILING_OPENS.store(2, Ordering::Relaxed);
+    assert_eq!(sup.poll(t0 + Duration::from_millis(500)), None);
+    assert!(!sup.is_reopening());
+    assert_eq!(sup.poll(t0 + Duration::from_millis(1100)), None, "reopen runs off-thread");
+    assert!(sup.is_reopening());
+    assert_eq!(
+        settle(&mut sup, t0 + Duration::from_millis(1100)),
+        Some(SupervisorEvent::ReconnectFailed(
+            "device unavailable; default device: device unavailable".into()
+        ))
+    );
+    assert!(sup.is_down());
+    assert_eq!(sup.poll(t0 + Duration::from_millis(2000)), None, "retries are spaced out");
+    assert!(!sup.is_reopening());
+    assert_eq!(sup.poll(t0 + Duration::from_millis(3200)), None);
+    assert_eq!(
+        settle(&mut sup, t0 + Duration::from_millis(3200)),
+        Some(SupervisorEvent::Reconnected("usb".into()))
+    );
+    let reconnected_at = Instant::now();
+    assert!(!sup.is_down());
+    assert!(!Arc::ptr_eq(&first, &sup.features()), "reconnect should hand out new features");
+    // The channel subset only applies to the named device.
+    assert_eq!(
+        *OPENED_DEVICES.lock().unwrap(),
+        [
+            (Some("usb".to_string()), true),
+            (Some("usb".to_string()), true),
+            (None, false),
+            (Some("usb".to_string()), true),
+        ]
+    );
+
+    // A named device that stays gone falls back to the default.
+    sup.stream_fault().expect("stream running").raise("device unplugged");
+    let t1 = t0 + Duration::from_secs(10);
+    assert!(matches!(sup.poll(t1), Some(SupervisorEvent::Lost(_))));
+    FAILING_OPENS.store(1, Ordering::Relaxed);
+    assert_eq!(sup.poll(t1 + Duration::from_secs(1)), None);
+    assert_eq!(
+        settle(&mut sup, t1 + Duration::from_secs(1)),
+        Some(SupervisorEvent::Reconnected("default device".into()))
+    );
+    assert_eq!(sup.outages(), 2);
+
+    // The recording carries on across both reopens on its original timeline.
+    std::thread::sleep(Duration::from_millis(100));
+    drop(sup);
+    let recording = FeatureRecording::load(&path).expect("recording should load");
+    let _ = std::fs::remove_file(&path);
+    let (last_us, _) = *recording.frames.last().expect("frames recorded");
+    assert!(
+        last_us > (reconnected_at - opened_at).as_micros() as u64,
+        "recording stopped at {last_us} us, before the reconnect"
+    );
+    assert!(recording.frames.windows(2).all(|w| w[0].0 <= w[1].0));
+}
+
+/// A kick-and-tone loop with a little hiss, scaled to `level` (peak amplitude).
+fn mix_fixture(sr: u32, seconds: f32, level: f32) -> Vec<[f32; 2]> {
+    let mut rng = fastrand::Rng::with_seed(3);
+    (0..(sr as f32 * seconds) as usize)
+        .map(|i| {
+            let t = i as f32 / sr as f32;
+            let beat = (t * 2.0).fract();
+            let kick = (-beat * 18.0).exp() * (t * 55.0 * std::f32::consts::TAU).sin();
+            let tone = (t * 440.0 * std::f32::consts::TAU).sin() * 0.25;
+            let hiss = (rng.f32() * 2.0 - 1.0) * 0.05;
+            let v = (kick * 0.7 + tone + hiss) * level;
+            [v, v]
+        })
+        .collect()
+}
+
+fn feature_peaks(cfg: AnalyzerConfig, frames: &[[f32; 2]]) -> (f32, f32) {
+    let mut analyzer = Analyzer::new(cfg, 48_000).expect("analyzer");
+    let (mut rms, mut band) = (0.0f32, 0.0f32);
+    analyzer.process(frames, |a| {
+        let f = a.features();
+        rms = rms.max(f.rms);
+        band = f.bands8().iter().fold(band, |m, b| m.max(*b));
+    });
+    (rms, band)
+}
+
+#[test]
+fn analyzer_levels_for_quiet_and_loud_input() {
+    let sr = 48_000;
+    let loud = mix_fixture(sr, 4.0, 0.9);
+    let quiet = mix_fixture(sr, 4.0, 0.02);
+    let fixed = AnalyzerConfig::default();
+    let agc = AnalyzerConfig {
+        agc: true,
+        ..AnalyzerConfig::default()
+    };
+
+    // Fixed gain: levels track the input, so quiet material stays near the floor.
+    let (rms, band) = feature_peaks(fixed.clone(), &loud);
+    assert!(rms > 0.15 && band > 0.3, "fixed loud rms {rms} band {band}");
+    let (rms, band) = feature_peaks(fixed, &quiet);
+    assert!(rms < 0.02 && band < 0.05, "fixed quiet rms {rms} band {band}");
+
+    // AGC: both land in the same working range; followed RMS peaks sit near 0.5.
+    let (loud_rms, loud_band) = feature_peaks(agc.clone(), &loud);
+    let (quiet_rms, quiet_band) = feature_peaks(agc.clone(), &quiet);
+    for (rms, band) in [(loud_rms, loud_band), (quiet_rms, quiet_band)] {
+        assert!((0.1..=0.55).contains(&rms), "agc rms {rms}");
+        assert!((0.2..=1.0).contains(&band), "agc band {band}");
+    }
+
+    // A pause below the silence threshold holds the gain instead of lifting hiss.
+    let mut analyzer = Analyzer::new(agc, sr).expect("analyzer");
+    analyzer.process(&loud, |_| {});
+    let hiss = (0..sr as usize * 12)
+        .map(|i| {
+            let v = ((i as f32) * 0.91).sin() * 1.3e-3;
+            [v, v]
+        })
+        .collect::<Vec<_>>();
+    analyzer.process(&hiss, |_| {});
+    let f = analyzer.features();
+    assert!(f.silent && f.rms < 0.005, "hiss read as rms {}", f.rms);
+}
@@ -1,17 +1,26 @@
+use clap::Parser;
 use tui_visualizer::audio::AudioFeatures;
-use tui_visualizer::control_matrix::{ControlMatrix, ControlMatrixError, ControlState};
-use tui_visualizer::preset_graph::{GraphOp, PresetGraph, PresetGraphError};
+use tui_visualizer::config::Config;
+use tui_visualizer::control_matrix::{
+    preset_param_control, ControlMatrix, ControlMatrixError, ControlState,
+};
+use tui_visualizer::idle::{dim_rgba, idle_features, IdleMode};
+use std::time::{Duration, Instant};
+use tui_visualizer::preset_file::{PresetDefinition, PresetFileError};
+use tui_visualizer::preset_graph::{GraphOp, PresetGraph, PresetGraphError, PresetGraphWalker};
+use tui_visualizer::preset_id::{preset_slug, PresetRef};
 use tui_visualizer::theme_pack::{ThemePackError, ThemePackManifest};
 
 fn sample_audio() -> AudioFeatures {
     AudioFeatures {
         rms: 1.0,
-        bands: [0.9, 0.8, 0.7, 0.4, 0.3, 0.2, 0.1, 0.0],
+        bands: AudioFeatures::pad_bands(&[0.9, 0.8, 0.7, 0.4, 0.3, 0.2, 0.1, 0.0]),
         onset: 0.65,
         beat: true,
         beat_strength: 0.9,
         centroid: 0.45,
         flatness: 0.25,
+        ..AudioFeatures::default()
     }
 }
 
@@ -32,6 +41,36 @@ fn preset_graph_parses_and_compiles() {
     assert_eq!(ir.adjacency[0][0].op, GraphOp::OnBeat);
 }
 
+#[test]
+fn preset_graph_resolves_preset_ids_against_the_registry() {
+    let text = r#"
+        node intro heatmap-smoke
+        node drop 0
+        edge intro drop always
+    "#;
+    let graph = PresetGraph::parse(text).expect("graph parse should succeed");
+    let ids = ["neon-grid-warp", "heatmap-smoke"].map(String::from);
+    let ir = graph.compile_with(&ids).expect("ids and in-range indices resolve");
+    assert_eq!(ir.nodes[0].preset_index, 1);
+    assert_eq!(ir.nodes[1].preset_index, 0);
+
+    let err = graph.compile().expect_err("ids need the registry");
+    assert!(matches!(err, PresetGraphError::UnresolvedPreset { ref node, .. } if node == "intro"));
+
+    let stale = PresetGraph::parse("node intro renamed-preset\nnode out 5")
+        .expect("graph parse should succeed");
+    let err = stale.compile_with(&ids).expect_err("unknown id should fail");
+    assert_eq!(
+        err,
+        PresetGraphError::UnresolvedPreset {
+            node: "intro".to_string(),
+            preset: "renamed-preset".to_string(),
+        }
+    );
+    let err = PresetGraph::parse("node out 5").unwrap().compile_with(&ids).expect_err("out of range");
+    assert!(err.to_string().contains("unknown preset '5'"), "{err}");
+}
+
 #[test]
 fn preset_graph_compile_rejects_unknown_refs() {
     let text = r#"
@@ -82,6 +121,173 @@ fn preset_graph_parse_rejects_out_of_range_chance() {
     assert!(matches!(err, PresetGraphError::Parse { .. }));
 }
 
+#[test]
+fn preset_graph_walker_follows_satisfied_edges_after_dwell() {
+    let text = r#"
+        node intro 0
+        node drop 3
+        node outro 7
+        edge intro drop on_beat
+        edge drop outro rms_ge 0.50
+    "#;
+    let ir = PresetGraph::parse(text)
+        .and_then(|g| g.compile())
+        .expect("graph should compile");
+    let mut walker = PresetGraphWalker::new(ir);
+    let dwell = Duration::from_secs(2);
+    let start = Instant::now();
+
+    let quiet = AudioFeatures::default();
+    assert_eq!(walker.step(start, &sample_audio(), dwell), None);
+    assert_eq!(walker.step(start + Duration::from_secs(1), &sample_audio(), dwell), None);
+    assert_eq!(walker.step(start + Duration::from_secs(3), &quiet, dwell), None);
+    assert_eq!(walker.step(start + Duration::from_secs(3), &sample_audio(), dwell), Some(3));
+    assert_eq!(walker.current().id, "drop");
+
+    assert_eq!(walker.step(start + Duration::from_secs(4), &sample_audio(), dwell), None);
+    assert_eq!(walker.step(start + Duration::from_secs(6), &sample_audio(), dwell), Some(7));
+    // Terminal nodes loop back to the entry node.
+    assert_eq!(walker.step(start + Duration::from_secs(9), &quiet, dwell), Some(0));
+}
+
+#[test]
+fn preset_graph_tempo_ops_need_a_tempo_lock() {
+    let text = r#"
+        node a 0
+        node b 1
+        node c 2
+        edge a b on_bar
+        edge b c bpm_ge 120
+    "#;
+    let ir = PresetGraph::parse(text)
+        .and_then(|g| g.compile())
+        .expect("graph should compile");
+    assert_eq!(ir.adjacency[0][0].op, GraphOp::OnBar);
+    assert_eq!(ir.adjacency[1][0].op, GraphOp::BpmGe(120.0));
+
+    let mut audio = AudioFeatures::default();
+    assert!(!GraphOp::OnBar.matches(&audio));
+    assert!(!GraphOp::BpmGe(0.0).matches(&audio));
+
+    audio.bpm = 124.0;
+    audio.bar_phase = 0.02;
+    assert!(GraphOp::OnBar.matches(&audio));
+    assert!(GraphOp::BpmGe(120.0).matches(&audio));
+    audio.bar_phase = 0.4;
+    assert!(!GraphOp::OnBar.matches(&audio));
+    assert!(!GraphOp::BpmGe(128.0).matches(&audio));
+
+    let err = PresetGraph::parse("node a 0\nnode b 1\nedge a b bpm_ge 900")
+        .expect_err("bpm_ge outside [0,400] must fail");
+    assert!(matches!(err, PresetGraphError::Parse { .. }));
+}
+
+#[test]
+fn preset_graph_walker_chance_edges_split_one_roll() {
+    let text = r#"
+        node fork 0
+        node left 1
+        node right 2
+        edge fork left chance 0.6
+        edge fork right chance 0.4
+    "#;
+    let ir = PresetGraph::parse(text)
+        .and_then(|g| g.compile())
+        .expect("graph should compile");
+    let now = Instant::now();
+    let mut lefts = 0;
+    for _ in 0..400 {
+        let mut walker = PresetGraphWalker::new(ir.clone());
+        walker.enter(0, now);
+        match walker.select_next(&AudioFeatures::default()) {
+            Some(1) => lefts += 1,
+            Some(2) => {}
+            other => panic!("complementary chance edges must always fire, got {other:?}"),
+        }
+    }
+    assert!((160..=320).contains(&lefts), "unexpected branch split: {lefts}/400");
+}
+
+#[test]
+fn preset_graph_walker_rerolls_partial_chance_edges_until_they_fire() {
+    let text = r#"
+        node relic 0
+        node temple 1
+        edge relic temple chance 0.5
+    "#;
+    let ir = PresetGraph::parse(text)
+        .and_then(|g| g.compile())
+        .expect("graph should compile");
+    let dwell = Duration::from_secs(2);
+    for seed in 0..32 {
+        fastrand::seed(seed);
+        let mut walker = PresetGraphWalker::new(ir.clone());
+        let mut now = Instant::now();
+        let mut advanced = false;
+        // A minute of quiet frames: no beats, so the roll is redrawn per dwell.
+        for _ in 0..600 {
+            if walker.step(now, &AudioFeatures::default(), dwell) == Some(1) {
+                advanced = true;
+                break;
+            }
+            now += Duration::from_millis(100);
+        }
+        assert!(advanced, "seed {seed}: walker stuck on a 0.5 chance node");
+    }
+}
+
+#[test]
+fn seeded_graph_walkers_replay_the_same_chance_branches() {
+    let text = r#"
+        node fork 0
+        node left 1
+        node right 2
+        edge fork left chance 0.5
+        edge fork right chance 0.5
+    "#;
+    let ir = PresetGraph::parse(text)
+        .and_then(|g| g.compile())
+        .expect("graph should compile");
+    let walk = |interleave: bool| {
+        fastrand::seed(0x5EED);
+        let mut walker = PresetGraphWalker::new(ir.clone());
+        let mut now = Instant::now();
+        let mut visited = Vec::new();
+        for _ in 0..64 {
+            now += Duration::from_millis(10);
+            if interleave {
+                // Draws elsewhere on the global RNG must not shift the walk.
+                fastrand::u64(..);
+            }
+            if let Some(preset) = walker.step(now, &AudioFeatures::default(), Duration::ZERO) {
+                visited.push(preset);
+            }
+        }
+        visited
+    };
+    let first = walk(false);
+    assert_eq!(first, walk(true));
+    assert!(first.contains(&1) && first.contains(&2), "{first:?}");
+}
+
+#[test]
+fn preset_graph_walker_resyncs_to_external_preset() {
+    let text = r#"
+        node a 4
+        node b 9
+        edge a b always
+    "#;
+    let ir = PresetGraph::parse(text)
+        .and_then(|g| g.compile())
+        .expect("graph should compile");
+    let mut walker = PresetGraphWalker::new(ir);
+    let now = Instant::now();
+    assert!(walker.sync_to_preset(9, now));
+    assert_eq!(walker.current().id, "b");
+    assert!(!walker.sync_to_preset(5, now));
+    assert_eq!(walker.current().id, "b");
+}
+
 #[test]
 fn control_matrix_routes_and_clamps() {
     let text = r#"
@@ -98,6 +304,22 @@ fn control_matrix_routes_and_clamps() {
     assert!((0.0..=1.0).contains(&hue));
 }
 
+#[test]
+fn control_matrix_routes_preset_parameters() {
+    let text = "route preset.feedback.zoom bass linear 1.0 2.0 0.5 0.5 2.5";
+    let matrix = ControlMatrix::parse(text).expect("matrix parse should succeed");
+    let route = &matrix.routes()[0];
+    assert_eq!(preset_param_control(&route.control), Some("feedback.zoom"));
+    assert_eq!(preset_param_control("zoom"), None);
+    let out = matrix.evaluate(&sample_audio(), &mut ControlState::default());
+    let zoom = out["preset.feedback.zoom"];
+    assert!((0.5..=2.5).contains(&zoom));
+
+    let err = ControlMatrix::parse("route preset. bass linear 1.0 1.0 0.0 0.0 1.0")
+        .expect_err("empty parameter name should fail");
+    assert!(matches!(err, ControlMatrixError::Parse { line: 1, .. }));
+}
+
 #[test]
 fn control_matrix_applies_smoothing() {
     let text = "route zoom rms linear 0.5 1.0 0.0 0.0 1.0";
@@ -123,6 +345,140 @@ fn control_matrix_applies_smoothing() {
     assert!((second - 0.5).abs() < 1e-6);
 }
 
+#[test]
+fn control_matrix_routes_tempo_features() {
+    let text = r#"
+        route pulse beat_phase linear 1.0 1.0 0.0 0.0 1.0
+        route sweep bar_phase linear 1.0 1.0 0.0 0.0 1.0
+        route pace bpm linear 1.0 1.0 0.0 0.0 1.0
+    "#;
+    let matrix = ControlMatrix::parse(text).expect("matrix parse should succeed");
+
+    let mut audio = sample_audio();
+    audio.bpm = 130.0;
+    audio.beat_phase = 0.25;
+    audio.bar_phase = 0.5625;
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert!((out["pulse"] - 0.25).abs() < 1e-6);
+    assert!((out["sweep"] - 0.5625).abs() < 1e-6);
+    assert!((out["pace"] - 0.5).abs() < 1e-6);
+
+    let unlocked = matrix.evaluate(&AudioFeatures::default(), &mut ControlState::default());
+    assert_eq!(unlocked["pace"], 0.0);
+}
+
+#[test]
+fn control_matrix_routes_drum_features() {
+    let text = r#"
+        route zoom kick linear 1.0 1.0 0.0 0.0 1.0
+        route glitch hihat linear 1.0 1.0 0.0 0.0 1.0
+        route flash snare linear 1.0 1.0 0.0 0.0 1.0
+    "#;
+    let matrix = ControlMatrix::parse(text).expect("matrix parse should succeed");
+
+    let mut audio = AudioFeatures::default();
+    audio.kick = 0.9;
+    audio.hat = 0.4;
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert!((out["zoom"] - 0.9).abs() < 1e-6);
+    assert!((out["glitch"] - 0.4).abs() < 1e-6);
+    assert_eq!(out["flash"], 0.0);
+}
+
+#[test]
+fn control_matrix_routes_stereo_features() {
+    let text = r#"
+        route pan balance linear 1.0 1.0 0.0 0.0 1.0
+        route spread width linear 1.0 1.0 0.0 0.0 1.0
+        route left rms_left linear 1.0 1.0 0.0 0.0 1.0
+    "#;
+    let matrix = ControlMatrix::parse(text).expect("matrix parse should succeed");
+
+    let mut audio = AudioFeatures::default();
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert!((out["pan"] - 0.5).abs() < 1e-6, "centred balance should map to 0.5");
+
+    audio.balance = -1.0;
+    audio.stereo_width = 0.6;
+    audio.rms_left = 0.3;
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert_eq!(out["pan"], 0.0);
+    assert!((out["spread"] - 0.6).abs() < 1e-6);
+    assert!((out["left"] - 0.3).abs() < 1e-6);
+}
+
+#[test]
+fn control_matrix_band_keys_follow_configured_count() {
+    let text = r#"
+        route low band0 linear 1.0 1.0 0.0 0.0 1.0
+        route high band12 linear 1.0 1.0 0.0 0.0 1.0
+    "#;
+    let matrix = ControlMatrix::parse(text).expect("matrix parse should succeed");
+    assert!(ControlMatrix::parse("route x band32 linear 1.0 1.0 0.0 0.0 1.0").is_err());
+
+    let mut audio = AudioFeatures::default();
+    audio.bands[0] = 0.5;
+    audio.bands[12] = 0.8;
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert!((out["low"] - 0.5).abs() < 1e-6);
+    assert_eq!(out["high"], 0.0, "band12 is past the default 8-band layout");
+
+    audio.band_count = 16;
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert!((out["high"] - 0.8).abs() < 1e-6);
+}
+
+#[test]
+fn control_matrix_routes_pitch_chroma_and_key_features() {
+    let text = r#"
+        route a chroma9 linear 1.0 1.0 0.0 0.0 1.0
+        route hue pitch_class linear 1.0 1.0 0.0 0.0 1.0
+        route tonic key linear 1.0 1.0 0.0 0.0 1.0
+        route major mode linear 1.0 1.0 0.0 0.0 1.0
+        route next_preset key_change linear 1.0 1.0 0.0 0.0 1.0
+    "#;
+    let matrix = ControlMatrix::parse(text).expect("matrix parse should succeed");
+    assert!(ControlMatrix::parse("route x chroma12 linear 1.0 1.0 0.0 0.0 1.0").is_err());
+
+    let mut audio = AudioFeatures::default();
+    audio.chroma[9] = 0.75;
+    audio.pitch_hz = 440.0;
+    audio.key = 7;
+    audio.key_minor = true;
+    audio.key_change = 1.0;
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert!((out["a"] - 0.75).abs() < 1e-6);
+    assert!((out["hue"] - 9.0 / 12.0).abs() < 1e-6);
+    assert!((out["tonic"] - 7.0 / 12.0).abs() < 1e-6);
+    assert_eq!(out["major"], 0.0);
+    assert_eq!(out["next_preset"], 1.0);
+}
+
+#[test]
+fn control_matrix_routes_loudness_and_crest() {
+    let text = r#"
+        route intensity loudness linear 1.0 1.0 0.0 0.0 1.0
+        route zoom loudness_short linear 1.0 1.0 0.0 0.0 1.0
+        route camera_speed crest linear 1.0 1.0 0.0 0.0 1.0
+    "#;
+    let matrix = ControlMatrix::parse(text).expect("matrix parse should succeed");
+
+    let audio = AudioFeatures {
+        lufs_momentary: -8.0,
+        lufs_short_term: -24.0,
+        crest_db: 10.5,
+        ..AudioFeatures::default()
+    };
+    let out = matrix.evaluate(&audio, &mut ControlState::default());
+    assert_eq!(out["intensity"], 1.0);
+    assert!((out["zoom"] - 0.5).abs() < 1e-6);
+    assert!((out["camera_speed"] - 0.5).abs() < 1e-6);
+
+    // Silence reads as the bottom of every loudness route.
+    let out = matrix.evaluate(&AudioFeatures::default(), &mut ControlState::default());
+    assert_eq!((out["intensity"], out["zoom"], out["camera_speed"]), (0.0, 0.0, 0.0));
+}
+
 #[test]
 fn control_matrix_rejects_duplicate_controls() {
     let text = r#"
@@ -171,7 +527,52 @@ fn theme_pack_parses_manifest() {
     let pack = ThemePackManifest::parse(text).expect("theme pack parse should succeed");
     assert_eq!(pack.name, "Neon Drift");
     assert_eq!(pack.tags, vec!["night".to_string(), "energetic".to_string()]);
-    assert_eq!(pack.preset_indices, vec![1, 5, 8]);
+    assert_eq!(
+        pack.presets,
+        vec![PresetRef::Index(1), PresetRef::Index(5), PresetRef::Index(8)]
+    );
+}
+
+#[test]
+fn theme_pack_accepts_preset_ids_and_reports_unresolved_refs() {
+    let text = r#"
+        name=Mixed Refs
+        presets=julia-cathedral-zoom,2,Lost-Preset,heatmap-smoke
+        transition.min_beats=8
+        transition.max_beats=24
+        transition.crossfade_ms=450
+        defaults.intensity=1.0
+        defaults.zoom=1.0
+    "#;
+    let pack = ThemePackManifest::parse(text).expect("theme pack parse should succeed");
+    assert_eq!(pack.presets[0], PresetRef::Id("julia-cathedral-zoom".to_string()));
+    assert_eq!(pack.presets[2], PresetRef::Id("lost-preset".to_string()));
+    assert_eq!(ThemePackManifest::parse(&pack.to_text()).expect("round trip"), pack);
+
+    let ids = ["heatmap-smoke", "julia-cathedral-zoom", "neon-grid-warp"].map(String::from);
+    let (indices, unresolved) = pack.resolve_presets(&ids);
+    assert_eq!(indices, vec![1, 2, 0]);
+    assert_eq!(unresolved, vec![PresetRef::Id("lost-preset".to_string())]);
+
+    let dup = text.replace("2,Lost-Preset", "heatmap-smoke");
+    let err = ThemePackManifest::parse(&dup).expect_err("duplicate preset id should fail");
+    assert!(matches!(err, ThemePackError::DuplicatePresetId(id) if id == "heatmap-smoke"));
+    let bad = text.replace("Lost-Preset", "lost preset!");
+    assert!(matches!(
+        ThemePackManifest::parse(&bad),
+        Err(ThemePackError::Parse { .. })
+    ));
+}
+
+#[test]
+fn preset_slugs_are_stable_lowercase_ids() {
+    assert_eq!(preset_slug("Mandelbrot: Bass Zoom"), "mandelbrot-bass-zoom");
+    assert_eq!(preset_slug("Particle Fountain: Hi-hat Sparks"), "particle-fountain-hi-hat-sparks");
+    assert_eq!(preset_slug("  CRT -- Scanlines!  "), "crt-scanlines");
+    assert_eq!(PresetRef::parse(" 12 "), Some(PresetRef::Index(12)));
+    assert_eq!(PresetRef::parse("Heatmap-Smoke"), Some(PresetRef::Id("heatmap-smoke".to_string())));
+    assert_eq!(PresetRef::parse("heatmap smoke"), None);
+    assert_eq!(PresetRef::parse(""), None);
 }
 
 #[test]
@@ -243,3 +644,134 @@ fn theme_pack_rejects_unknown_key() {
     let err = ThemePackManifest::parse(text).expect_err("unknown key should fail");
     assert!(matches!(err, ThemePackError::Parse { .. }));
 }
+
+#[test]
+fn preset_file_parses_and_round_trips() {
+    let text = r#"
+        # custom dive
+        name=Deep Julia
+        algo=Julia_Deep
+        algo.c_base=-0.7, 0.27
+        algo.speed=0.6
+        palette=Cosmic
+        feedback.fade=0.95
+        postfx.scanline=0
+        category=Fractal
+        energy=low
+        tags=dive, slow
+    "#;
+    let def = PresetDefinition::parse(text).expect("preset file parse should succeed");
+    assert_eq!(def.name, "Deep Julia");
+    assert_eq!(def.algo, "julia_deep");
+    assert_eq!(def.palette, "cosmic");
+    assert_eq!(
+        def.params,
+        vec![
+            ("c_base".to_string(), vec![-0.7, 0.27]),
+            ("speed".to_string(), vec![0.6]),
+        ]
+    );
+    assert!(def.feedback.enabled);
+    assert_eq!(def.feedback.fade, 0.95);
+    assert_eq!(def.post_fx.scanline, 0.0);
+    assert_eq!(def.post_fx.bloom, 1.0);
+    assert_eq!(def.meta.category.as_deref(), Some("fractal"));
+    assert_eq!(def.meta.energy.as_deref(), Some("low"));
+    assert_eq!(def.meta.motion, None);
+    assert_eq!(def.meta.tags, vec!["dive".to_string(), "slow".to_string()]);
+
+    let again = PresetDefinition::parse(&def.to_text()).expect("round trip should parse");
+    assert_eq!(again, def);
+}
+
+#[test]
+fn preset_file_requires_algo_and_palette() {
+    let err = PresetDefinition::parse("name=Nothing\npalette=neon").expect_err("missing algo should fail");
+    assert!(matches!(err, PresetFileError::MissingField("algo")));
+    let err = PresetDefinition::parse("name=Nothing\nalgo=plasma").expect_err("missing palette should fail");
+    assert!(matches!(err, PresetFileError::MissingField("palette")));
+}
+
+#[test]
+fn preset_file_rejects_bad_lines_and_values() {
+    let base = "name=Bad\nalgo=plasma\npalette=neon\n";
+    for (extra, line) in [
+        ("shader=glsl", 4),
+        ("feedback=spiral", 4),
+        ("algo.freq=fast", 4),
+        ("palette=acid", 4),
+    ] {
+        let err = PresetDefinition::parse(&format!("{base}{extra}")).expect_err(extra);
+        assert!(
+            matches!(err, PresetFileError::Parse { line: l, .. } if l == line),
+            "{extra}: {err}"
+        );
+    }
+
+    let err = PresetDefinition::parse(&format!("{base}postfx.bloom=3")).expect_err("bloom out of range");
+    assert!(matches!(
+        err,
+        PresetFileError::InvalidValue {
+            field: "postfx.bloom",
+            ..
+        }
+    ));
+    let err = PresetDefinition::parse(&format!("{base}feedback.zoom=0")).expect_err("zero zoom");
+    assert!(matches!(
+        err,
+        PresetFileError::InvalidValue {
+            field: "feedback.zoom",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn idle_mode_dims_rotates_and_resumes_on_sound() {
+    let start = Instant::now();
+    let mut idle = IdleMode::new(0.5, 10.0);
+    assert!(!idle.update(start, false));
+    assert_eq!(idle.brightness(start), 1.0);
+
+    assert!(idle.update(start, true));
+    assert!(!idle.attract_due(start + Duration::from_secs(9)));
+    assert!(idle.attract_due(start + Duration::from_secs(10)));
+    assert!(!idle.attract_due(start + Duration::from_secs(15)));
+    assert!(idle.attract_due(start + Duration::from_secs(21)));
+    assert!((idle.brightness(start + Duration::from_secs(10)) - 0.5).abs() < 1e-6);
+
+    let back = start + Duration::from_secs(30);
+    assert!(!idle.update(back, false));
+    assert_eq!(idle.brightness(back), 1.0);
+    assert!(!idle.attract_due(back + Duration::from_secs(60)));
+
+    let mut never = IdleMode::new(0.0, 0.0);
+    never.update(start, true);
+    assert!(!never.attract_due(start + Duration::from_secs(3600)));
+    assert_eq!(never.brightness(start + Duration::from_secs(60)), 1.0);
+}
+
+#[test]
+fn idle_and_agc_are_opt_in() {
+    let cfg = Config::try_parse_from(["tui-visualizer"]).expect("defaults parse");
+    assert!(!cfg.idle, "idle/attract mode must not change the default behavior");
+    assert!(!cfg.analyzer.agc);
+
+    let cfg = Config::try_parse_from(["tui-visualizer", "--idle", "true", "--agc", "true"])
+        .expect("opt-in flags parse");
+    assert!(cfg.idle && cfg.analyzer.agc);
+}
+
+#[test]
+fn idle_features_drift_without_beats() {
+    let a = idle_features(0.0);
+    let b = idle_features(7.5);
+    assert!(a.silent && b.silent);
+    assert!(!a.beat && !b.beat && a.bpm == 0.0);
+    assert_ne!(a.bands, b.bands, "idle features should move over time");
+    assert!(a.bands8().iter().all(|v| (0.0..=1.0).contains(v)));
+
+    let mut px = vec![200u8, 100, 50, 255];
+    dim_rgba(&mut px, 0.5);
+    assert_eq!(px, [100, 50, 25, 255]);
+}
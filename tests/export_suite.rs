@@ -4,6 +4,7 @@ mod export_video;
 
 use clap::Parser;
 use std::path::PathBuf;
+use tui_visualizer::audio::{Analyzer, AnalyzerConfig};
 
 #[test]
 fn parse_args_defaults_are_stable() {
@@ -17,6 +18,7 @@ fn parse_args_defaults_are_stable() {
     assert_eq!(args.fps, 60);
     assert_eq!(args.duration, None);
     assert_eq!(args.engine, export_video::EngineArg::Metal);
+    assert_eq!(args.seed, 0xF15D_2026);
 }
 
 #[test]
@@ -40,6 +42,8 @@ fn parse_args_overrides_work() {
         "--engine",
         "cpu",
         "--safe",
+        "--seed",
+        "99",
     ])
     .expect("parse should succeed");
 
@@ -52,6 +56,7 @@ fn parse_args_overrides_work() {
     assert_eq!(args.preset.as_deref(), Some("mandelbrot"));
     assert_eq!(args.engine, export_video::EngineArg::Cpu);
     assert!(args.safe);
+    assert_eq!(args.seed, 99);
 }
 
 #[test]
@@ -110,3 +115,82 @@ fn validate_rejects_non_positive_duration_cap() {
     let err = export_video::validate_args(&args).expect_err("duration=0 must fail validation");
     assert!(err.to_string().contains("--duration"));
 }
+
+#[test]
+fn validate_rejects_out_of_range_spectrum_bins() {
+    let args = export_video::Cli::try_parse_from([
+        "export_video",
+        "--audio",
+        "song.wav",
+        "--spectrum-bins",
+        "4",
+    ])
+    .expect("parse should succeed");
+
+    let err = export_video::validate_args(&args).expect_err("spectrum-bins=4 must fail validation");
+    assert!(err.to_string().contains("--spectrum-bins"));
+}
+
+fn sine_frames(sr: u32, seconds: f32, hz: f32) -> Vec<[f32; 2]> {
+    (0..(sr as f32 * seconds) as usize)
+        .map(|i| {
+            let s = (i as f32 * hz * std::f32::consts::TAU / sr as f32).sin() * 0.5;
+            [s, s * 0.5]
+        })
+        .collect()
+}
+
+#[test]
+fn feature_track_carries_per_frame_spectrum_and_waveform() {
+    let sr = 48_000u32;
+    let samples = sine_frames(sr, 1.0, 440.0);
+    let cfg = AnalyzerConfig {
+        spectrum_bins: 24,
+        ..AnalyzerConfig::default()
+    };
+    let track = export_video::build_feature_track(&samples, sr, 30, 30, &cfg)
+        .expect("feature track should build");
+
+    assert_eq!(track.features.len(), 30);
+    assert_eq!(track.window_len, 1024);
+    assert_eq!(track.spectrum(29).len(), 24);
+    assert!(track.spectrum(30).is_empty());
+    assert!(track.spectrum(29).iter().any(|&v| v > 0.1));
+
+    let mut waveform = vec![0.0f32; 1024];
+    let mut side = vec![0.0f32; 1024];
+    track.waveform_into(0, &mut waveform, &mut side);
+    assert!(waveform.iter().all(|&s| s == 0.0), "frame 0 has no history yet");
+    track.waveform_into(15, &mut waveform, &mut side);
+    assert!(waveform.iter().any(|&s| s.abs() > 0.3));
+    assert!(side.iter().any(|&s| s.abs() > 0.1));
+}
+
+#[test]
+fn feature_track_matches_the_shared_analyzer() {
+    let sr = 44_100u32;
+    let samples = sine_frames(sr, 0.5, 220.0);
+    let cfg = AnalyzerConfig::default();
+    let track = export_video::build_feature_track(&samples, sr, 24, 12, &cfg)
+        .expect("feature track should build");
+
+    // Replay the same input through the library analyzer in uneven chunks and
+    // read it at each frame time; the export must see exactly the same values.
+    let mut analyzer = Analyzer::new(cfg, sr).expect("analyzer");
+    let mut fed = 0usize;
+    for (frame, features) in track.features.iter().enumerate() {
+        let end = (frame as f32 / 24.0 * sr as f32).floor() as usize;
+        for chunk in samples[fed..end].chunks(333) {
+            analyzer.process(chunk, |_| {});
+        }
+        fed = end;
+        assert_eq!(analyzer.features(), *features);
+        assert_eq!(analyzer.spectrum(), track.spectrum(frame));
+
+        let mut waveform = vec![0.0f32; track.window_len];
+        let mut side = vec![0.0f32; track.window_len];
+        track.waveform_into(frame, &mut waveform, &mut side);
+        assert_eq!(analyzer.waveform(), &waveform[..]);
+        assert_eq!(analyzer.side(), &side[..]);
+    }
+}
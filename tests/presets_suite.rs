@@ -2,8 +2,12 @@ use std::time::{Duration, Instant};
 
 use tui_visualizer::audio::AudioFeatures;
 use tui_visualizer::config::{Quality, SwitchMode};
+use tui_visualizer::preset_file::PresetDefinition;
+use tui_visualizer::preset_graph::PresetGraph;
+use tui_visualizer::theme_pack::ThemePackManifest;
 use tui_visualizer::visual::{
-    make_presets, CameraPathMode, Preset, PresetEngine, RenderCtx, VisualEngine,
+    check_definition, make_presets, make_presets_with, CameraPathMode, Level, Preset,
+    PresetCategory, PresetEngine, RenderCtx, VisualEngine, BUILTIN_PRESET_IDS,
 };
 
 fn synth_audio(t: f32, step: usize) -> AudioFeatures {
@@ -15,7 +19,7 @@ fn synth_audio(t: f32, step: usize) -> AudioFeatures {
 
     AudioFeatures {
         rms: (0.12 + bass * 0.40 + mid * 0.30 + treb * 0.18).clamp(0.0, 1.0),
-        bands: [
+        bands: AudioFeatures::pad_bands(&[
             (bass * 0.95).clamp(0.0, 1.0),
             bass.clamp(0.0, 1.0),
             (bass * 0.6 + mid * 0.35).clamp(0.0, 1.0),
@@ -24,12 +28,13 @@ fn synth_audio(t: f32, step: usize) -> AudioFeatures {
             treb.clamp(0.0, 1.0),
             (treb * 0.9).clamp(0.0, 1.0),
             (treb * 0.75 + mid * 0.2).clamp(0.0, 1.0),
-        ],
+        ]),
         onset: if hard { 0.90 } else if soft { 0.58 } else { (treb * 0.35).clamp(0.0, 0.5) },
         beat: hard || soft,
         beat_strength: if hard { 0.95 } else if soft { 0.55 } else { 0.0 },
         centroid: (0.18 + treb * 0.62).clamp(0.0, 1.0),
         flatness: (0.12 + treb * 0.58).clamp(0.0, 1.0),
+        ..AudioFeatures::default()
     }
 }
 
@@ -61,6 +66,232 @@ fn preset_count_and_names_are_sane() {
     }
 }
 
+#[test]
+fn preset_ids_are_unique_and_bundled_files_resolve() {
+    let ids = make_presets().iter().map(|p| p.id()).collect::<Vec<_>>();
+    assert_eq!(ids, BUILTIN_PRESET_IDS, "BUILTIN_PRESET_IDS must list make_presets() in order");
+    let unique = ids.iter().collect::<std::collections::HashSet<_>>();
+    assert_eq!(unique.len(), ids.len(), "preset ids must be unique");
+    assert!(ids.iter().all(|id| !id.is_empty()));
+    // Built-in ids are frozen: themes, graphs and playlists refer to them.
+    for id in [
+        "mandelbrot-bass-zoom",
+        "julia-treble-shimmer",
+        "voronoi-shatter-beat-cuts",
+        "spectrum-bars-classic-analyzer",
+        "lissajous-scope-phase-portrait",
+    ] {
+        assert!(ids.iter().any(|i| i == id), "missing built-in id {id}");
+    }
+
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    for entry in std::fs::read_dir(root.join("assets/theme")).expect("assets/theme exists") {
+        let path = entry.expect("dir entry").path();
+        let pack = ThemePackManifest::load(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        let (indices, unresolved) = pack.resolve_presets(&ids);
+        assert!(unresolved.is_empty(), "{}: {unresolved:?}", path.display());
+        assert_eq!(indices.len(), pack.presets.len());
+    }
+    for entry in std::fs::read_dir(root.join("assets/graph")).expect("assets/graph exists") {
+        let path = entry.expect("dir entry").path();
+        PresetGraph::load(&path)
+            .and_then(|g| g.compile_with(&ids))
+            .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    }
+}
+
+#[test]
+fn preset_metadata_covers_every_category() {
+    let presets = make_presets();
+    for category in PresetCategory::ALL {
+        assert_eq!(PresetCategory::from_label(category.label()), Some(category));
+        assert!(
+            presets.iter().any(|p| p.meta().category == category),
+            "no built-in preset in category {}",
+            category.label()
+        );
+    }
+    for p in &presets {
+        let meta = p.meta();
+        assert!(!meta.tags.is_empty(), "preset '{}' has no tags", p.name());
+        let scope_name = [
+            "Spectrum Bars",
+            "Spectrum Mirror",
+            "Spectrum Halo",
+            "Oscilloscope",
+            "Waveform Ring",
+            "Lissajous",
+        ]
+        .iter()
+            .any(|s| p.name().starts_with(s));
+        assert_eq!(meta.category == PresetCategory::Scope, scope_name, "{}", p.name());
+    }
+}
+
+#[test]
+fn adaptive_switching_in_calm_sections_skips_high_energy_presets() {
+    let presets = make_presets();
+    let metas = presets.iter().map(|p| (p.name(), p.meta())).collect::<Vec<_>>();
+    let mut engine = PresetEngine::new(presets, 0, false, SwitchMode::Adaptive, 4, 4.0);
+    engine.resize(16, 12);
+    let mut now = Instant::now();
+    let mut current = engine.preset_name();
+    let mut switches = 0;
+
+    for f in 0..2400 {
+        now += Duration::from_millis(100);
+        let a = section_audio(0.05, 0.05, 0.05, 0.0, false);
+        engine.update_auto_switch(now, &a);
+        let ctx = RenderCtx {
+            now,
+            t: f as f32 * 0.1,
+            dt: 0.1,
+            w: 16,
+            h: 12,
+            audio: a,
+            beat_pulse: 0.0,
+            fractal_zoom_mul: 1.0,
+            safe: false,
+            stereo: false,
+            quality: Quality::Fast,
+            scale: 1,
+            spectrum: &[],
+            waveform: &[],
+            side: &[],
+        };
+        let _ = engine.render(ctx, Quality::Fast, 1);
+        let name = engine.preset_name();
+        if name != current {
+            assert_eq!(engine.scene_section_name(), "Calm");
+            let (_, meta) = metas.iter().find(|(n, _)| *n == name).expect("known preset");
+            assert_ne!(meta.energy, Level::High, "calm section switched to '{name}'");
+            assert_ne!(meta.flash_risk, Level::High, "calm section switched to '{name}'");
+            current = name;
+            switches += 1;
+        }
+    }
+    assert!(switches >= 3, "only {switches} switches in calm audio");
+}
+
+#[test]
+fn preset_params_list_get_and_clamp_to_range() {
+    let mut presets = make_presets();
+    let kaleido = presets
+        .iter_mut()
+        .find(|p| p.name() == "Plasma Kaleidoscope")
+        .expect("kaleidoscope preset");
+    let names = kaleido.params().iter().map(|p| p.name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["freq", "symmetry", "feedback.fade", "feedback.warp", "feedback.warp_freq", "feedback.zoom"]
+    );
+    assert_eq!(kaleido.param("symmetry"), Some(7.0));
+    assert!(kaleido.set_param("symmetry", 4.6));
+    assert_eq!(kaleido.param("symmetry"), Some(5.0));
+    assert!(kaleido.set_param("feedback.fade", 3.0));
+    assert_eq!(kaleido.param("feedback.fade"), Some(1.0));
+    assert!(!kaleido.set_param("points", 4.0));
+    assert_eq!(kaleido.param("points"), None);
+
+    for p in &presets {
+        for param in p.params() {
+            assert!(param.min < param.max, "{}: {}", p.name(), param.name);
+        }
+    }
+
+    let mut engine = PresetEngine::new(make_presets(), 0, false, SwitchMode::Manual, 4, 8.0);
+    let before = engine.preset_params();
+    assert!(!before.is_empty());
+    let fade = before.iter().find(|p| p.name == "feedback.fade").expect("tunnel fade");
+    let tunable = engine.tunable_preset_params();
+    assert!(before.iter().all(|p| tunable.contains(p.name)));
+    assert!(!tunable.contains("no_such_param"));
+    assert!(engine.set_preset_param("feedback.fade", fade.min));
+    assert!(!engine.set_preset_param("no_such_param", 1.0));
+    let after = engine.preset_params();
+    assert_eq!(after.iter().find(|p| p.name == "feedback.fade").map(|p| p.value), Some(fade.min));
+}
+
+#[test]
+fn seeded_runs_repeat_preset_frames_and_shuffle_order() {
+    let render_frames = |seed: u64| {
+        fastrand::seed(seed);
+        let mut presets = make_presets();
+        let (w, h) = (48usize, 32usize);
+        let mut frames = Vec::new();
+        for p in presets.iter_mut().step_by(7) {
+            let mut prev = vec![0u8; w * h * 4];
+            let mut out = vec![0u8; w * h * 4];
+            for f in 0..3usize {
+                let t = f as f32 / 30.0;
+                let ctx = RenderCtx {
+                    now: Instant::now(),
+                    t,
+                    dt: 1.0 / 30.0,
+                    w,
+                    h,
+                    audio: synth_audio(t, f),
+                    beat_pulse: 0.4,
+                    fractal_zoom_mul: 1.0,
+                    safe: false,
+                    stereo: false,
+                    quality: Quality::Fast,
+                    scale: 1,
+                    spectrum: &[],
+                    waveform: &[],
+                    side: &[],
+                };
+                p.render(&ctx, &prev, &mut out);
+                std::mem::swap(&mut prev, &mut out);
+            }
+            frames.push(prev);
+        }
+        frames
+    };
+    assert!(render_frames(42) == render_frames(42), "same seed rendered different frames");
+
+    let switches = |seed: u64| {
+        fastrand::seed(seed);
+        let mut engine = PresetEngine::new(make_presets(), 0, true, SwitchMode::Beat, 1, 8.0);
+        engine.resize(16, 12);
+        // Start well ahead of the wall clock so every transition has finished
+        // by the next frame, whatever the test machine's speed.
+        let mut now = Instant::now() + Duration::from_secs(30);
+        let mut names = vec![engine.preset_name()];
+        for f in 0..240usize {
+            now += Duration::from_millis(200);
+            let a = section_audio(0.6, 0.3, 0.4, 0.2, f % 4 == 0);
+            engine.update_auto_switch(now, &a);
+            let ctx = RenderCtx {
+                now,
+                t: f as f32 * 0.2,
+                dt: 0.2,
+                w: 16,
+                h: 12,
+                audio: a,
+                beat_pulse: 0.0,
+                fractal_zoom_mul: 1.0,
+                safe: false,
+                stereo: false,
+                quality: Quality::Fast,
+                scale: 1,
+                spectrum: &[],
+                waveform: &[],
+                side: &[],
+            };
+            let _ = engine.render(ctx, Quality::Fast, 1);
+            if names.last() != Some(&engine.preset_name()) {
+                names.push(engine.preset_name());
+            }
+        }
+        names
+    };
+    let first = switches(7);
+    assert!(first.len() > 10, "only {} presets visited", first.len());
+    assert_eq!(first, switches(7));
+    assert_ne!(first, switches(8), "different seeds shuffled identically");
+}
+
 #[test]
 fn every_preset_renders_non_black_frames() {
     let mut presets: Vec<Box<dyn Preset>> = make_presets();
@@ -86,8 +317,12 @@ fn every_preset_renders_non_black_frames() {
                 beat_pulse: if audio.beat { 0.9 } else { 0.0 },
                 fractal_zoom_mul: 1.0,
                 safe: false,
+                stereo: false,
                 quality: Quality::Balanced,
                 scale: 1,
+                spectrum: &[],
+                waveform: &[],
+                side: &[],
             };
 
             p.render(&ctx, &prev, &mut out);
@@ -100,6 +335,223 @@ fn every_preset_renders_non_black_frames() {
     }
 }
 
+#[test]
+fn safe_mode_damps_hat_triggered_flashes() {
+    let w = 96usize;
+    let h = 64usize;
+    let n = w * h * 4;
+    let render = |hat: f32, safe: bool| {
+        fastrand::seed(7);
+        let mut presets = make_presets();
+        let p = presets
+            .iter_mut()
+            .find(|p| p.name() == "Particle Fountain: Hi-hat Sparks")
+            .expect("hi-hat sparks preset");
+        let ctx = RenderCtx {
+            now: Instant::now(),
+            t: 1.0,
+            dt: 1.0 / 60.0,
+            w,
+            h,
+            audio: AudioFeatures {
+                hat,
+                ..AudioFeatures::default()
+            },
+            beat_pulse: 0.0,
+            fractal_zoom_mul: 1.0,
+            safe,
+            stereo: false,
+            quality: Quality::Balanced,
+            scale: 1,
+            spectrum: &[],
+            waveform: &[],
+            side: &[],
+        };
+        let prev = vec![0u8; n];
+        let mut out = vec![0u8; n];
+        p.render(&ctx, &prev, &mut out);
+        out
+    };
+
+    let flash = mean_abs_rgb_diff(&render(0.0, false), &render(1.0, false));
+    let safe_flash = mean_abs_rgb_diff(&render(0.0, true), &render(1.0, true));
+    assert!(flash > 0.0, "hat should light up the sparks");
+    assert!(safe_flash < flash, "safe flash {safe_flash} vs unsafe {flash}");
+}
+
+#[test]
+fn preset_files_resolve_and_append_renderable_presets() {
+    let def = PresetDefinition::parse(
+        "name=User: Hex Drive\nalgo=hex_tunnel\nalgo.freq=4.5\npalette=fire\nfeedback=none\npostfx.mix=0",
+    )
+    .expect("definition parses");
+    // Checking a definition builds nothing, so it leaves seeded runs alone.
+    fastrand::seed(7);
+    check_definition(&def).expect("definition resolves");
+    let after_check = fastrand::u32(..);
+    fastrand::seed(7);
+    assert_eq!(after_check, fastrand::u32(..));
+
+    let builtin = make_presets().len();
+    let mut presets = make_presets_with(std::slice::from_ref(&def));
+    assert_eq!(presets.len(), builtin + 1);
+    assert!(presets[..builtin].iter().any(|p| p.has_metal_shader()));
+    let user = presets.last_mut().expect("user preset appended");
+    assert_eq!(user.name(), "User: Hex Drive");
+    assert_eq!(user.id(), "user-hex-drive", "file presets use the slug of their name");
+    assert!(!user.has_metal_shader(), "Metal has no shader for file presets");
+    let meta = user.meta();
+    assert_eq!(meta.category, PresetCategory::Geometric, "category follows the algo");
+    assert_eq!((meta.energy, meta.flash_risk), (Level::Medium, Level::Low));
+
+    let (w, h) = (64usize, 48usize);
+    let prev = vec![0u8; w * h * 4];
+    let mut out = vec![0u8; w * h * 4];
+    let audio = synth_audio(0.4, 0);
+    let ctx = RenderCtx {
+        now: Instant::now(),
+        t: 0.4,
+        dt: 1.0 / 60.0,
+        w,
+        h,
+        audio,
+        beat_pulse: 0.5,
+        fractal_zoom_mul: 1.0,
+        safe: false,
+        stereo: false,
+        quality: Quality::Fast,
+        scale: 1,
+        spectrum: &[],
+        waveform: &[],
+        side: &[],
+    };
+    user.render(&ctx, &prev, &mut out);
+    assert!(has_non_black(&out), "user preset stayed fully black");
+
+    let broken = [
+        ("algo=lava_lamp", "unknown algo"),
+        ("algo=plasma\nalgo.symmetry=5", "no parameter 'symmetry'"),
+        ("algo=julia\nalgo.c_base=0.3", "two numbers"),
+        ("algo=voronoi\nalgo.points=0", "whole number"),
+        ("algo=plasma\ncategory=ambient", "unknown category"),
+        ("algo=plasma\nenergy=extreme", "unknown energy level"),
+    ];
+    for (lines, expect) in broken {
+        let def = PresetDefinition::parse(&format!("name=Broken\npalette=neon\n{lines}")).expect(lines);
+        let err = check_definition(&def).expect_err(lines);
+        assert!(err.contains(expect), "{lines}: {err}");
+        assert_eq!(make_presets_with(&[def]).len(), builtin);
+    }
+    let def = PresetDefinition::parse("name=Broken\npalette=teal\nalgo=plasma").expect("parses");
+    assert!(check_definition(&def).is_err());
+}
+
+#[test]
+fn bundled_preset_files_load_and_resolve() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/preset");
+    let mut count = 0;
+    for entry in std::fs::read_dir(&dir).expect("assets/preset exists") {
+        let path = entry.expect("dir entry").path();
+        let def = PresetDefinition::load(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        check_definition(&def).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        count += 1;
+    }
+    assert!(count > 0, "expected bundled .preset examples");
+}
+
+#[test]
+fn field_presets_pan_with_the_mix_only_in_stereo_split_mode() {
+    let w = 96usize;
+    let h = 64usize;
+    let n = w * h * 4;
+    let render = |balance: f32, stereo: bool| {
+        fastrand::seed(11);
+        let mut presets = make_presets();
+        let ctx = RenderCtx {
+            now: Instant::now(),
+            t: 1.0,
+            dt: 1.0 / 60.0,
+            w,
+            h,
+            audio: AudioFeatures {
+                balance,
+                stereo_width: balance.abs(),
+                ..synth_audio(1.0, 1)
+            },
+            beat_pulse: 0.0,
+            fractal_zoom_mul: 1.0,
+            safe: false,
+            stereo,
+            quality: Quality::Balanced,
+            scale: 1,
+            spectrum: &[],
+            waveform: &[],
+            side: &[],
+        };
+        let prev = vec![0u8; n];
+        let mut out = vec![0u8; n];
+        presets[0].render(&ctx, &prev, &mut out);
+        out
+    };
+
+    assert_eq!(render(0.0, false), render(0.9, false));
+    assert!(mean_abs_rgb_diff(&render(0.0, true), &render(0.9, true)) > 0.5);
+}
+
+#[test]
+fn scope_presets_draw_the_spectrum_and_waveform() {
+    let mut presets: Vec<Box<dyn Preset>> = make_presets();
+    let w = 96usize;
+    let h = 64usize;
+    let n = w * h * 4;
+    let spectrum: Vec<f32> = (0..32).map(|i| 0.9 - i as f32 * 0.02).collect();
+    let waveform: Vec<f32> = (0..512).map(|i| (i as f32 * 0.09).sin() * 0.6).collect();
+
+    let scopes = [
+        "Spectrum Bars",
+        "Spectrum Mirror",
+        "Spectrum Halo",
+        "Oscilloscope",
+        "Waveform Ring",
+        "Lissajous",
+    ];
+    let mut checked = 0;
+    for p in presets
+        .iter_mut()
+        .filter(|p| scopes.iter().any(|s| p.name().starts_with(s)))
+    {
+        let mut frames = Vec::new();
+        for (spectrum, waveform) in [(&[][..], &[][..]), (&spectrum[..], &waveform[..])] {
+            let ctx = RenderCtx {
+                now: Instant::now(),
+                t: 1.0,
+                dt: 1.0 / 60.0,
+                w,
+                h,
+                audio: synth_audio(1.0, 1),
+                beat_pulse: 0.0,
+                fractal_zoom_mul: 1.0,
+                safe: false,
+                stereo: false,
+                quality: Quality::Balanced,
+                scale: 1,
+                spectrum,
+                waveform,
+                side: &[],
+            };
+            let prev = vec![0u8; n];
+            let mut out = vec![0u8; n];
+            p.render(&ctx, &prev, &mut out);
+            frames.push(out);
+        }
+
+        let diff = mean_abs_rgb_diff(&frames[0], &frames[1]);
+        assert!(diff > 1.5, "preset '{}' barely reacts to audio data (diff {diff:.2})", p.name());
+        checked += 1;
+    }
+    assert_eq!(checked, 6, "expected six scope presets");
+}
+
 #[test]
 fn adaptive_auto_mode_switches_presets() {
     let presets = make_presets();
@@ -130,8 +582,12 @@ fn adaptive_auto_mode_switches_presets() {
             beat_pulse: if a.beat { 0.9 } else { 0.0 },
             fractal_zoom_mul: 1.0,
             safe: false,
+            stereo: false,
             quality: Quality::Fast,
             scale: 1,
+            spectrum: &[],
+            waveform: &[],
+            side: &[],
         };
         let _ = engine.render(ctx, Quality::Fast, 1);
         if engine.preset_name() != first {
@@ -143,6 +599,88 @@ fn adaptive_auto_mode_switches_presets() {
     assert!(changed, "adaptive auto-mode did not switch presets");
 }
 
+/// Flat section audio: `low` drives the two bass bands, `high` the top three.
+fn section_audio(low: f32, high: f32, rms: f32, onset: f32, beat: bool) -> AudioFeatures {
+    AudioFeatures {
+        rms,
+        bands: AudioFeatures::pad_bands(&[low, low, 0.4, 0.4, 0.3, high, high, high]),
+        onset,
+        beat,
+        beat_strength: if beat { 0.6 } else { 0.0 },
+        ..AudioFeatures::default()
+    }
+}
+
+#[test]
+fn auto_mode_holds_through_build_up_and_cuts_on_the_drop() {
+    let presets = make_presets();
+    let mut engine = PresetEngine::new(presets, 0, false, SwitchMode::Adaptive, 4, 8.0);
+    engine.resize(32, 24);
+    let mut now = Instant::now();
+    let mut t = 0.0f32;
+    let mut step = |engine: &mut PresetEngine, a: AudioFeatures| {
+        now += Duration::from_millis(50);
+        t += 0.05;
+        engine.update_auto_switch(now, &a);
+        let ctx = RenderCtx {
+            now,
+            t,
+            dt: 0.05,
+            w: 32,
+            h: 24,
+            audio: a,
+            beat_pulse: 0.0,
+            fractal_zoom_mul: 1.0,
+            safe: false,
+            stereo: false,
+            quality: Quality::Fast,
+            scale: 1,
+            spectrum: &[],
+            waveform: &[],
+            side: &[],
+        };
+        let _ = engine.render(ctx, Quality::Fast, 1);
+    };
+
+    // 30 s groove, then a 16 s breakdown whose energy and hats climb.
+    for f in 0..600 {
+        step(&mut engine, section_audio(0.7, 0.3, 0.5, 0.3, f % 10 == 0));
+    }
+    assert_eq!(engine.structure_phase_name(), None);
+    // A transition already running when the build-up is detected may finish
+    // (at most 2.6 s); nothing new may start after that.
+    let mut detected = None;
+    let mut held: Option<&str> = None;
+    for f in 0..320 {
+        let x = f as f32 / 320.0;
+        step(&mut engine, section_audio(0.05, 0.2 + 0.7 * x, 0.2 + 0.5 * x, 0.1 + 0.45 * x, false));
+        if detected.is_none() && engine.structure_phase_name() == Some("Build-up") {
+            detected = Some(f);
+        }
+        match held {
+            Some(name) => assert_eq!(engine.preset_name(), name, "preset switched during the build-up"),
+            None if detected.is_some_and(|d| f >= d + 60) => held = Some(engine.preset_name()),
+            None => {}
+        }
+    }
+    let held = held.expect("build-up was never detected");
+
+    let mut switched = false;
+    for f in 0..20 {
+        now += Duration::from_millis(50);
+        engine.update_auto_switch(now, &section_audio(0.9, 0.6, 0.8, 0.5, f % 10 == 0));
+        if engine.preset_name() != held || engine.transition_kind_name() != "Fade" {
+            let kind = engine.transition_kind_name();
+            assert!(kind == "Cut" || kind == "Flash Cut", "drop used a {kind} transition");
+            assert!(f < 6, "drop fired {f} frames late");
+            switched = true;
+            break;
+        }
+    }
+    assert!(switched, "no transition on the drop");
+    assert_eq!(engine.structure_phase_name(), None);
+}
+
 #[test]
 fn camera_path_api_surface_is_stable() {
     let presets = make_presets();
@@ -220,12 +758,13 @@ fn deep_zoom_fractal_motion_has_no_large_reset_spikes() {
         let t = f as f32 * (1.0 / 60.0);
         let audio = AudioFeatures {
             rms: 0.28,
-            bands: [0.33, 0.35, 0.31, 0.29, 0.27, 0.24, 0.22, 0.20],
+            bands: AudioFeatures::pad_bands(&[0.33, 0.35, 0.31, 0.29, 0.27, 0.24, 0.22, 0.20]),
             onset: 0.0,
             beat: false,
             beat_strength: 0.0,
             centroid: 0.32,
             flatness: 0.14,
+            ..AudioFeatures::default()
         };
         let ctx = RenderCtx {
             now: Instant::now(),
@@ -237,8 +776,12 @@ fn deep_zoom_fractal_motion_has_no_large_reset_spikes() {
             beat_pulse: 0.0,
             fractal_zoom_mul: 1.8,
             safe: false,
+            stereo: false,
             quality: Quality::Balanced,
             scale: 1,
+            spectrum: &[],
+            waveform: &[],
+            side: &[],
         };
 
         p.render(&ctx, &prev, &mut out);